@@ -6,22 +6,21 @@ use std::marker::PhantomData;
 use std::ptr;
 
 use minutiae::server::*;
-use minutiae::util::Color;
 use uuid::Uuid;
 
 use super::{Client, ClientState, GenClient, Tys, debug};
 
 pub struct ThinClient<T: Tys> {
     pub universe: Vec<[u8; 4]>,
-    pub state: ClientState<Vec<Color>, ThinServerMessage>,
+    pub state: ClientState<ColorSnapshot, ThinServerMessage>,
     __phantom_t: PhantomData<T>,
 }
 
 impl<T: Tys> ThinClient<T> {
-    pub fn new(universe_size: usize) -> Self {
+    pub fn new(width: usize, height: usize) -> Self {
         ThinClient {
             // TODO: Investigate if this is necessary
-            universe: vec![[0u8, 0u8, 0u8, 255u8]; universe_size * universe_size * 4],
+            universe: vec![[0u8, 0u8, 0u8, 255u8]; width * height * 4],
             state: ClientState::new(),
             __phantom_t: PhantomData,
         }
@@ -35,10 +34,14 @@ impl<T: Tys> ThinClient<T> {
         };
     }
 
-    fn apply_snap_inner(&mut self, snap: Vec<Color>) {
+    fn apply_snap_inner(&mut self, snap: ColorSnapshot) {
         debug("Received snapshot from server... attempting to apply it.");
-        debug_assert_eq!(self.universe.len(), snap.len() / 4);
-        for (i, color) in snap.iter().enumerate() {
+        // resize our image of the universe if the server's universe has different dimensions
+        let universe_len = snap.width as usize * snap.height as usize;
+        if self.universe.len() != universe_len {
+            self.universe.resize(universe_len, [0u8, 0u8, 0u8, 255u8]);
+        }
+        for (i, color) in snap.colors.iter().enumerate() {
             self.apply_diff(Diff {universe_index: i, color: *color});
         }
     }
@@ -46,7 +49,7 @@ impl<T: Tys> ThinClient<T> {
 
 impl<
     T: Tys<
-        Snapshot=Vec<Color>,
+        Snapshot=ColorSnapshot,
         ServerMessage=ThinServerMessage
     > + 'static
 > Client<T> for ThinClient<T> where
@@ -68,14 +71,14 @@ impl<
         self.apply_snap_inner(snap);
     }
 
-    fn get_state(&mut self) -> &mut ClientState<Vec<Color>, ThinServerMessage> {
+    fn get_state(&mut self) -> &mut ClientState<ColorSnapshot, ThinServerMessage> {
         &mut self.state
     }
 }
 
 impl<
     T: Tys<
-        Snapshot=Vec<Color>,
+        Snapshot=ColorSnapshot,
         ServerMessage=ThinServerMessage
     > + 'static
 > GenClient for ThinClient<T> where T::ServerMessage: ServerMessage<T::Snapshot> {
@@ -39,7 +39,7 @@ fn colony_event_generator(
 
 pub fn main() {
     let mut wrapper: CellGeneratorWrapper<_, _, _, _, WorldGenerator> = CellGeneratorWrapper::new();
-    let universe = Universe2D::new(Universe2DConf { size: 800 }, &mut wrapper);
+    let universe = Universe2D::new(Universe2DConf::square(800), &mut wrapper);
     let driver = BasicDriver;
 
     let (wrapped_action_executor, server_logic) = HybridServer::<CustomClientMessage, ColonyTys>::hook_handler(
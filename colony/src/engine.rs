@@ -1,10 +1,8 @@
 use std::fmt::Debug;
 
 use minutiae::prelude::*;
-use minutiae::engine::parallel::ParallelEngine;
+use minutiae::engine::parallel::{EntityDriver, ParallelEngine};
 use minutiae::server::Tys;
-use minutiae::universe::{CellContainer, ContiguousUniverse};
-use uuid::Uuid;
 
 use entity_driver::our_entity_driver;
 use super::*;
@@ -32,8 +30,6 @@ pub fn get_engine<'u>() -> impl Engine<
         MES,
         CA,
         EA,
-        usize,
-        Vec<Cell<CS>>,
         Universe2D<CS, ES, MES>,
         _
     > = ParallelEngine::new(exec_actions, our_entity_driver);
@@ -43,13 +39,12 @@ pub fn get_engine<'u>() -> impl Engine<
 
 pub fn get_custom_engine<
     CS: CellState + Send + Debug + 'static,
-    ES: EntityState<CS> + Send + Debug,
-    MES: MutEntityState + Send,
-    CA: CellAction<CS> + Send + Debug,
-    EA: EntityAction<CS, ES> + Send + Debug,
-    I: Ord + Copy + Send + 'static,
-    CC: CellContainer<CS, I> + Send + 'static,
-    U: Universe<CS, ES, MES, Coord=I> + ContiguousUniverse<CS, ES, MES, I, CC>,
+    ES: EntityState<CS> + Send + Debug + 'static,
+    MES: MutEntityState + Send + 'static,
+    CA: CellAction<CS> + Send + Debug + 'static,
+    EA: EntityAction<CS, ES> + Send + Debug + 'static,
+    I: Copy + Send + 'static,
+    U: Universe<CS, ES, MES, Coord=I> + Sync + 'static,
     F: Fn(
         &mut U,
         &[OwnedAction<CS, ES, CA, EA, I>],
@@ -58,18 +53,10 @@ pub fn get_custom_engine<
     ),
 >(
     exec_actions: F,
-    entity_driver: fn(
-        source_universe_index: I,
-        entity: &Entity<CS, ES, MES>,
-        entities: &EntityContainer<CS, ES, MES, I>,
-        cells: &[Cell<CS>],
-        cell_action_executor: &mut FnMut(CA, I),
-        self_action_executor: &mut FnMut(SelfAction<CS, ES, EA>),
-        entity_action_executor: &mut FnMut(EA, usize, Uuid)
-    )
+    entity_driver: EntityDriver<CS, ES, MES, CA, EA, U>
 ) -> impl Engine<CS, ES, MES, CA, EA, U> {
     let engine: ParallelEngine<
-        CS, ES, MES, CA, EA, I, CC, U, F
+        CS, ES, MES, CA, EA, U, F
     > = ParallelEngine::new(exec_actions, entity_driver);
 
     box engine
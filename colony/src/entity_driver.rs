@@ -1,16 +1,15 @@
 use minutiae::prelude::*;
-use uuid::Uuid;
+use minutiae::mailbox::Recipient;
+use minutiae::view::EntityView;
 
 use super::*;
 
 pub fn our_entity_driver(
-    source_universe_index: usize,
-    entity: &Entity<CS, ES, MES>,
-    entities: &EntityContainer<CS, ES, MES, usize>,
-    cells: &[Cell<CS>],
+    view: &EntityView<CS, ES, MES, Universe2D<CS, ES, MES>>,
     cell_action_executor: &mut FnMut(CA, usize),
     self_action_executor: &mut FnMut(SelfAction<CS, ES, EA>),
-    entity_action_executor: &mut FnMut(EA, usize, Uuid)
+    entity_action_executor: &mut FnMut(EA, EntityHandle),
+    _: &mut FnMut(Recipient, ())
 ) {
 
 }
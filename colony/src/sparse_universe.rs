@@ -15,8 +15,6 @@ use minutiae::util::get_coords;
 #[cfg(test)]
 use test;
 
-use super::UNIVERSE_SIZE;
-
 /// A world generator that can generate the initial values for arbitrary cells on demand without
 /// needing to generate surrounding cells.
 pub trait CellGenerator<
@@ -71,13 +69,14 @@ impl<
     CG: CellGenerator<CS, ES, MES, I>,
 > Generator<CS, ES, MES> for CellGeneratorWrapper<CS, ES, MES, I, CG> {
     fn gen(&mut self, conf: &Universe2DConf) -> (Vec<Cell<CS>>, Vec<Vec<Entity<CS, ES, MES>>>) {
-        let cells = (0..(conf.size * conf.size) as usize)
-            .map(|universe_index: usize| -> I { I::from_2d_index(UNIVERSE_SIZE, universe_index) })
+        let width = conf.width as usize;
+        let cells = (0..conf.len())
+            .map(|universe_index: usize| -> I { I::from_2d_index(width, universe_index) })
             .map(|coord| CG::gen_cell(coord))
             .collect();
 
-        let entities = (0..(conf.size * conf.size) as usize)
-            .map(|universe_index: usize| -> I { I::from_2d_index(UNIVERSE_SIZE, universe_index) })
+        let entities = (0..conf.len())
+            .map(|universe_index: usize| -> I { I::from_2d_index(width, universe_index) })
             .map(|coord| CG::gen_initial_entities(coord))
             .collect();
 
@@ -121,8 +120,12 @@ impl PartialOrd for P2D {
 }
 
 impl Into2DIndex for P2D {
-    fn into_2d_index(self, universe_size: usize) -> usize {
-        self.get_index(universe_size) as usize
+    fn into_2d_index(self, universe_size: usize) -> Option<usize> {
+        if self.x >= universe_size {
+            return None;
+        }
+
+        Some(self.get_index(universe_size))
     }
 
     fn from_2d_index(universe_size: usize, universe_index: usize) -> Self {
@@ -11,11 +11,11 @@ fn exec_cell_action(action: &OwnedAction<CS, ES, CA, EA>, cells: &mut[Cell<CS>],
 fn exec_self_action(action: &OwnedAction<CS, ES, CA, EA>, entities: &mut EntityContainer<CS, ES, MES>) {
     match action.action {
         Action::SelfAction(ref self_action) => {
-            let (entity_index, entity_uuid) = (action.source_entity_index, action.source_uuid);
+            let entity_handle = action.source_entity;
             match *self_action {
                 SelfAction::Translate(x_offset, y_offset) => {
                     // this function will return early if the entity has been deleted
-                    let universe_index = match entities.get_verify(entity_index, entity_uuid) {
+                    let universe_index = match entities.get_verify(entity_handle) {
                         Some((_, universe_index)) => universe_index,
                         None => { return; }, // entity has been deleted, so do nothing.
                     };
@@ -24,17 +24,18 @@ fn exec_self_action(action: &OwnedAction<CS, ES, CA, EA>, entities: &mut EntityC
                     let (cur_x, cur_y) = get_coords(universe_index, UNIVERSE_SIZE);
                     let new_x = cur_x as isize + x_offset;
                     let new_y = cur_y as isize + y_offset;
-                    let dst_universe_index = get_index(new_x as usize, new_y as usize, UNIVERSE_SIZE);
-
-                    // make sure there are no entities where we're trying to move
-                    if entities.get_entities_at(dst_universe_index).len() > 0 {
-                        return;
-                    }
 
                     // verify that the supplied desination coordinates are in bounds
                     // TODO: verify that the supplied destination coordinates are within ruled bounds of destination
                     if new_x >= 0 && new_x < UNIVERSE_SIZE as isize && new_y >= 0 && new_y < UNIVERSE_SIZE as isize {
-                        entities.move_entity(entity_index, dst_universe_index);
+                        let dst_universe_index = get_index(new_x as usize, new_y as usize, UNIVERSE_SIZE);
+
+                        // make sure there are no entities where we're trying to move
+                        if entities.get_entities_at(dst_universe_index).len() > 0 {
+                            return;
+                        }
+
+                        entities.move_entity(entity_handle, dst_universe_index);
                     }
                 },
                 _ => unimplemented!(),
@@ -48,21 +49,30 @@ fn exec_entity_action(action: &OwnedAction<CS, ES, CA, EA>) {
     unimplemented!();
 }
 
-impl SerialEngine<CS, ES, MES, CA, EA, SerialGridIterator, SerialEntityIterator<CS, ES>> for DancerEngine {
-    fn iter_cells(&self, cells: &[Cell<CS>]) -> SerialGridIterator {
-        SerialGridIterator::new(UNIVERSE_SIZE)
-    }
-
-    fn iter_entities(&self, entities: &[Vec<Entity<CS, ES, MES>>]) -> SerialEntityIterator<CS, ES> {
-        SerialEntityIterator::new(UNIVERSE_SIZE)
+impl SerialEngine<CS, ES, MES, CA, EA, SerialEntityIterator<CS, ES>, Universe2D<CS, ES, MES>> for DancerEngine {
+    fn iter_entities(&self, _: &Universe2D<CS, ES, MES>) -> SerialEntityIterator<CS, ES> {
+        SerialEntityIterator::new(UNIVERSE_SIZE * UNIVERSE_SIZE)
     }
 
     fn exec_actions(
-        &self, universe: &mut Universe<CS, ES, MES, CA, EA>, cell_actions: &[OwnedAction<CS, ES, CA, EA>],
+        &self, universe: &mut Universe2D<CS, ES, MES>, cell_actions: &[OwnedAction<CS, ES, CA, EA>],
         self_actions: &[OwnedAction<CS, ES, CA, EA>], entity_actions: &[OwnedAction<CS, ES, CA, EA>]
     ) {
         for cell_action in cell_actions { exec_cell_action(cell_action, &mut universe.cells, &mut universe.entities); }
         for self_action in self_actions { exec_self_action(self_action, &mut universe.entities); }
         for entity_action in entity_actions { exec_entity_action(entity_action); }
     }
+
+    fn view_distance(&self) -> usize { VIEW_DISTANCE }
+
+    fn drive_entity(
+        &mut self,
+        view: &EntityView<CS, ES, MES, Universe2D<CS, ES, MES>>,
+        cell_action_executor: &mut FnMut(CA, usize),
+        self_action_executor: &mut FnMut(SelfAction<CS, ES, EA>),
+        entity_action_executor: &mut FnMut(EA, EntityHandle),
+        _: &mut FnMut(Recipient, ())
+    ) {
+        entity_driver(view, cell_action_executor, self_action_executor, entity_action_executor);
+    }
 }
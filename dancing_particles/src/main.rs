@@ -17,14 +17,17 @@ use std::os::raw::c_void;
 
 use minutiae::prelude::*;
 use minutiae::engine::serial::SerialEngine;
-use minutiae::engine::iterator::{SerialEntityIterator, SerialGridIterator};
+use minutiae::engine::iterator::SerialEntityIterator;
 use minutiae::emscripten::{EmscriptenDriver, CanvasRenderer};
 use minutiae::driver::middleware::MinDelay;
+use minutiae::mailbox::Recipient;
+use minutiae::neighborhood::Moore;
+use minutiae::universe::Universe2D;
+use minutiae::view::EntityView;
 use noise::*;
 use palette::{FromColor, Hsv, Rgb};
 use pcg::PcgRng;
 use rand::Rng;
-use uuid::Uuid;
 
 mod engine;
 use engine::DancerEngine;
@@ -76,11 +79,8 @@ impl EntityAction<CS, ES> for EA {}
 
 impl EntityState<CS> for ES {}
 
-// dummy function until `cell_mutator` is deprecated entirely
-pub fn cell_mutator(_: usize, _: &[Cell<CS>]) -> Option<CS> { None }
-
 struct WG;
-impl Generator<CS, ES, MES, CA, EA> for WG {
+impl Generator<CS, ES, MES> for WG {
     fn gen(&mut self, conf: &UniverseConf) -> (Vec<Cell<CS>>, Vec<Vec<Entity<CS, ES, MES>>>) {
         let base_cell = Cell{
             state: CS {
@@ -88,15 +88,15 @@ impl Generator<CS, ES, MES, CA, EA> for WG {
                 noise_val_2: 0.0
             }
         };
-        let cells = vec![base_cell; conf.size * conf.size];
+        let cells = vec![base_cell; conf.len()];
 
         let mut rng = PcgRng::new_unseeded();
 
-        let mut entities = vec![Vec::new(); UNIVERSE_SIZE * UNIVERSE_SIZE];
+        let mut entities = vec![Vec::new(); conf.len()];
         // randomly distribute some starter entities into the universe.
         let mut spawned_particles = 0;
         while spawned_particles < PARTICLE_COUNT {
-            let index: usize = rng.gen_range(0, UNIVERSE_SIZE * UNIVERSE_SIZE);
+            let index: usize = rng.gen_range(0, conf.len());
             if entities[index].len() == 0 {
                 entities[index].push(Entity::new(ES {secondary: rng.gen()}, MES {}));
                 spawned_particles += 1;
@@ -108,48 +108,41 @@ impl Generator<CS, ES, MES, CA, EA> for WG {
 }
 
 fn entity_driver(
-    universe_index: usize,
-    entity: &Entity<CS, ES, MES>,
-    entities: &EntityContainer<CS, ES, MES>,
-    cells: &[Cell<CS>],
+    view: &EntityView<CS, ES, MES, Universe2D<CS, ES, MES>>,
     cell_action_executor: &mut FnMut(CA, usize),
     self_action_executor: &mut FnMut(SelfAction<CS, ES, EA>),
-    entity_action_executor: &mut FnMut(EA, usize, Uuid)
+    entity_action_executor: &mut FnMut(EA, EntityHandle)
 ) {
-    let (cur_x, cur_y) = get_coords(universe_index, UNIVERSE_SIZE);
+    let secondary = view.entity().state.secondary;
     // look around us and find the cell with the highest value for the dimension we're following and move towards it.
-    let mut best_coord: (usize, f32) = (universe_index, -100000000000.0);
-    for (x, y) in iter_visible(cur_x, cur_y, VIEW_DISTANCE, UNIVERSE_SIZE) {
-        let index = get_index(x, y, UNIVERSE_SIZE);
-        let val = if !entity.state.secondary { cells[index].state.noise_val_1 } else { cells[index].state.noise_val_2 };
+    let mut best_coord: ((isize, isize), f32) = ((0, 0), -100000000000.0);
+    for ((x_offset, y_offset), cell) in view.iter_neighborhood(&Moore(VIEW_DISTANCE)) {
+        let val = if !secondary { cell.state.noise_val_1 } else { cell.state.noise_val_2 };
         if val > best_coord.1 {
-            best_coord = (index, val);
+            best_coord = ((x_offset, y_offset), val);
         }
     }
 
-    if best_coord.0 != universe_index {
-        let (cell_x, cell_y) = get_coords(best_coord.0, UNIVERSE_SIZE);
-        let our_x_offset = if cur_x > cell_x { -1 } else if cur_x == cell_x { 0 } else { 1 };
-        let our_y_offset = if cur_y > cell_y { -1 } else if cur_y == cell_y { 0 } else { 1 };
+    let (x_offset, y_offset) = best_coord.0;
+    if (x_offset, y_offset) != (0, 0) {
+        let our_x_offset = if x_offset < 0 { -1 } else if x_offset == 0 { 0 } else { 1 };
+        let our_y_offset = if y_offset < 0 { -1 } else if y_offset == 0 { 0 } else { 1 };
         let self_action = SelfAction::Translate(our_x_offset, our_y_offset);
         return self_action_executor(self_action);
     }
 }
 
-fn get_color(cell: &Cell<CS>, entity_indexes: &[usize], entity_container: &EntityContainer<CS, ES, MES>) -> [u8; 4] {
-    if entity_indexes.len() == 0 { cell.state.get_color() } else {
-        if ! unsafe { entity_container.get(entity_indexes[0]).state.secondary } { [255, 0, 0, 255] } else { [0, 255, 0, 255] }
+fn get_color(cell: &Cell<CS>, entity_handles: &[EntityHandle], entity_container: &EntityContainer<CS, ES, MES>) -> [u8; 4] {
+    match entity_handles.first().and_then(|&handle| entity_container.get(handle)) {
+        None => cell.state.get_color(),
+        Some(entity) => if !entity.state.secondary { [255, 0, 0, 255] } else { [0, 255, 0, 255] },
     }
 }
 
 fn main() {
-    let conf = UniverseConf {
-        iter_cells: false,
-        size: UNIVERSE_SIZE,
-        view_distance: 1,
-    };
-    let universe = Universe::new(conf, &mut WG, cell_mutator, entity_driver);
-    let engine: Box<SerialEngine<CS, ES, MES, CA, EA, SerialGridIterator, SerialEntityIterator<CS, ES>>> = Box::new(DancerEngine);
+    let conf = UniverseConf::square(UNIVERSE_SIZE as u32);
+    let universe = Universe2D::new(conf, &mut WG);
+    let engine: Box<SerialEngine<CS, ES, MES, CA, EA, SerialEntityIterator<CS, ES>, Universe2D<CS, ES, MES>>> = Box::new(DancerEngine);
     let driver = EmscriptenDriver;
 
     let noise_module: SuperSimplex = SuperSimplex::new();
@@ -169,6 +162,6 @@ fn main() {
     driver.init(universe, engine, &mut [
         Box::new(MinDelay::from_tps(59.99)),
         Box::new(noise_middleware),
-        Box::new(CanvasRenderer::new(UNIVERSE_SIZE, get_color, canvas_render))
+        Box::new(CanvasRenderer::new(UNIVERSE_SIZE, UNIVERSE_SIZE, get_color, canvas_render))
     ]);
 }
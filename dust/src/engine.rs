@@ -1,17 +1,12 @@
 //! Defines the behavior of the engine that regulates the bahavior of the universe as a whole.
 
-use minutiae::action::Action;
-use num::clamp;
-use uuid::Uuid;
+use minutiae::engine::executor::{self, ActionHandler};
 
 use super::*;
 
 pub struct DancerEngine;
 
-fn exec_cell_action(action: &OwnedAction<CS, ES, CA, EA>, cells: &mut[Cell<CS>], entities: &mut EntityContainer<CS, ES, MES>) {
-    // unimplemented!();
-    println!("CELL ACTION??? {:?}", action);
-}
+type OurUniverse = Universe2D<CS, ES, MES>;
 
 fn calc_next_position(coord: usize, offset: f32, velocity: f32) -> (usize, f32) {
     let next_fp = (coord as f32) + offset + velocity;
@@ -25,128 +20,131 @@ fn calc_next_position(coord: usize, offset: f32, velocity: f32) -> (usize, f32)
     (next_fp.trunc() as usize, next_fp.fract())
 }
 
-fn exec_self_action(action: &OwnedAction<CS, ES, CA, EA>, entities: &mut EntityContainer<CS, ES, MES>) {
-    match action.action {
-        Action::SelfAction(ref sa) => {
-            let (entity_index, entity_uuid) = (action.source_entity_index, action.source_uuid);
-
-            match *sa {
-                SelfAction::Translate(x_offset, y_offset) => {
-                    // make sure there are no entities where we're trying to move, and if there are set our velocity to 0
-                    if entities.get_entities_at(dst_universe_index).len() > 0 {
-                        let us = entities.get_verify_mut(entity_index, entity_uuid).unwrap().0;
-                        match us.state {
-                            ES::Dust { ref mut velocity, .. } => {
-                                velocity.x = 0.0;
-                                velocity.y = 0.0;
-                            },
-                            _ => unreachable!(),
-                        }
-
-                        return;
-                    }
-
-                    translate_entity(x_offset, y_offset, entities, entity_index, entity_uuid, UNIVERSE_SIZE);
-                },
-                SelfAction::Custom(ref ea) => {
-                    match ea {
-                        &EA::UpdateVelocities { x: new_velocity_x, y: new_velocity_y } => {
-                            let (x_offset, y_offset) = {
-                                let (mut entity, universe_index) = match entities.get_verify_mut (entity_index, entity_uuid) {
-                                    Some(d) => d,
-                                    None => { return; }, // entity has been deleted, so do nothing.
-                                };
-
-                                let velocity: &mut Velocity = match entity.state {
-                                    ES::Dust { ref mut velocity, .. } => velocity,
-                                    _ => panic!("Tried to dispatch an `UpdateVelocities` action on a non-dust particle!"),
-                                };
-
-                                // update the velocity with the new velocity vector
-                                velocity.x += new_velocity_x;
-                                velocity.y += new_velocity_y;
-
-                                let (cur_x, cur_y) = get_coords(universe_index, UNIVERSE_SIZE);
-
-                                let &mut Velocity { x, y, x_offset, y_offset } = velocity;
-                                let (next_x, next_x_offset) = calc_next_position(cur_x, x_offset, x);
-                                velocity.x_offset = next_x_offset;
-                                let (next_y, next_y_offset) = calc_next_position(cur_y, y_offset, y);
-                                velocity.y_offset = next_y_offset;
-
-                                (next_x as isize - cur_x as isize, next_y as isize - cur_y as isize)
-                            };
-
-                            translate(x_offset, y_offset, entities, entity_index, entity_uuid);
-                        },
-                        _ => unreachable!(),
-                    }
-                },
-                _ => {
-                    println!("OTHER SELF ACTION TYPE UNHANDLED");
-                    unimplemented!()
-                },
-            }
-        },
-        _ => unreachable!(),
+/// Moves a dust particle by the supplied offset.  If there are already entities where it's trying to move, it stays
+/// where it is and its velocity is set to 0 instead.
+fn translate_dust(universe: &mut OurUniverse, entity_handle: EntityHandle, x_offset: isize, y_offset: isize) {
+    let universe_index = match universe.entities.get_verify(entity_handle) {
+        Some((_, universe_index)) => universe_index,
+        None => { return; }, // entity has been deleted, so do nothing.
+    };
+    if x_offset == 0 && y_offset == 0 {
+        return;
+    }
+
+    let (cur_x, cur_y) = get_coords(universe_index, UNIVERSE_SIZE);
+    let dst_universe_index = get_index(
+        (cur_x as isize + x_offset) as usize,
+        (cur_y as isize + y_offset) as usize,
+        UNIVERSE_SIZE
+    );
+
+    // make sure there are no entities where we're trying to move, and if there are set our velocity to 0
+    if universe.entities.get_entities_at(dst_universe_index).len() > 0 {
+        match universe.entities.get_mut(entity_handle).unwrap().state {
+            ES::Dust { ref mut velocity, .. } => {
+                velocity.x = 0.0;
+                velocity.y = 0.0;
+            },
+            _ => unreachable!(),
+        }
+
+        return;
     }
+
+    universe.entities.move_entity(entity_handle, dst_universe_index);
 }
 
-fn exec_entity_action(action: &OwnedAction<CS, ES, CA, EA>, entities: &mut EntityContainer<CS, ES, MES>) {
-    match action.action {
-        Action::EntityAction{ ref action, target_entity_index, target_uuid } => {
-            match action {
-                &EA::InvertShade => {
-                    let (entity, _) = match entities.get_verify_mut(target_entity_index, target_uuid) {
-                        Some(entity) => entity,
-                        None => {
-                            return; // Entity has been deleted or moved.
-                        },
+struct DancerActionHandler;
+
+impl ActionHandler<CS, ES, MES, CA, EA> for DancerActionHandler {
+    fn exec_cell_action(&mut self, _: &mut OurUniverse, _: EntityHandle, action: &CA, _: usize) {
+        match *action {}
+    }
+
+    fn exec_custom_self_action(&mut self, universe: &mut OurUniverse, source: EntityHandle, action: &EA) {
+        match action {
+            &EA::UpdateVelocities { x: new_velocity_x, y: new_velocity_y } => {
+                let (x_offset, y_offset) = {
+                    let (entity, universe_index) = match universe.entities.get_verify_mut(source) {
+                        Some(d) => d,
+                        None => { return; }, // entity has been deleted, so do nothing.
+                    };
+
+                    let velocity: &mut Velocity = match entity.state {
+                        ES::Dust { ref mut velocity, .. } => velocity,
+                        _ => panic!("Tried to dispatch an `UpdateVelocities` action on a non-dust particle!"),
                     };
 
-                    match entity.state {
-                        ES::Dust { ref mut shade, .. } => *shade *= -1.0, // invert the shade,
-                        _ => (),
-                    }
-                },
-                _ => unreachable!(),
-            }
-        },
-        _ => unreachable!(),
+                    // update the velocity with the new velocity vector
+                    velocity.x += new_velocity_x;
+                    velocity.y += new_velocity_y;
+
+                    let (cur_x, cur_y) = get_coords(universe_index, UNIVERSE_SIZE);
+
+                    let &mut Velocity { x, y, x_offset, y_offset } = velocity;
+                    let (next_x, next_x_offset) = calc_next_position(cur_x, x_offset, x);
+                    velocity.x_offset = next_x_offset;
+                    let (next_y, next_y_offset) = calc_next_position(cur_y, y_offset, y);
+                    velocity.y_offset = next_y_offset;
+
+                    (next_x as isize - cur_x as isize, next_y as isize - cur_y as isize)
+                };
+
+                translate_dust(universe, source, x_offset, y_offset);
+            },
+            _ => unreachable!(),
+        }
     }
-}
 
-pub fn exec_actions(
-    universe: &mut Universe<CS, ES, MES, CA, EA>, cell_actions: &[OwnedAction<CS, ES, CA, EA>],
-    self_actions: &[OwnedAction<CS, ES, CA, EA>], entity_actions: &[OwnedAction<CS, ES, CA, EA>]
-) {
-    for cell_action in cell_actions { exec_cell_action(cell_action, &mut universe.cells, &mut universe.entities); }
-    for self_action in self_actions { exec_self_action(self_action, &mut universe.entities); }
-    for entity_action in entity_actions { exec_entity_action(entity_action, &mut universe.entities); }
+    fn exec_entity_action(&mut self, universe: &mut OurUniverse, _: EntityHandle, action: &EA, target: EntityHandle) {
+        match action {
+            &EA::InvertShade => {
+                let entity = match universe.entities.get_mut(target) {
+                    Some(entity) => entity,
+                    None => {
+                        return; // Entity has been deleted.
+                    },
+                };
+
+                match entity.state {
+                    ES::Dust { ref mut shade, .. } => *shade *= -1.0, // invert the shade,
+                    _ => (),
+                }
+            },
+            _ => unreachable!(),
+        }
+    }
 }
 
 pub fn exec_actions(
-    universe: &mut Universe<CS, ES, MES, CA, EA>, cell_actions: &[OwnedAction<CS, ES, CA, EA>],
+    universe: &mut OurUniverse, cell_actions: &[OwnedAction<CS, ES, CA, EA>],
     self_actions: &[OwnedAction<CS, ES, CA, EA>], entity_actions: &[OwnedAction<CS, ES, CA, EA>]
 ) {
-    for cell_action in cell_actions { exec_cell_action(cell_action, &mut universe.cells, &mut universe.entities); }
-    for self_action in self_actions { exec_self_action(self_action, &mut universe.entities); }
-    for entity_action in entity_actions { exec_entity_action(entity_action); }
+    executor::exec_actions(&mut DancerActionHandler, universe, None, cell_actions, self_actions, entity_actions);
 }
 
-impl SerialEngine<CS, ES, MES, CA, EA, SerialGridIterator, SerialEntityIterator<CS, ES>> for DancerEngine {
-    fn iter_cells(&self, cells: &[Cell<CS>]) -> SerialGridIterator {
-        SerialGridIterator::new(UNIVERSE_SIZE)
-    }
-
-    fn iter_entities(&self, entities: &[Vec<Entity<CS, ES, MES>>]) -> SerialEntityIterator<CS, ES> {
-        SerialEntityIterator::new(UNIVERSE_SIZE)
+impl SerialEngine<CS, ES, MES, CA, EA, SerialEntityIterator<CS, ES>, OurUniverse> for DancerEngine {
+    fn iter_entities(&self, _: &OurUniverse) -> SerialEntityIterator<CS, ES> {
+        SerialEntityIterator::new(UNIVERSE_SIZE * UNIVERSE_SIZE)
     }
 
     fn exec_actions(
-        &self, universe: &mut Universe<CS, ES, MES, CA, EA>, cell_actions: &[OwnedAction<CS, ES, CA, EA>],
+        &self, universe: &mut OurUniverse, cell_actions: &[OwnedAction<CS, ES, CA, EA>],
         self_actions: &[OwnedAction<CS, ES, CA, EA>], entity_actions: &[OwnedAction<CS, ES, CA, EA>]
     ) {
         exec_actions(universe, cell_actions, self_actions, entity_actions);
     }
+
+    fn view_distance(&self) -> usize { VIEW_DISTANCE }
+
+    fn drive_entity(
+        &mut self,
+        view: &EntityView<CS, ES, MES, OurUniverse>,
+        cell_action_executor: &mut FnMut(CA, usize),
+        self_action_executor: &mut FnMut(SelfAction<CS, ES, EA>),
+        entity_action_executor: &mut FnMut(EA, EntityHandle),
+        message_executor: &mut FnMut(Recipient, ())
+    ) {
+        entity_driver(view, cell_action_executor, self_action_executor, entity_action_executor, message_executor);
+    }
 }
@@ -1,122 +1,61 @@
 //! Defines the behavior of the various types of entities in the universe
 
-use minutiae::util::iter_visible;
-
 use super::*;
 
 pub fn entity_driver(
-    universe_index: usize,
-    entity: &Entity<CS, ES, MES>,
-    entities: &EntityContainer<CS, ES, MES>,
-    cells: &[Cell<CS>],
+    view: &EntityView<CS, ES, MES, Universe2D<CS, ES, MES>>,
     cell_action_executor: &mut FnMut(CA, usize),
     self_action_executor: &mut FnMut(SelfAction<CS, ES, EA>),
-    entity_action_executor: &mut FnMut(EA, usize, Uuid)
+    entity_action_executor: &mut FnMut(EA, EntityHandle),
+    _: &mut FnMut(Recipient, ())
 ) {
-    let (cur_x, cur_y) = get_coords(universe_index, UNIVERSE_SIZE);
+    let entity = view.entity();
 
     match &entity.state {
         &ES::Builder => {
+            // entities can't modify their mutable state from within the driver, so the directions are shifted into a
+            // copy of it that's discarded at the end of the tick.
+            let mut mes: MES = entity.mut_state;
             let mut checked: [bool; 4] = [false; 4];
             let mut checked_count: u8 = 0;
 
-            let mut mutate_entity = |es: &ES, target_entity_index: usize| {
-                match es {
-                    &ES::Dust{ shade, .. } => {
-                        // dispatch an action to invert this entity's shade
-                        entity_action_executor(EA::InvertShade, target_entity_index, entity.uuid);
-                    },
-                    _ => (),
-                }
-            };
-
-            // check if we're adjascent to any entities
-            fn check_left(
-                entity: &Entity<CS, ES, MES>, entities: &EntityContainer<CS, ES, MES>,
-                checked: &mut [bool; 4], universe_index: usize, mutate_entity: &mut FnMut(&ES, usize)
-            ) {
-                let old: MES = entity.mut_state.get();
-                entity.mut_state.set(old.shift_replace(0));
-
-                let target_universe_index = universe_index - 1;
-                let found_entities = entities.get_entities_at(target_universe_index);
-
-                if universe_index > 0 && found_entities.len() > 0 {
-                    mutate_entity(unsafe { &entities.get(found_entities[0]).state }, found_entities[0]);
-                }
-            };
-
-            fn check_right(
-                entity: &Entity<CS, ES, MES>, entities: &EntityContainer<CS, ES, MES>,
-                checked: &mut [bool; 4], universe_index: usize, mutate_entity: &mut FnMut(&ES, usize)
-            ) {
-                let old: MES = entity.mut_state.get();
-                entity.mut_state.set(old.shift_replace(3));
-
-                if universe_index < (UNIVERSE_SIZE * UNIVERSE_SIZE) {
-                    let target_universe_index = universe_index + 1;
-                    let found_entities = entities.get_entities_at(target_universe_index);
-
-                    if found_entities.len() > 0 {
-                        mutate_entity(unsafe { &entities.get(found_entities[0]).state }, found_entities[0]);
-                    }
-                }
-            };
-
-            fn check_above(
-                entity: &Entity<CS, ES, MES>, entities: &EntityContainer<CS, ES, MES>,
-                checked: &mut [bool; 4], universe_index: usize, mutate_entity: &mut FnMut(&ES, usize)
-            ) {
-                let old: MES = entity.mut_state.get();
-                entity.mut_state.set(old.shift_replace(1));
-
-                if universe_index > UNIVERSE_SIZE {
-                    let target_universe_index = universe_index - UNIVERSE_SIZE;
-                    let found_entities = entities.get_entities_at(target_universe_index);
-
-                    if found_entities.len() > 0 {
-                        mutate_entity(unsafe { &entities.get(found_entities[0]).state }, found_entities[0]);
-                    }
-                }
-            };
-
-            fn check_below(
-                entity: &Entity<CS, ES, MES>, entities: &EntityContainer<CS, ES, MES>,
-                checked: &mut [bool; 4], universe_index: usize, mutate_entity: &mut FnMut(&ES, usize)
-            ) {
-                let old: MES = entity.mut_state.get();
-                entity.mut_state.set(old.shift_replace(2));
-
-                if universe_index < ((UNIVERSE_SIZE * UNIVERSE_SIZE) - UNIVERSE_SIZE) {
-                    let target_universe_index = universe_index + UNIVERSE_SIZE;
-                    let found_entities = entities.get_entities_at(target_universe_index);
-
-                    if found_entities.len() > 0 {
-                        mutate_entity(unsafe { &entities.get(found_entities[0]).state }, found_entities[0]);
+            // check if we're adjascent to any entities at the supplied offset
+            let mut check_adjascent = |x_offset: isize, y_offset: isize| {
+                if let Some(&target_handle) = view.entities_at(x_offset, y_offset).first() {
+                    if let Some((target, _)) = view.get_entity(target_handle) {
+                        match target.state {
+                            ES::Dust{ .. } => {
+                                // dispatch an action to invert this entity's shade
+                                entity_action_executor(EA::InvertShade, target_handle);
+                            },
+                            _ => (),
+                        }
                     }
                 }
             };
 
             while checked_count < 4 {
-                let mes_cp: MES = entity.mut_state.get();
-                let sum = mes_cp.0[0] + mes_cp.0[1] + mes_cp.0[2] + mes_cp.0[3];
-                // println!("MES: {:?}", mes_cp.0);
+                let sum = mes.0[0] + mes.0[1] + mes.0[2] + mes.0[3];
+                // println!("MES: {:?}", mes.0);
                 let modulo = sum % 4;
 
                 if checked[modulo as usize] {
-                    entity.mut_state.set(mes_cp.shift_replace(if modulo < 4 { modulo + 1 } else { modulo }));
+                    mes = mes.shift_replace(if modulo < 4 { modulo + 1 } else { modulo });
                     // let move_action = match SelfAction::Translate
                 } else {
                     checked[modulo as usize] = true;
                     checked_count += 1;
 
-                    match modulo {
-                        0 => check_left(entity, entities, &mut checked, universe_index, &mut mutate_entity),
-                        1 => check_right(entity, entities, &mut checked, universe_index, &mut mutate_entity),
-                        2 => check_above(entity, entities, &mut checked, universe_index, &mut mutate_entity),
-                        3 => check_below(entity, entities, &mut checked, universe_index, &mut mutate_entity),
+                    // check left, right, above, and below
+                    let (x_offset, y_offset, shifted) = match modulo {
+                        0 => (-1, 0, 0),
+                        1 => (1, 0, 3),
+                        2 => (0, -1, 1),
+                        3 => (0, 1, 2),
                         _ => unreachable!(),
-                    }
+                    };
+                    mes = mes.shift_replace(shifted);
+                    check_adjascent(x_offset, y_offset);
                 }
             }
         },
@@ -129,46 +68,42 @@ pub fn entity_driver(
             let (x_velocity_diff, y_velocity_diff) = {
                 let (mut x_velocity_sum, mut y_velocity_sum) = (0.0, 0.0);
 
-                for (entity_x, entity_y) in iter_visible(cur_x, cur_y, VIEW_DISTANCE, UNIVERSE_SIZE) {
-                    let universe_index = get_index(entity_x, entity_y, UNIVERSE_SIZE);
-                    for entity_index in entities.get_entities_at(universe_index) {
-                        match unsafe { entities.get(*entity_index) }.state {
-                            ES::Dust { shade: their_shade, .. } => {
-                                // find the difference between that particle's color and our own and normalize it into
-                                // the range from (-1.0, 1.0)
-                                // -|(x+1)-(y+1)| + 1.0
-                                let mut normalized_diff = (-1.0 * ((shade + 1.0) - (their_shade + 1.0)).abs()) + 1.0;
-                                debug_assert!(normalized_diff >= -1.0 && normalized_diff <= 1.0);
-                                let (x_dist, y_dist): (isize, isize) = (entity_x as isize - cur_x as isize, entity_y as isize - cur_y as isize);
-
-                                if x_dist != 0 {
-                                    // opposites attract
-                                    // x_velocity_sum += (1.0 - normalized_diff) * (VELOCITY_DISTANCE_FACTOR / (x_dist as f32)) * VELOCITY_SCALE;
-
-                                    // same colors attract
-                                    // x_velocity_sum += normalized_diff * (VELOCITY_DISTANCE_FACTOR / (x_dist as f32)) * VELOCITY_SCALE;
-
-                                    // attraction depends on universe's sequence number
-                                    unsafe {
-                                        x_velocity_sum += (ATTRACTION_FACTOR - normalized_diff) * (VELOCITY_DISTANCE_FACTOR / (x_dist as f32)) * VELOCITY_SCALE
-                                    };
-                                }
-
-                                if y_dist != 0 {
-                                    // opposites attract
-                                    // y_velocity_sum += (1.0 - normalized_diff) * (VELOCITY_DISTANCE_FACTOR / (y_dist as f32)) * VELOCITY_SCALE;
-
-                                    // same colors attract
-                                    // y_velocity_sum += normalized_diff * (VELOCITY_DISTANCE_FACTOR / (y_dist as f32)) * VELOCITY_SCALE;
-
-                                    // attraction depends on universe's sequence number
-                                    unsafe {
-                                        y_velocity_sum += (ATTRACTION_FACTOR - normalized_diff) * (VELOCITY_DISTANCE_FACTOR / (y_dist as f32)) * VELOCITY_SCALE
-                                    };
-                                }
-                            },
-                            _ => (),
-                        }
+                for (their_entity, _, (x_dist, y_dist)) in view.iter_entities() {
+                    match their_entity.state {
+                        ES::Dust { shade: their_shade, .. } => {
+                            // find the difference between that particle's color and our own and normalize it into
+                            // the range from (-1.0, 1.0)
+                            // -|(x+1)-(y+1)| + 1.0
+                            let mut normalized_diff = (-1.0 * ((shade + 1.0) - (their_shade + 1.0)).abs()) + 1.0;
+                            debug_assert!(normalized_diff >= -1.0 && normalized_diff <= 1.0);
+
+                            if x_dist != 0 {
+                                // opposites attract
+                                // x_velocity_sum += (1.0 - normalized_diff) * (VELOCITY_DISTANCE_FACTOR / (x_dist as f32)) * VELOCITY_SCALE;
+
+                                // same colors attract
+                                // x_velocity_sum += normalized_diff * (VELOCITY_DISTANCE_FACTOR / (x_dist as f32)) * VELOCITY_SCALE;
+
+                                // attraction depends on universe's sequence number
+                                unsafe {
+                                    x_velocity_sum += (ATTRACTION_FACTOR - normalized_diff) * (VELOCITY_DISTANCE_FACTOR / (x_dist as f32)) * VELOCITY_SCALE
+                                };
+                            }
+
+                            if y_dist != 0 {
+                                // opposites attract
+                                // y_velocity_sum += (1.0 - normalized_diff) * (VELOCITY_DISTANCE_FACTOR / (y_dist as f32)) * VELOCITY_SCALE;
+
+                                // same colors attract
+                                // y_velocity_sum += normalized_diff * (VELOCITY_DISTANCE_FACTOR / (y_dist as f32)) * VELOCITY_SCALE;
+
+                                // attraction depends on universe's sequence number
+                                unsafe {
+                                    y_velocity_sum += (ATTRACTION_FACTOR - normalized_diff) * (VELOCITY_DISTANCE_FACTOR / (y_dist as f32)) * VELOCITY_SCALE
+                                };
+                            }
+                        },
+                        _ => (),
                     }
                 }
 
@@ -23,16 +23,18 @@ use std::os::raw::c_void;
 use minutiae::prelude::*;
 use minutiae::engine::serial::SerialEngine;
 use minutiae::engine::parallel::ParallelEngine;
-use minutiae::engine::iterator::{SerialEntityIterator, SerialGridIterator};
+use minutiae::engine::iterator::SerialEntityIterator;
 use minutiae::driver::BasicDriver;
 // use minutiae::emscripten::{EmscriptenDriver, CanvasRenderer};
 use minutiae::driver::middleware::MinDelay;
 use minutiae::driver::middleware::gif_renderer::GifRenderer;
+use minutiae::mailbox::Recipient;
+use minutiae::universe::Universe2D;
+use minutiae::view::EntityView;
 use noise::*;
 use palette::{FromColor, Hsv, Rgb};
 use pcg::PcgRng;
 use rand::Rng;
-use uuid::Uuid;
 
 mod engine;
 use engine::DancerEngine;
@@ -149,14 +151,14 @@ impl MES {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum CA {
 
 }
 
 impl CellAction<CS> for CA {}
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum EA {
     /// Update the velocity of the entity with the given vector and translate according to the result.
     UpdateVelocities { x: f32, y: f32 },
@@ -166,9 +168,6 @@ impl EntityAction<CS, ES> for EA {}
 
 impl EntityState<CS> for ES {}
 
-// dummy function until `cell_mutator` is deprecated entirely
-pub fn cell_mutator(_: usize, _: &[Cell<CS>]) -> Option<CS> { None }
-
 /// Creates a new dust particle, initialized with random values
 fn create_dust(rng: &mut PcgRng) -> Entity<CS, ES, MES> {
     let state = ES::Dust {
@@ -187,11 +186,11 @@ fn create_dust(rng: &mut PcgRng) -> Entity<CS, ES, MES> {
 }
 
 struct WG;
-impl Generator<CS, ES, MES, CA, EA> for WG {
+impl Generator<CS, ES, MES> for WG {
     fn gen(&mut self, conf: &UniverseConf) -> (Vec<Cell<CS>>, Vec<Vec<Entity<CS, ES, MES>>>) {
         // start with an empty universe
-        let cells = vec![Cell { state: CS { color: Rgb::new_u8(3, 3, 3), noise_val_2: 0.0 }}; UNIVERSE_SIZE * UNIVERSE_SIZE];
-        let mut entities: Vec<Vec<Entity<CS, ES, MES>>> = vec![Vec::new(); UNIVERSE_SIZE * UNIVERSE_SIZE];
+        let cells = vec![Cell { state: CS { color: Rgb::new_u8(3, 3, 3), noise_val_2: 0.0 }}; conf.len()];
+        let mut entities: Vec<Vec<Entity<CS, ES, MES>>> = vec![Vec::new(); conf.len()];
 
         // create some fast PRNG using "true" RNG
         let mut true_rng = rand::thread_rng();
@@ -232,11 +231,10 @@ fn offset_color(base: &Rgb, noise: f32) -> Rgb {
     *base
 }
 
-fn get_color(cell: &Cell<CS>, entity_indexes: &[usize], entity_container: &EntityContainer<CS, ES, MES>) -> [u8; 4] {
-    let rgb_color = match entity_indexes {
-        &[] => cell.state.color,
-        &[.., last_index] => {
-            let entity = unsafe { entity_container.get(last_index) };
+fn get_color(cell: &Cell<CS>, entity_handles: &[EntityHandle], entity_container: &EntityContainer<CS, ES, MES>) -> [u8; 4] {
+    let rgb_color = match entity_handles.last().and_then(|&handle| entity_container.get(handle)) {
+        None => cell.state.color,
+        Some(entity) => {
 
             if COLOR_MODE == 0 {
                 // generate color based on the shade of the entity
@@ -268,14 +266,10 @@ fn get_color(cell: &Cell<CS>, entity_indexes: &[usize], entity_container: &Entit
 }
 
 fn main() {
-    let conf = UniverseConf {
-        iter_cells: false,
-        size: UNIVERSE_SIZE,
-        view_distance: VIEW_DISTANCE,
-    };
-    let universe = Universe::new(conf, &mut WG, cell_mutator, entity_driver);
+    let conf = UniverseConf::square(UNIVERSE_SIZE as u32);
+    let universe = Universe2D::new(conf, &mut WG);
     // let engine: Box<SerialEngine<CS, ES, MES, CA, EA, SerialGridIterator, SerialEntityIterator<CS, ES>>> = Box::new(DancerEngine);
-    let engine = Box::new(ParallelEngine::new(SerialGridIterator::new(UNIVERSE_SIZE), Box::new(engine::exec_actions), entity_driver));
+    let engine = Box::new(ParallelEngine::new(engine::exec_actions, entity_driver));
     // let driver = EmscriptenDriver;
     let driver = BasicDriver;
 
@@ -284,8 +278,8 @@ fn main() {
 
     driver.init(universe, engine, &mut [
         Box::new(MinDelay::from_tps(30.99)),
-        // Box::new(CanvasRenderer::new(UNIVERSE_SIZE, get_color, canvas_render)),
-        Box::new(GifRenderer::new("./output.gif", UNIVERSE_SIZE, get_color)),
+        // Box::new(CanvasRenderer::new(UNIVERSE_SIZE, UNIVERSE_SIZE, get_color, canvas_render)),
+        Box::new(GifRenderer::new("./output.gif", UNIVERSE_SIZE, UNIVERSE_SIZE, get_color)),
         Box::new(TracerMiddleware::new(0.5, 0.033, 1)),
     ]);
 }
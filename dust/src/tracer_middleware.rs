@@ -21,12 +21,14 @@ impl TracerMiddleware {
     }
 }
 
-impl Middleware<
-    CS, ES, MES, CA, EA, Box<ParallelEngine<CS, ES, MES, CA, EA, SerialGridIterator>>
+impl<N: Engine<CS, ES, MES, CA, EA, Universe2D<CS, ES, MES>>> Middleware<
+    CS, ES, MES, CA, EA, Universe2D<CS, ES, MES>, N
 > for TracerMiddleware {
-    fn after_render(&mut self, universe: &mut Universe<CS, ES, MES, CA, EA>) {
+    fn after_render(&mut self, universe: &mut Universe2D<CS, ES, MES>) {
+        let tick = universe.entities.tick as usize;
+
         // for each of the entities, alter the color of the background to match the entity's color
-        for (entity, entity_index, universe_index) in universe.entities.iter_mut() {
+        for (entity, _, universe_index) in universe.entities.iter() {
             let entity_color = entity.state.get_base_color();
             let mut cell = &mut universe.cells[universe_index];
             let cell_color = cell.state.color;
@@ -35,7 +37,7 @@ impl Middleware<
         }
 
         // fade the color of all cells every `n` ticks
-        if universe.seq % self.fade_interval == 0 {
+        if tick % self.fade_interval == 0 {
             for mut cell in &mut universe.cells {
                 cell.state.color = cell.state.color.mix(&Rgb::new_u8(3, 3, 3), self.fade_factor);
             }
@@ -45,7 +47,7 @@ impl Middleware<
             ATTRACTION_FACTOR = {
                 let period = 100;
 
-                let progress: usize = universe.seq % (2 * period);
+                let progress: usize = tick % (2 * period);
                 if progress > period {
                     1.0 - ((progress - period) as f32 / period as f32)
                 } else {
@@ -9,23 +9,29 @@ pub use self::fish::fish_driver;
 mod predator;
 pub use self::predator::predator_driver;
 
+/// The view of the universe that entities use to decide on their actions
+pub type OurEntityView<'a> = EntityView<'a, OurCellState, OurEntityState, OurMutEntityState, OurUniverse>;
+
+/// Returns a PRNG for an entity to make random decisions with.  Cloning an entity's mutable state re-seeds its PRNG.
+fn entity_rng(entity: &Entity<OurCellState, OurEntityState, OurMutEntityState>) -> PcgRng {
+    entity.mut_state.clone().rng.unwrap()
+}
+
 /// This function determines the core logic of the simulation.  Every entity evaluates this function every tick of the
 /// simulation.  Actions are sent to the various executors and dispatched in batch after all entities have submitted them.
 pub fn our_entity_driver(
-    source_universe_index: usize,
-    entity: &Entity<OurCellState, OurEntityState, OurMutEntityState>,
-    entities: &EntityContainer<OurCellState, OurEntityState, OurMutEntityState>,
-    cells: &[Cell<OurCellState>],
+    view: &OurEntityView,
     cell_action_executor: &mut FnMut(OurCellAction, usize),
     self_action_executor: &mut FnMut(SelfAction<OurCellState, OurEntityState, OurEntityAction>),
-    entity_action_executor: &mut FnMut(OurEntityAction, usize, Uuid)
+    entity_action_executor: &mut FnMut(OurEntityAction, EntityHandle),
+    _: &mut FnMut(Recipient, ())
 ) {
-    match entity.state {
+    match view.entity().state {
         OurEntityState::Fish{..} => {
-            fish_driver(source_universe_index, entity, entities, cells, cell_action_executor, self_action_executor);
+            fish_driver(view, cell_action_executor, self_action_executor);
         },
         OurEntityState::Predator{direction, ..} => {
-            predator_driver(direction, source_universe_index, entity, entities, self_action_executor, entity_action_executor);
+            predator_driver(direction, view, self_action_executor, entity_action_executor);
         }
     }
 }
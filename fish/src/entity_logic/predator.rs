@@ -5,44 +5,36 @@ use super::*;
 
 pub fn predator_driver(
     direction: Option<(i8, i8)>,
-    source_universe_index: usize,
-    entity: &Entity<OurCellState, OurEntityState, OurMutEntityState>,
-    entities: &EntityContainer<OurCellState, OurEntityState, OurMutEntityState>,
+    view: &OurEntityView,
     self_action_executor: &mut FnMut(SelfAction<OurCellState, OurEntityState, OurEntityAction>),
-    entity_action_executor: &mut FnMut(OurEntityAction, usize, Uuid)
+    entity_action_executor: &mut FnMut(OurEntityAction, EntityHandle)
 ) {
     // 1. If we're adjascent to a fish, eat it.
     // 2. If we see a fish, move towards it.
     // 3. If we don't see any fish, pick a random vector (if we don't already have one picked) and move that way.
 
-    // if there are no predators to flee from, look for the nearest food item
-    let (cur_x, cur_y) = get_coords(source_universe_index, UNIVERSE_SIZE);
-    let mut closest_fish: Option<(usize, usize, usize, Uuid, usize)> = None;
-    for (x, y) in iter_visible(cur_x, cur_y, VIEW_DISTANCE, UNIVERSE_SIZE) {
-        let universe_index = get_index(x, y, UNIVERSE_SIZE);
-        for entity_index in entities.get_entities_at(universe_index) {
-            let target_entity = unsafe { entities.get(*entity_index) };
-            if let OurEntityState::Fish{..} = target_entity.state {
-                // if we found a nearby fish, calculate the distance between it and us
-                // if it's less than the current minimum distance, run towards this one first
-                let cur_distance = manhattan_distance(cur_x, cur_y, x, y);
-                match closest_fish {
-                    Some((_, _, _, _, min_distance)) => {
-                        if min_distance > cur_distance {
-                            closest_fish = Some((x, y, *entity_index, target_entity.uuid, cur_distance));
-                        }
-                    },
-                    None => closest_fish = Some((x, y, *entity_index, target_entity.uuid, cur_distance)),
-                }
+    // look for the nearest fish
+    let mut closest_fish: Option<(isize, isize, EntityHandle, usize)> = None;
+    for (target_entity, target_handle, (x_offset, y_offset)) in view.iter_entities() {
+        if let OurEntityState::Fish{..} = target_entity.state {
+            // if we found a nearby fish, calculate the distance between it and us
+            // if it's less than the current minimum distance, run towards this one first
+            let cur_distance = (x_offset.abs() + y_offset.abs()) as usize;
+            match closest_fish {
+                Some((_, _, _, min_distance)) => {
+                    if min_distance > cur_distance {
+                        closest_fish = Some((x_offset, y_offset, target_handle, cur_distance));
+                    }
+                },
+                None => closest_fish = Some((x_offset, y_offset, target_handle, cur_distance)),
             }
         }
     }
 
     // If we can see a fish if we're adjascent to it, eat it.  If not, move towards it.
-    if let Some((fish_x, fish_y, fish_entity_index, fish_uuid, fish_distance)) = closest_fish {
-        let (x_offset, y_offset) = calc_offset(cur_x, cur_y, fish_x, fish_y);
+    if let Some((x_offset, y_offset, fish_handle, fish_distance)) = closest_fish {
         if fish_distance <= 1 {
-            return entity_action_executor(OurEntityAction::EatFish, fish_entity_index, fish_uuid);
+            return entity_action_executor(OurEntityAction::EatFish, fish_handle);
         } else {
             let self_action = SelfAction::Translate(x_offset, y_offset);
             return self_action_executor(self_action);
@@ -50,22 +42,15 @@ pub fn predator_driver(
     }
 
     // we can't see any fish, so pick a random direction to swim in (if we haven't already picked one) and swim that way
+    let (cur_x, cur_y) = view.coords();
     let (x_dir, y_dir) = {
         let mut get_random_vector = || {
-            let mut mut_state_inner = entity.mut_state.take();
-
+            let mut rng = entity_rng(view.entity());
             let mut vector: (i8, i8) = (0, 0);
+            while vector == (0, 0) {
+                vector = (rng.gen_range(-1, 2), rng.gen_range(-1, 2));
+            }
 
-            vector = {
-                let mut rng = mut_state_inner.rng.as_mut().unwrap();
-                while vector == (0, 0) {
-                    vector = (rng.gen_range(-1, 2), rng.gen_range(-1, 2));
-                }
-
-                vector
-            };
-
-            entity.mut_state.set(mut_state_inner);
             let self_action = SelfAction::Custom(OurEntityAction::SetVector(vector.0, vector.1));
             self_action_executor(self_action);
 
@@ -19,10 +19,9 @@ use std::fmt::{self, Display, Formatter};
 
 use pcg::PcgRng;
 use rand::Rng;
-use uuid::Uuid;
 
-use minutiae::universe::{Universe, UniverseConf};
-use minutiae::container::EntityContainer;
+use minutiae::universe::{Universe2D, Universe2DConf};
+use minutiae::container::{EntityContainer, EntityHandle};
 use minutiae::cell::{Cell, CellState};
 use minutiae::entity::{Entity, EntityState, MutEntityState};
 use minutiae::action::{Action, CellAction, EntityAction, OwnedAction, SelfAction};
@@ -31,11 +30,12 @@ use minutiae::engine::Engine;
 use minutiae::engine::parallel::ParallelEngine;
 #[cfg(target_os = "emscripten")]
 use minutiae::engine::serial::SerialEngine;
-use minutiae::engine::iterator::SerialGridIterator;
 #[cfg(target_os = "emscripten")]
 use minutiae::engine::iterator::SerialEntityIterator;
+use minutiae::mailbox::Recipient;
 use minutiae::generator::Generator;
-use minutiae::util::{calc_offset, get_coords, get_index, iter_visible, manhattan_distance, Color};
+use minutiae::util::{get_coords, get_index, manhattan_distance, Color};
+use minutiae::view::EntityView;
 use minutiae::driver::{Driver, BasicDriver};
 use minutiae::driver::middleware::{Middleware, MinDelay};
 #[cfg(target_os = "emscripten")]
@@ -158,14 +158,14 @@ impl Default for OurMutEntityState {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum OurCellAction {
     Eat, // The only thing that we can really do to the world right now is eat food
 }
 
 impl CellAction<OurCellState> for OurCellAction {}
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum OurEntityAction {
     EatFish,
     MakeBaby,
@@ -174,29 +174,35 @@ pub enum OurEntityAction {
 
 impl EntityAction<OurCellState, OurEntityState> for OurEntityAction {}
 
+type OurUniverse = Universe2D<OurCellState, OurEntityState, OurMutEntityState>;
+
+type OurActions = [OwnedAction<OurCellState, OurEntityState, OurCellAction, OurEntityAction>];
+
+type OurActionExecutor = fn(&mut OurUniverse, &OurActions, &OurActions, &OurActions);
+
 #[cfg(not(target_os = "emscripten"))]
 type OurEngineType = Box<
         ParallelEngine<OurCellState, OurEntityState, OurMutEntityState, OurCellAction,
-        OurEntityAction, SerialGridIterator>
+        OurEntityAction, OurUniverse, OurActionExecutor>
     >;
 
 #[cfg(target_os = "emscripten")]
 type OurEngineType = Box<
     SerialEngine<OurCellState, OurEntityState, OurMutEntityState, OurCellAction,
-    OurEntityAction, SerialGridIterator, SerialEntityIterator<OurCellState, OurEntityState>>
+    OurEntityAction, SerialEntityIterator<OurCellState, OurEntityState>, OurUniverse>
 >;
 
 struct OurWorldGenerator(u64);
 
-impl Generator<OurCellState, OurEntityState, OurMutEntityState, OurCellAction, OurEntityAction> for OurWorldGenerator {
+impl Generator<OurCellState, OurEntityState, OurMutEntityState> for OurWorldGenerator {
     fn gen(
-        &mut self, conf: &UniverseConf
+        &mut self, conf: &Universe2DConf
     ) -> (
         Vec<Cell<OurCellState>>,
         Vec<Vec<Entity<OurCellState, OurEntityState, OurMutEntityState>>>,
     ) {
         println!("Generating world...");
-        let length = conf.size * conf.size;
+        let length = conf.len();
         let mut cells = Vec::with_capacity(length);
         for _ in 0..length {
             // populate the world with water cells
@@ -210,7 +216,7 @@ impl Generator<OurCellState, OurEntityState, OurMutEntityState, OurCellAction, O
         // populate the world with `FISH_COUNT` randomly placed fish
         let origin_entity = Entity::new(OurEntityState::Fish{food: 0}, OurMutEntityState {rng: Some(rng.clone())});
         for _ in 0..FISH_COUNT {
-            let index = rng.gen_range(0, UNIVERSE_SIZE * UNIVERSE_SIZE);
+            let index = rng.gen_range(0, length);
             let entity = origin_entity.clone();
             entities[index].push(entity);
         }
@@ -221,7 +227,7 @@ impl Generator<OurCellState, OurEntityState, OurMutEntityState, OurCellAction, O
             OurMutEntityState {rng: Some(rng.clone())}
         );
         for _ in 0..PREDATOR_COUNT {
-            let index = rng.gen_range(0, UNIVERSE_SIZE * UNIVERSE_SIZE);
+            let index = rng.gen_range(0, length);
             let entity = origin_predator.clone();
             entities[index].push(entity);
         }
@@ -230,17 +236,13 @@ impl Generator<OurCellState, OurEntityState, OurMutEntityState, OurCellAction, O
     }
 }
 
-fn our_cell_mutator(_: usize, _: &[Cell<OurCellState>]) -> Option<OurCellState> { None }
-
 /// Adds a step onto the end of each simulation cycle that has a chance of spawning some food into the world for the fish to eat
 struct FoodSpawnerMiddleware(PcgRng);
 
 impl Middleware<
-    OurCellState, OurEntityState, OurMutEntityState, OurCellAction, OurEntityAction, OurEngineType
+    OurCellState, OurEntityState, OurMutEntityState, OurCellAction, OurEntityAction, OurUniverse, OurEngineType
 > for FoodSpawnerMiddleware {
-    fn before_render(
-        &mut self, universe: &mut Universe<OurCellState, OurEntityState, OurMutEntityState, OurCellAction, OurEntityAction>
-    ) {
+    fn before_render(&mut self, universe: &mut OurUniverse) {
         let mut rng = &mut self.0;
         if rng.gen_range(0, FOOD_SPAWN_RARITY) == 0 {
             let food_spawn_x = rng.gen_range(0, UNIVERSE_SIZE);
@@ -273,35 +275,46 @@ impl FoodSpawnerMiddleware {
     }
 }
 
-fn main() {
-    use minutiae::universe;
+/// The types of the simulation, used by the server to send the colors of the universe to clients
+#[cfg(not(target_os = "emscripten"))]
+#[derive(Clone, Copy)]
+struct OurTys;
 
-    let mut conf = universe::UniverseConf::default();
-    conf.size = UNIVERSE_SIZE;
+#[cfg(not(target_os = "emscripten"))]
+impl minutiae::server::Tys for OurTys {
+    type C = OurCellState;
+    type E = OurEntityState;
+    type M = OurMutEntityState;
+    type CA = OurCellAction;
+    type EA = OurEntityAction;
+    type I = usize;
+    type U = OurUniverse;
+    type Snapshot = minutiae::server::ColorSnapshot;
+    type ServerMessage = minutiae::server::ThinServerMessage;
+    type ClientMessage = minutiae::server::ThinClientMessage;
+}
+
+fn main() {
+    let conf = Universe2DConf::square(UNIVERSE_SIZE as u32);
     #[cfg(target_os = "emscripten")]
     let engine: OurEngineType = Box::new(OurEngine {});
     #[cfg(not(target_os = "emscripten"))]
     let engine = Box::new(
-        ParallelEngine::new(SerialGridIterator::new(UNIVERSE_SIZE * UNIVERSE_SIZE), exec_actions, our_entity_driver)
+        ParallelEngine::new(exec_actions as OurActionExecutor, our_entity_driver)
     );
 
-    let universe = universe::Universe::new(
-        conf,
-        &mut OurWorldGenerator(19093929992071),
-        our_cell_mutator,
-        our_entity_driver,
-    );
+    let universe = Universe2D::new(conf, &mut OurWorldGenerator(19093929992071));
 
     #[cfg(target_os = "emscripten")]
     {
         fn calc_color(
             cell: &Cell<OurCellState>,
-            entity_indexes: &[usize],
+            entity_handles: &[EntityHandle],
             entity_container: &EntityContainer<OurCellState, OurEntityState, OurMutEntityState>
         ) -> [u8; 4] {
-            if !entity_indexes.is_empty() {
-                for i in entity_indexes {
-                    if let OurEntityState::Predator{..} = *unsafe { &entity_container.get(*i).state } {
+            if !entity_handles.is_empty() {
+                for &handle in entity_handles {
+                    if let Some(&Entity{state: OurEntityState::Predator{..}, ..}) = entity_container.get(handle) {
                         return [233, 121, 78, 255];
                     }
                 }
@@ -316,23 +329,26 @@ fn main() {
 
         EmscriptenDriver.init(universe, engine, &mut [
             Box::new(FoodSpawnerMiddleware::new()),
-            Box::new(CanvasRenderer::new(UNIVERSE_SIZE, calc_color, canvas_render)),
+            Box::new(CanvasRenderer::new(UNIVERSE_SIZE, UNIVERSE_SIZE, calc_color, canvas_render)),
         ]);
     }
 
     #[cfg(not(target_os = "emscripten"))]
     {
-        use minutiae::server::{self, Color};
-        use minutiae::server::thin::*;
+        use std::ops::Range;
+        use std::sync::Arc;
+        use std::sync::atomic::AtomicU32;
+
+        use minutiae::server::{ColorServer, Server};
 
         fn calc_color(
             cell: &Cell<OurCellState>,
-            entity_indexes: &[usize],
+            entity_handles: &[EntityHandle],
             entity_container: &EntityContainer<OurCellState, OurEntityState, OurMutEntityState>
         ) -> Color {
-            if !entity_indexes.is_empty() {
-                for i in entity_indexes {
-                    if let OurEntityState::Predator{..} = *unsafe { &entity_container.get(*i).state } {
+            if !entity_handles.is_empty() {
+                for &handle in entity_handles {
+                    if let Some(&Entity{state: OurEntityState::Predator{..}, ..}) = entity_container.get(handle) {
                         return Color([233, 121, 78]);
                     }
                 }
@@ -345,9 +361,18 @@ fn main() {
             }
         }
 
-        let server_logic = server::ColorServer::new(UNIVERSE_SIZE, calc_color);
-        let seq = server_logic.seq.clone();
-        let server = Box::new(server::thin::ColorServer::new(UNIVERSE_SIZE, "0.0.0.0:7037", server_logic, seq));
+        /// Visits the universe indexes of the cells in the supplied range, which are already in row-major order
+        fn iter_cells(start_index: usize, end_index: usize) -> Range<usize> { start_index..end_index }
+
+        let server_logic: ColorServer<OurTys, _> = ColorServer::new(
+            calc_color,
+            iter_cells,
+            0,
+            UNIVERSE_SIZE * UNIVERSE_SIZE,
+            UNIVERSE_SIZE as u32,
+            UNIVERSE_SIZE as u32,
+        );
+        let server = Server::new("0.0.0.0:7037", server_logic, Arc::new(AtomicU32::new(0)));
         let driver = BasicDriver;
         driver.init(universe, engine, &mut [
             // Box::new(UniverseDisplayer {}),
@@ -361,20 +386,12 @@ fn main() {
 
 #[bench]
 fn universe_step_parallel(b: &mut test::Bencher) {
-    use minutiae::universe;
-
-    let mut conf = universe::UniverseConf::default();
-    conf.size = UNIVERSE_SIZE;
+    let conf = Universe2DConf::square(UNIVERSE_SIZE as u32);
     let mut engine = Box::new(
-        ParallelEngine::new(SerialGridIterator::new(UNIVERSE_SIZE * UNIVERSE_SIZE), exec_actions, our_entity_driver)
+        ParallelEngine::new(exec_actions as OurActionExecutor, our_entity_driver)
     );
 
-    let mut universe = Universe::new(
-        conf,
-        &mut OurWorldGenerator(19200064321271),
-        our_cell_mutator,
-        our_entity_driver,
-    );
+    let mut universe = Universe2D::new(conf, &mut OurWorldGenerator(19200064321271));
 
     let mut middleware = FoodSpawnerMiddleware(PcgRng::new_unseeded());
 
@@ -19,15 +19,17 @@ use ketos::rc_vec::RcVec;
 use ketos::restrict::RestrictConfig;
 use itertools::Itertools;
 use minutiae::prelude::*;
+use minutiae::engine::executor::{self, ActionHandler};
 use minutiae::engine::serial::SerialEngine;
 use minutiae::engine::iterator::SerialEntityIterator;
 use minutiae::driver::middleware::MinDelay;
 use minutiae::driver::BasicDriver;
+use minutiae::mailbox::Recipient;
 use minutiae::universe::Universe2D;
-use minutiae::util::{debug, translate_entity};
+use minutiae::util::debug;
+use minutiae::view::EntityView;
 use pcg::PcgRng;
 use rand::{Rng, SeedableRng};
-use uuid::Uuid;
 
 #[cfg(feature = "wasm")]
 extern {
@@ -169,13 +171,14 @@ impl Default for MES {
 
 impl MutEntityState for MES {}
 
+#[derive(Clone, Debug)]
 enum CA {
 
 }
 
 impl CellAction<CS> for CA {}
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 enum EA {
 
 }
@@ -238,12 +241,12 @@ fn map_value_to_self_action(val: &Value) -> Result<SelfAction<CS, ES, EA>, Strin
     }
 }
 
-fn map_value_to_cell_action(_val: &Value) -> Result<(CA, usize), String> {
-    unimplemented!();
+fn map_value_to_cell_action(val: &Value) -> Result<(CA, usize), String> {
+    Err(format!("Ants have no cell actions, but a {} was jammed into the cell action buffer.", val.type_name()))
 }
 
-fn map_value_to_entity_action(_val: &Value) -> Result<(EA, usize, Uuid), String> {
-    unimplemented!();
+fn map_value_to_entity_action(val: &Value) -> Result<(EA, EntityHandle), String> {
+    Err(format!("Ants have no entity actions, but a {} was jammed into the entity action buffer.", val.type_name()))
 }
 
 impl EntityAction<CS, ES> for EA {}
@@ -300,7 +303,7 @@ fn process_action_buffers(
     context: &Context,
     cell_action_executor: &mut FnMut(CA, usize),
     self_action_executor: &mut FnMut(SelfAction<CS, ES, EA>),
-    entity_action_executor: &mut FnMut(EA, usize, Uuid)
+    entity_action_executor: &mut FnMut(EA, EntityHandle)
 ) -> Result<(), String> {
     let scope = context.scope();
 
@@ -321,8 +324,8 @@ fn process_action_buffers(
     let entity_action_list = get_list_by_name(scope, "__ENTITY_ACTIONS")?;
 
     for val in &entity_action_list {
-        let (action, entity_index, uuid): (EA, usize, Uuid) = map_value_to_entity_action(val)?;
-        entity_action_executor(action, entity_index, uuid);
+        let (action, target_entity): (EA, EntityHandle) = map_value_to_entity_action(val)?;
+        entity_action_executor(action, target_entity);
     }
 
     Ok(())
@@ -330,44 +333,21 @@ fn process_action_buffers(
 
 struct AntEngine;
 
-fn exec_cell_action(
-    owned_action: &OwnedAction<CS, ES, CA, EA>,
-    _cells: &mut [Cell<CS>],
-    entities: &mut EntityContainer<CS, ES, MES>
-) {
-    let (_entity, _entity_universe_index) = match entities.get_verify_mut(owned_action.source_entity_index, owned_action.source_uuid) {
-        Some((entity, universe_index)) => (entity, universe_index),
-        None => { return; }, // The entity been deleted, so abort.
-    };
-
-    match &owned_action.action {
-        &Action::CellAction {ref action, ..} => match action {
-            _ => unimplemented!(),
-        },
-        _ => unreachable!(),
+/// Ants only produce built-in self actions, which are all applied by minutiae's default executor.
+struct AntActionHandler;
+
+impl ActionHandler<CS, ES, MES, CA, EA> for AntActionHandler {
+    fn exec_cell_action(&mut self, _: &mut U, _: EntityHandle, action: &CA, _: usize) {
+        match *action {}
     }
-}
 
-fn exec_self_action(
-    universe: &mut U,
-    action: &OwnedAction<CS, ES, CA, EA>
-) {
-    match action.action {
-        Action::SelfAction(SelfAction::Translate(x_offset, y_offset)) => translate_entity(
-            x_offset,
-            y_offset,
-            &mut universe.entities,
-            action.source_entity_index,
-            action.source_uuid,
-            UNIVERSE_SIZE
-        ),
-        Action::EntityAction{ .. } | Action::CellAction{ .. } => unreachable!(),
-         _ => unimplemented!(),
+    fn exec_custom_self_action(&mut self, _: &mut U, _: EntityHandle, action: &EA) {
+        match *action {}
     }
-}
 
-fn exec_entity_action(_action: &OwnedAction<CS, ES, CA, EA>) {
-    unimplemented!(); // TODO
+    fn exec_entity_action(&mut self, _: &mut U, _: EntityHandle, action: &EA, _: EntityHandle) {
+        match *action {}
+    }
 }
 
 impl SerialEngine<CS, ES, MES, CA, EA, SerialEntityIterator<CS, ES>, U> for AntEngine {
@@ -382,23 +362,20 @@ impl SerialEngine<CS, ES, MES, CA, EA, SerialEntityIterator<CS, ES>, U> for AntE
         self_actions: &[OwnedAction<CS, ES, CA, EA>],
         entity_actions: &[OwnedAction<CS, ES, CA, EA>]
     ) {
-        for cell_action in cell_actions { exec_cell_action(cell_action, &mut universe.cells, &mut universe.entities); }
-        for self_action in self_actions { exec_self_action(universe, self_action); }
-        for entity_action in entity_actions { exec_entity_action(entity_action); }
+        executor::exec_actions(&mut AntActionHandler, universe, None, cell_actions, self_actions, entity_actions);
     }
 
     fn drive_entity(
         &mut self,
-        universe_index: usize,
-        entity: &Entity<CS, ES, MES>,
-        _: &U,
+        view: &EntityView<CS, ES, MES, U>,
         cell_action_executor: &mut FnMut(CA, usize),
         self_action_executor: &mut FnMut(SelfAction<CS, ES, EA>),
-        entity_action_executor: &mut FnMut(EA, usize, Uuid)
+        entity_action_executor: &mut FnMut(EA, EntityHandle),
+        _: &mut FnMut(Recipient, ())
     ) {
-        match entity.state {
+        match view.entity().state {
             ES::Ant(Ant { ref code, ref context, .. }) => {
-                reset_action_buffers(context, universe_index);
+                reset_action_buffers(context, view.position());
 
                 for c in code {
                     match ketos::exec::execute(context, Rc::clone(&c)) {
@@ -430,13 +407,14 @@ type OurSerialEngine = Box<SerialEngine<CS, ES, MES, CA, EA, SerialEntityIterato
 /// to display on the canvas.  This is called each tick.  The returned value is the color in RGBA.
 fn calc_color(
     cell: &Cell<CS>,
-    entity_indexes: &[usize],
+    entity_handles: &[EntityHandle],
     entity_container: &EntityContainer<CS, ES, MES>
 ) -> [u8; 4] {
-    if !entity_indexes.is_empty() {
-        for i in entity_indexes {
-            match unsafe { &entity_container.get(*i).state } {
-                &ES::Ant { .. } => { return [91, 75, 11, 255] },
+    if !entity_handles.is_empty() {
+        for &handle in entity_handles {
+            match entity_container.get(handle).map(|entity| &entity.state) {
+                Some(&ES::Ant { .. }) => { return [91, 75, 11, 255] },
+                None => (),
             }
         }
         [12, 24, 222, 255]
@@ -461,7 +439,7 @@ fn init(
 
     driver.init(universe, engine, &mut [
         Box::new(MinDelay::from_tps(59.99)),
-        Box::new(CanvasRenderer::new(UNIVERSE_SIZE, calc_color, canvas_render)),
+        Box::new(CanvasRenderer::new(UNIVERSE_SIZE, UNIVERSE_SIZE, calc_color, canvas_render)),
     ]);
 }
 
@@ -475,16 +453,13 @@ fn init(
     driver.init(universe, engine, &mut [
         Box::new(MinDelay::from_tps(59.99)),
         Box::new(minutiae::driver::middleware::gif_renderer::GifRenderer::new(
-            "./out.gif", UNIVERSE_SIZE, calc_color
+            "./out.gif", UNIVERSE_SIZE, UNIVERSE_SIZE, calc_color
         )),
     ]);
 }
 
 fn main() {
-    let conf = UniverseConf {
-        size: 800,
-        view_distance: 1,
-    };
+    let conf = UniverseConf::square(UNIVERSE_SIZE as u32);
     let universe = Universe2D::new(conf, &mut WorldGenerator);
     let engine: OurSerialEngine = Box::new(AntEngine);
 
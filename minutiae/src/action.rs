@@ -4,28 +4,33 @@
 
 use std::marker::PhantomData;
 
-use uuid::Uuid;
-
 use cell::CellState;
+use container::EntityHandle;
 use entity::EntityState;
+use universe::HexDirection;
 
-/// An action that is associated with a particular entity
+/// An action that is associated with a particular entity.  `I` is the coordinate type of the
+/// universe in which the action takes place.
 #[derive(Debug)]
-pub struct OwnedAction<C: CellState, E: EntityState<C>, CA: CellAction<C>, EA: EntityAction<C, E>> {
-    pub source_entity_index: usize,
-    pub source_uuid: Uuid,
-    pub action: Action<C, E, CA, EA>,
+pub struct OwnedAction<
+    C: CellState,
+    E: EntityState<C>,
+    CA: CellAction<C>,
+    EA: EntityAction<C, E>,
+    I = usize,
+> {
+    pub source_entity: EntityHandle,
+    pub action: Action<C, E, CA, EA, I>,
 }
 
-impl<C: CellState, E: EntityState<C>, CA: CellAction<C>, EA: EntityAction<C, E>> Clone
-    for OwnedAction<C, E, CA, EA>
+impl<C: CellState, E: EntityState<C>, CA: CellAction<C>, EA: EntityAction<C, E>, I> Clone
+    for OwnedAction<C, E, CA, EA, I>
 where
-    Action<C, E, CA, EA>: Clone,
+    Action<C, E, CA, EA, I>: Clone,
 {
     fn clone(&self) -> Self {
         OwnedAction {
-            source_entity_index: self.source_entity_index,
-            source_uuid: self.source_uuid,
+            source_entity: self.source_entity,
             action: self.action.clone(),
         }
     }
@@ -33,15 +38,20 @@ where
 
 #[allow(non_camel_case_types)]
 #[derive(Debug)]
-pub enum Action<C: CellState, E: EntityState<C>, CA: CellAction<C>, EA: EntityAction<C, E>> {
+pub enum Action<
+    C: CellState,
+    E: EntityState<C>,
+    CA: CellAction<C>,
+    EA: EntityAction<C, E>,
+    I = usize,
+> {
     CellAction {
         action: CA,
-        universe_index: usize,
+        universe_index: I,
     },
     EntityAction {
         action: EA,
-        target_entity_index: usize,
-        target_uuid: Uuid,
+        target_entity: EntityHandle,
     },
     SelfAction(SelfAction<C, E, EA>),
 }
@@ -51,7 +61,8 @@ impl<
         E: EntityState<C> + Clone,
         CA: CellAction<C> + Clone,
         EA: EntityAction<C, E> + Clone,
-    > Clone for Action<C, E, CA, EA>
+        I: Copy,
+    > Clone for Action<C, E, CA, EA, I>
 where
     SelfAction<C, E, EA>: Clone,
 {
@@ -66,12 +77,10 @@ where
             },
             &Action::EntityAction {
                 ref action,
-                target_entity_index,
-                target_uuid,
+                target_entity,
             } => Action::EntityAction {
                 action: action.clone(),
-                target_entity_index,
-                target_uuid,
+                target_entity,
             },
             &Action::SelfAction(ref action) => Action::SelfAction(action.clone()),
         }
@@ -79,7 +88,13 @@ where
 }
 
 /// An attempt of an entity to mutate a cell.
-pub trait CellAction<C: CellState> {}
+pub trait CellAction<C: CellState> {
+    /// Merges `other`, which targets the same cell, into this action and returns `true` if both
+    /// are deltas that can be combined regardless of the order in which they're applied, such as
+    /// adding to a quantity stored in the cell.  Merging must be commutative and associative.  By
+    /// default actions are never merged and are applied one at a time.
+    fn accumulate(&mut self, _other: &Self) -> bool { false }
+}
 
 /// An attempt of an entity to mutate another entity.
 pub trait EntityAction<C: CellState, E: EntityState<C>> {}
@@ -89,6 +104,19 @@ pub trait EntityAction<C: CellState, E: EntityState<C>> {}
 #[derive(Debug)]
 pub enum SelfAction<C: CellState, E: EntityState<C>, EA: EntityAction<C, E>> {
     Translate(isize, isize),
+    /// Moves the entity to the cell at the absolute `(x, y)` coordinates of a 2D universe
+    MoveTo(usize, usize),
+    /// Creates a new entity with the supplied state and default mutable state at an `(x, y)`
+    /// offset from the entity
+    Spawn(E, isize, isize),
+    /// Replaces the state of the entity
+    SetState(E),
+    /// Moves the entity by an `(x, y, z)` offset in a three-dimensional universe
+    Translate3D(isize, isize, isize),
+    /// Moves the entity a number of steps in one of the six directions of a hexagonal universe
+    TranslateHex(HexDirection, usize),
+    /// Moves the entity along an edge of a graph universe to the adjacent node with this index
+    TraverseEdge(usize),
     Suicide,
     Custom(EA),
     __phantom_c(PhantomData<C>),
@@ -102,6 +130,13 @@ where
     fn clone(&self) -> Self {
         match self {
             &SelfAction::Translate(x, y) => SelfAction::Translate(x, y),
+            &SelfAction::MoveTo(x, y) => SelfAction::MoveTo(x, y),
+            &SelfAction::Spawn(ref state, x, y) => SelfAction::Spawn(state.clone(), x, y),
+            &SelfAction::SetState(ref state) => SelfAction::SetState(state.clone()),
+            &SelfAction::Translate3D(x, y, z) => SelfAction::Translate3D(x, y, z),
+            &SelfAction::TranslateHex(direction, distance) =>
+                SelfAction::TranslateHex(direction, distance),
+            &SelfAction::TraverseEdge(dst_node) => SelfAction::TraverseEdge(dst_node),
             &SelfAction::Suicide => SelfAction::Suicide,
             &SelfAction::Custom(ref ea) => SelfAction::Custom(ea.clone()),
             &SelfAction::__phantom_c(spooky) => SelfAction::__phantom_c(spooky),
@@ -112,4 +147,24 @@ where
 
 impl<C: CellState, E: EntityState<C>, EA: EntityAction<C, E>> SelfAction<C, E, EA> {
     pub fn translate(x: isize, y: isize) -> SelfAction<C, E, EA> { SelfAction::Translate(x, y) }
+
+    pub fn move_to(x: usize, y: usize) -> SelfAction<C, E, EA> { SelfAction::MoveTo(x, y) }
+
+    pub fn spawn(state: E, x: isize, y: isize) -> SelfAction<C, E, EA> {
+        SelfAction::Spawn(state, x, y)
+    }
+
+    pub fn set_state(state: E) -> SelfAction<C, E, EA> { SelfAction::SetState(state) }
+
+    pub fn translate_3d(x: isize, y: isize, z: isize) -> SelfAction<C, E, EA> {
+        SelfAction::Translate3D(x, y, z)
+    }
+
+    pub fn translate_hex(direction: HexDirection, distance: usize) -> SelfAction<C, E, EA> {
+        SelfAction::TranslateHex(direction, distance)
+    }
+
+    pub fn traverse_edge(dst_node: usize) -> SelfAction<C, E, EA> {
+        SelfAction::TraverseEdge(dst_node)
+    }
 }
@@ -2,10 +2,9 @@
 //! represents its offset from the top left of the universe.  All cells are one variant of a single Enum that represents
 //! all possible variants and states that a cell can take on.
 //!
-//! Every tick of the simulation, a function is evaluated that transforms a cell from its current state into the next
-//! state.  Its only inputs are the cell itself and 2-dim array of its neighboring cells as `Option`s to account for
-//! cases where the cell is on the edge of the universe.  The size of the the supplied array is dependant on the view
-//! distance of the universe.
+//! Every tick of the simulation, a `CellRuleEngine` can evaluate a rule that transforms a cell from its current state
+//! into the next state.  Its only inputs are the cell itself and a `CellNeighborhood` through which the surrounding cells
+//! can be looked up, taking into account the edges of the universe.
 
 use std::clone::Clone;
 
@@ -1,138 +1,315 @@
 //! Declares container types that are used to provide abstracted access to data strucures within a
 //! universe.
 
+use std::{collections::HashMap, hash::Hash};
+
 #[cfg(feature = "serde")]
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
+use rand_pcg::Pcg32;
 use slab::Slab;
 use uuid::Uuid;
 
 use cell::CellState;
 use entity::{Entity, EntityState, MutEntityState};
+use lifecycle::{Death, DeathCause, Lifecycle, LifecycleEvent, Spawn};
+use neighborhood::{iter_neighborhood, Neighborhood};
+use rng::{mix64, seeded_uuid, stream_rng, uuid_stream, StreamKey};
+use universe::Universe2DConf;
+use util::{calc_offset_bounded, get_coords, get_index, iter_visible, DistanceMetric};
+
+/// A reference to an entity held in an `EntityContainer`, consisting of the index of the slot
+/// that the entity occupies and the generation of that slot at the time the entity was inserted.
+/// Slots are reused after the entity in them is removed, but their generation is incremented each
+/// time that happens; lookups using a handle to an entity that no longer exists therefore return
+/// `None` rather than referring to whatever entity has since taken its place.
+///
+/// Handles are only meaningful to the container that created them.  Entities' UUIDs remain their
+/// stable identity outside of the container; use `EntityContainer::get_handle` to translate one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct EntityHandle {
+    pub index: usize,
+    pub generation: u32,
+}
+
+impl StreamKey for EntityHandle {
+    fn stream_key(&self) -> u64 { mix64(self.index as u64 ^ mix64(u64::from(self.generation))) }
+}
 
 /// Data structure holding all of the universe's entities.  The entities and their state are held in
-/// a vector of `EntitySlot`s, each of which either holds an entity or the index of the next empty
-/// slot.  Using this method, it's possible to add/remove entities from anywhere in the container
-/// without causing any allocations.
+/// a slab, which allows entities to be added and removed from anywhere in the container while
+/// reusing the slots of removed entities.  The current generation of each slot is tracked alongside
+/// it so that `EntityHandle`s to removed entities can be detected.
 ///
-/// A second internal structure is used to map universe indexes to entity indexes; it holds the
-/// entity indexes of all entities that reside in each universe index.
+/// A second internal structure is used to map universe coordinates to entity handles; it holds the
+/// handles of all entities that reside at each coordinate.  Only occupied coordinates have an
+/// entry, so its size is proportional to the number of entities rather than the size of the
+/// universe.  Every slot also records where its entity's handle is stored within the list for its
+/// coordinate, which allows entities to be moved and removed in constant time.  `I` is the
+/// coordinate type of the universe that the container belongs to.
+///
+/// The container also holds the universe's seed, from which the UUIDs of newly inserted entities
+/// and all of the random number streams handed out by `rng` and `entity_rng` are derived, along
+/// with the number of ticks that have been simulated.  When enabled with
+/// `record_lifecycle_events`, entities that are inserted and removed are recorded as lifecycle
+/// events, which aren't serialized.
 #[derive(Clone, Debug)]
-// TODO: https://github.com/carllerche/slab/pull/41/files
-// #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-// #[cfg_attr(feature = "serde", serde(bound = "C: for<'d> Deserialize<'d>"))]
-pub struct EntityContainer<C: CellState, E: EntityState<C>, M: MutEntityState> {
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound = "C: for<'d> Deserialize<'d>, I: Serialize + for<'d> Deserialize<'d>")
+)]
+pub struct EntityContainer<
+    C: CellState,
+    E: EntityState<C>,
+    M: MutEntityState,
+    I: Copy + Eq + Hash = usize,
+> {
     /// `(entity, universe_ix)`
-    pub entities: Slab<(Entity<C, E, M>, usize)>,
-    /// A mapping of universe index to entity index
-    pub positions: Vec<Vec<usize>>,
+    pub entities: Slab<(Entity<C, E, M>, I)>,
+    /// The current generation of every slot of `entities` that has ever been occupied
+    pub generations: Vec<u32>,
+    /// A mapping of universe index to the handles of the entities located there
+    pub positions: HashMap<I, Vec<EntityHandle>>,
+    /// For every occupied slot of `entities`, the index of its handle within `positions`
+    pub position_indexes: Vec<usize>,
+    /// The seed from which UUIDs and random number streams are derived
+    pub seed: u64,
+    /// The number of UUIDs that have been minted from the seed so far
+    pub minted_uuids: u64,
+    /// The number of ticks that have been simulated so far
+    pub tick: u64,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub lifecycle: Lifecycle<C, E, M, I>,
 }
 
-impl<C: CellState, E: EntityState<C>, M: MutEntityState> EntityContainer<C, E, M> {
-    pub fn new(universe_size: usize) -> Self {
-        // a point of `u32::MAX` indicates that the slot is the last available one.
-        // (We use `u32` instead of `usize` so that we can handle binary
-        // serialization/deserialization on 32-bit platforms such as WebAssembly and Asm.JS)
+impl<C: CellState, E: EntityState<C>, M: MutEntityState, I: Copy + Eq + Hash>
+    EntityContainer<C, E, M, I>
+{
+    pub fn new() -> Self { EntityContainer::with_seed(0) }
+
+    /// Creates an empty container that derives UUIDs and random number streams from the supplied
+    /// seed.
+    pub fn with_seed(seed: u64) -> Self {
         EntityContainer {
             entities: Slab::new(),
-            positions: vec![Vec::new(); universe_size * universe_size],
+            generations: Vec::new(),
+            positions: HashMap::new(),
+            position_indexes: Vec::new(),
+            seed,
+            minted_uuids: 0,
+            tick: 0,
+            lifecycle: Lifecycle::default(),
         }
     }
 
-    /// Inserts an entity into the container, returning its index
-    pub fn insert(&mut self, entity: Entity<C, E, M>, universe_index: usize) -> usize {
-        let entity_id = self.entities.insert((entity, universe_index));
+    /// Generates the next UUID in the sequence derived from the container's seed.
+    pub fn mint_uuid(&mut self) -> Uuid {
+        let uuid = seeded_uuid(self.seed, self.minted_uuids);
+        self.minted_uuids += 1;
+        uuid
+    }
 
-        // update the positions vector to store the index of the entity
-        self.positions[universe_index].push(entity_id);
+    /// Returns a random number generator for the current tick that's selected by `stream`, which
+    /// can be anything that identifies the consumer of the randomness such as a universe index.
+    /// Its output depends only on the seed, tick and stream.
+    pub fn rng(&self, stream: u64) -> Pcg32 { stream_rng(self.seed, self.tick, stream) }
+
+    /// Returns a random number generator for the current tick that's specific to the supplied
+    /// entity, allowing it to make random decisions that are reproducible for a given seed
+    /// regardless of the order in which entities are processed.
+    pub fn entity_rng(&self, entity: &Entity<C, E, M>) -> Pcg32 {
+        self.rng(uuid_stream(entity.uuid))
+    }
 
-        entity_id
+    /// Marks the end of a tick, moving all random number streams on to the next tick and making the
+    /// tick's lifecycle events available through `lifecycle_events`.  `driver::step_engine` calls
+    /// this after every step of the engine.
+    pub fn advance_tick(&mut self) {
+        self.tick += 1;
+        self.lifecycle.advance_tick();
     }
 
-    /// Removes an entity from the container, returning it along with its current index in the
-    /// universe.
-    pub fn remove(&mut self, entity_index: usize) -> Entity<C, E, M> {
-        let (entity, universe_index) = self.entities.remove(entity_index);
+    /// Starts or stops recording the entities that are spawned and removed.  Recording is off by
+    /// default; drivers turn it on when any of their middleware observes lifecycle events.
+    pub fn record_lifecycle_events(&mut self, record: bool) { self.lifecycle.recording = record; }
 
-        // find the index of the index pointer inside the location vector and remove it
-        let position_index = self.positions[universe_index]
-            .iter()
-            .position(|&index| index == entity_index)
-            .expect(
-                "Unable to locate entity index at the expected location in the positions vector!",
-            );
-        let removed_entity_index = self.positions[universe_index].remove(position_index);
-        debug_assert_eq!(removed_entity_index, entity_index);
+    /// Returns the entities that were spawned and removed during the last completed tick.
+    pub fn lifecycle_events(&self) -> &[LifecycleEvent<C, E, M, I>] { self.lifecycle.last_tick() }
 
-        entity
+    /// Inserts an entity into the container, returning a handle to it.  If the entity doesn't have
+    /// a UUID yet, it's assigned the next one minted from the container's seed.
+    pub fn insert(&mut self, entity: Entity<C, E, M>, universe_index: I) -> EntityHandle {
+        self.insert_child(entity, universe_index, None)
     }
 
-    /// Returns a reference to the entity contained at the supplied index.  Will cause undefined
-    /// behavior in release mode and panic in debug mode ifthe index is out of bounds or the
-    /// slot at the specified index is empty.
-    pub unsafe fn get(&self, index: usize) -> &Entity<C, E, M> { &self.entities[index].0 }
+    /// Inserts an entity that was spawned by another entity, which is recorded as its parent.
+    pub fn insert_child(
+        &mut self,
+        mut entity: Entity<C, E, M>,
+        universe_index: I,
+        parent: Option<EntityHandle>,
+    ) -> EntityHandle {
+        if entity.uuid.is_nil() {
+            entity.uuid = self.mint_uuid();
+        }
+        let index = self.entities.insert((entity, universe_index));
+        if index >= self.generations.len() {
+            self.generations.resize(index + 1, 0);
+            self.position_indexes.resize(index + 1, 0);
+        }
+        let handle = EntityHandle {
+            index,
+            generation: self.generations[index],
+        };
 
-    /// Returns a mutable reference to the entity contained at the supplied index.  Will cause
-    /// undefined behavior in release mode and panic in debug mode ifthe index is out of bounds
-    /// or the slot at the specified index is empty.
-    pub unsafe fn get_mut(&mut self, index: usize) -> &mut Entity<C, E, M> {
-        &mut self.entities[index].0
+        self.add_position(handle, universe_index);
+        if self.lifecycle.recording {
+            let uuid = self.entities[index].0.uuid;
+            self.lifecycle.record(LifecycleEvent::Spawn(Spawn {
+                handle,
+                uuid,
+                position: universe_index,
+                parent,
+            }));
+        }
+        handle
     }
 
-    /// Checks if 1) an entity exists at the provided index and 2) that its UUID matches the
-    /// supplied UUID.  If so, returns a reference to the contained entity and its corresponding
-    /// universe index.
-    pub fn get_verify(&self, index: usize, uuid: Uuid) -> Option<(&Entity<C, E, M>, usize)> {
-        let (entity, universe_index) = &self.entities[index];
-        if entity.uuid == uuid {
-            Some((entity, *universe_index))
-        } else {
-            None
-        }
+    /// Returns `true` if the supplied handle refers to an entity that is still in the container.
+    pub fn contains(&self, handle: EntityHandle) -> bool {
+        self.generations.get(handle.index) == Some(&handle.generation)
+            && self.entities.contains(handle.index)
     }
 
-    /// Checks if 1) an entity exists at the provided index and 2) that its UUID matches the
-    /// supplied UUID.  If so, returns the a mutable reference to the contained entity and its
-    /// corresponding universe index.
-    pub fn get_verify_mut(
+    /// Removes an entity from the container, returning it.  Returns `None` if the entity has
+    /// already been removed.
+    pub fn remove(&mut self, handle: EntityHandle) -> Option<Entity<C, E, M>> {
+        self.remove_with_cause(handle, DeathCause::Removed)
+    }
+
+    /// Removes an entity from the container like `remove`, recording why it was removed.
+    pub fn remove_with_cause(
         &mut self,
-        index: usize,
-        uuid: Uuid,
-    ) -> Option<(&mut Entity<C, E, M>, usize)> {
-        let (entity, universe_index) = &mut self.entities[index];
-        if entity.uuid == uuid {
-            Some((entity, *universe_index))
-        } else {
-            None
+        handle: EntityHandle,
+        cause: DeathCause,
+    ) -> Option<Entity<C, E, M>> {
+        if !self.contains(handle) {
+            return None;
+        }
+
+        let (entity, universe_index) = self.entities.remove(handle.index);
+        self.generations[handle.index] = self.generations[handle.index].wrapping_add(1);
+        self.remove_position(handle, universe_index);
+
+        if self.lifecycle.recording {
+            self.lifecycle.record(LifecycleEvent::Death(Death {
+                handle,
+                position: universe_index,
+                cause,
+                entity: entity.clone(),
+            }));
+        }
+        Some(entity)
+    }
+
+    /// Returns a reference to the entity referred to by the supplied handle or `None` if it no
+    /// longer exists.
+    pub fn get(&self, handle: EntityHandle) -> Option<&Entity<C, E, M>> {
+        self.get_verify(handle).map(|(entity, _)| entity)
+    }
+
+    /// Returns a mutable reference to the entity referred to by the supplied handle or `None` if
+    /// it no longer exists.
+    pub fn get_mut(&mut self, handle: EntityHandle) -> Option<&mut Entity<C, E, M>> {
+        self.get_verify_mut(handle).map(|(entity, _)| entity)
+    }
+
+    /// Checks if the entity referred to by the supplied handle still exists and, if so, returns a
+    /// reference to it along with its universe index.
+    pub fn get_verify(&self, handle: EntityHandle) -> Option<(&Entity<C, E, M>, I)> {
+        if !self.contains(handle) {
+            return None;
         }
+
+        let (entity, universe_index) = &self.entities[handle.index];
+        Some((entity, *universe_index))
+    }
+
+    /// Checks if the entity referred to by the supplied handle still exists and, if so, returns a
+    /// mutable reference to it along with its universe index.
+    pub fn get_verify_mut(&mut self, handle: EntityHandle) -> Option<(&mut Entity<C, E, M>, I)> {
+        if !self.contains(handle) {
+            return None;
+        }
+
+        let (entity, universe_index) = &mut self.entities[handle.index];
+        Some((entity, *universe_index))
+    }
+
+    /// Finds the handle of the entity with the supplied UUID, if it exists in the container.  This
+    /// requires a linear search of all entities.
+    pub fn get_handle(&self, uuid: Uuid) -> Option<EntityHandle> {
+        self.iter()
+            .find(|&(entity, _, _)| entity.uuid == uuid)
+            .map(|(_, handle, _)| handle)
     }
 
     /// Moves an entity from one location in the universe to another.  This function assumes that
-    /// the supplied index is occupied and that the destination index is sane.
-    pub fn move_entity(&mut self, entity_index: usize, dst_universe_index: usize) {
-        debug_assert!(entity_index < self.entities.len());
-        let src_universe_index: &mut usize = &mut self.entities[entity_index].1;
-
-        // remove the index of the entity from the old universe index
-        let position_index = self.positions[*src_universe_index]
-            .iter()
-            .position(|&index| index == entity_index)
-            .expect(
-                "Unable to locate entity index at the expected location in the positions vector!",
-            );
-        let removed = self.positions[*src_universe_index].remove(position_index);
-        debug_assert_eq!(entity_index, removed);
-        // and add it to the new universe index in the position vector
-        self.positions[dst_universe_index].push(entity_index);
-        *src_universe_index = dst_universe_index;
+    /// the destination index is sane.  Returns `false` without doing anything if the entity no
+    /// longer exists.
+    pub fn move_entity(&mut self, handle: EntityHandle, dst_universe_index: I) -> bool {
+        let src_universe_index = match self.get_verify(handle) {
+            Some((_, universe_index)) => universe_index,
+            None => return false,
+        };
+
+        self.remove_position(handle, src_universe_index);
+        self.add_position(handle, dst_universe_index);
+        self.entities[handle.index].1 = dst_universe_index;
+        true
+    }
+
+    /// Adds the handle of an entity to the positions map at the supplied universe index.
+    fn add_position(&mut self, handle: EntityHandle, universe_index: I) {
+        let handles = self.positions.entry(universe_index).or_insert_with(Vec::new);
+        self.position_indexes[handle.index] = handles.len();
+        handles.push(handle);
+    }
+
+    /// Removes the entry for an entity from the positions map, dropping the entry for the universe
+    /// index entirely if it was the last entity there.  The last handle at the universe index is
+    /// moved into the vacated position, so its position index is updated to match.
+    fn remove_position(&mut self, handle: EntityHandle, universe_index: I) {
+        let position_index = self.position_indexes[handle.index];
+        let handles = self
+            .positions
+            .get_mut(&universe_index)
+            .expect("No entry in the positions map for the universe index of an entity!");
+        debug_assert_eq!(handles[position_index], handle);
+        handles.swap_remove(position_index);
+
+        match handles.get(position_index) {
+            Some(moved_handle) => self.position_indexes[moved_handle.index] = position_index,
+            None if handles.is_empty() => {
+                self.positions.remove(&universe_index);
+            },
+            None => (),
+        }
     }
 
     /// Creates an iterator over the entities contained within the container with the format
-    /// `(Entity, entity_index, universe_index)`.
-    pub fn iter<'a>(&'a self) -> impl Iterator<Item = (&Entity<C, E, M>, usize, usize)> {
-        self.entities
-            .iter()
-            .map(|(entity_ix, (entity, universe_ix))| (entity, entity_ix, *universe_ix))
+    /// `(Entity, entity_handle, universe_index)`.
+    pub fn iter<'a>(&'a self) -> impl Iterator<Item = (&Entity<C, E, M>, EntityHandle, I)> {
+        let generations = &self.generations;
+        self.entities.iter().map(move |(index, (entity, universe_ix))| {
+            let handle = EntityHandle {
+                index,
+                generation: generations[index],
+            };
+            (entity, handle, *universe_ix)
+        })
     }
 
     /// Creates a mutable iterator over the entities contained within the container with the format
@@ -159,24 +336,273 @@ impl<C: CellState, E: EntityState<C>, M: MutEntityState> EntityContainer<C, E, M
     //         })
     // }
 
-    /// Returns the position of the entity with the given entity index.
-    pub fn get_position_index(&self, entity_index: usize) -> usize {
-        debug_assert!(self.entities.len() > entity_index);
-        let universe_index = self.entities[entity_index].1;
+    /// Returns the position of the entity referred to by the supplied handle within the slice
+    /// returned by `get_entities_at` for its universe index, or `None` if it no longer exists.
+    pub fn get_position_index(&self, handle: EntityHandle) -> Option<usize> {
+        if !self.contains(handle) {
+            return None;
+        }
 
-        self.positions[universe_index]
-            .iter()
-            .position(|&index| index == entity_index)
-            .expect(
-                "Unable to find entry in position vector at at index pointed to by entity vector!",
-            )
+        Some(self.position_indexes[handle.index])
     }
 
-    /// Returns a reference to the slice of all the `entity_index`es of all entities at a certain
-    /// universe index.
-    pub fn get_entities_at(&self, universe_index: usize) -> &[usize] {
-        &self.positions[universe_index]
+    /// Returns a reference to the slice of the handles of all entities at a certain universe index.
+    pub fn get_entities_at(&self, universe_index: I) -> &[EntityHandle] {
+        self.positions
+            .get(&universe_index)
+            .map(|handles| handles.as_slice())
+            .unwrap_or(&[])
     }
 
     pub fn len(&self) -> usize { self.entities.len() }
 }
+
+/// Spatial queries for containers belonging to two-dimensional universes.  The supplied universe
+/// configuration determines the universe's dimensions and boundary policy; in a wrapping universe,
+/// distances are measured the shortest way around.  All queries are answered by looking up the
+/// coordinates that are within range in the positions map, so their cost depends on the size of
+/// the searched area rather than the total number of entities.
+impl<C: CellState, E: EntityState<C>, M: MutEntityState> EntityContainer<C, E, M, usize> {
+    /// Visits all entities within `radius` of `universe_index` as measured by `metric`, including
+    /// any at `universe_index` itself, with the format `(Entity, entity_handle, universe_index)`.
+    pub fn iter_within<'a>(
+        &'a self,
+        universe_index: usize,
+        radius: usize,
+        metric: DistanceMetric,
+        conf: &Universe2DConf,
+    ) -> impl Iterator<Item = (&'a Entity<C, E, M>, EntityHandle, usize)> + 'a {
+        let (width, height, boundary) = (conf.width as usize, conf.height as usize, conf.boundary);
+        let (cur_x, cur_y) = get_coords(universe_index, width);
+
+        iter_visible(cur_x, cur_y, radius, width, height, boundary)
+            .filter(move |&(x, y)| {
+                let offset = calc_offset_bounded(cur_x, cur_y, x, y, width, height, boundary);
+                metric.is_within(offset, radius)
+            })
+            .flat_map(move |(x, y)| {
+                let universe_index = get_index(x, y, width);
+                self.get_entities_at(universe_index)
+                    .iter()
+                    .map(move |&handle| (&self.entities[handle.index].0, handle, universe_index))
+            })
+    }
+
+    /// Counts the entities within `radius` of `universe_index` as measured by `metric`.
+    pub fn count_within(
+        &self,
+        universe_index: usize,
+        radius: usize,
+        metric: DistanceMetric,
+        conf: &Universe2DConf,
+    ) -> usize {
+        self.iter_within(universe_index, radius, metric, conf).count()
+    }
+
+    /// Visits all entities in the supplied neighborhood of `universe_index` with the format
+    /// `(Entity, entity_handle, universe_index)`.  Like all neighborhoods, this doesn't include
+    /// entities at `universe_index` itself.
+    pub fn iter_neighborhood<'a, N: Neighborhood + ?Sized>(
+        &'a self,
+        universe_index: usize,
+        neighborhood: &'a N,
+        conf: &Universe2DConf,
+    ) -> impl Iterator<Item = (&'a Entity<C, E, M>, EntityHandle, usize)> + 'a {
+        let (width, height, boundary) = (conf.width as usize, conf.height as usize, conf.boundary);
+        let (cur_x, cur_y) = get_coords(universe_index, width);
+
+        iter_neighborhood(neighborhood, cur_x, cur_y, width, height, boundary).flat_map(
+            move |(x, y)| {
+                let universe_index = get_index(x, y, width);
+                self.get_entities_at(universe_index)
+                    .iter()
+                    .map(move |&handle| (&self.entities[handle.index].0, handle, universe_index))
+            },
+        )
+    }
+
+    /// Finds the `k` entities nearest to `universe_index` for which `predicate` returns `true`,
+    /// looking no further than `max_distance` away.  Returns `(entity_handle, universe_index,
+    /// distance)` for each of them ordered from nearest to farthest; ties are broken by the order
+    /// in which `iter_within` visits them.
+    pub fn nearest<F: Fn(&Entity<C, E, M>) -> bool>(
+        &self,
+        universe_index: usize,
+        k: usize,
+        max_distance: usize,
+        metric: DistanceMetric,
+        conf: &Universe2DConf,
+        predicate: F,
+    ) -> Vec<(EntityHandle, usize, f64)> {
+        let (width, height, boundary) = (conf.width as usize, conf.height as usize, conf.boundary);
+        let (cur_x, cur_y) = get_coords(universe_index, width);
+
+        let mut found: Vec<(EntityHandle, usize, f64)> = self
+            .iter_within(universe_index, max_distance, metric, conf)
+            .filter(|&(entity, _, _)| predicate(entity))
+            .map(|(_, handle, target_index)| {
+                let (x, y) = get_coords(target_index, width);
+                let offset = calc_offset_bounded(cur_x, cur_y, x, y, width, height, boundary);
+                (handle, target_index, metric.distance(offset))
+            })
+            .collect();
+        found.sort_by(|a, b| a.2.partial_cmp(&b.2).unwrap());
+        found.truncate(k);
+        found
+    }
+
+    /// Divides the universe into square regions of `region_size` cells per side and counts the
+    /// entities in each of them.  Returns the counts in row-major order of the regions; regions at
+    /// the right and bottom edges of the universe are truncated if its dimensions aren't multiples
+    /// of `region_size`.
+    pub fn region_counts(&self, region_size: usize, conf: &Universe2DConf) -> Vec<usize> {
+        assert!(region_size > 0);
+        let width = conf.width as usize;
+        let regions_x = (width + region_size - 1) / region_size;
+        let regions_y = (conf.height as usize + region_size - 1) / region_size;
+
+        let mut counts = vec![0; regions_x * regions_y];
+        for (&universe_index, handles) in &self.positions {
+            let (x, y) = get_coords(universe_index, width);
+            counts[(y / region_size) * regions_x + (x / region_size)] += handles.len();
+        }
+        counts
+    }
+}
+
+impl<C: CellState, E: EntityState<C>, M: MutEntityState, I: Copy + Eq + Hash> Default
+    for EntityContainer<C, E, M, I>
+{
+    fn default() -> Self { EntityContainer::new() }
+}
+
+#[test]
+fn stale_entity_handles() {
+    use test_util::{new_entity, CS, ES, MES};
+
+    let mut container: EntityContainer<CS, ES, MES> = EntityContainer::new();
+    let first = container.insert(new_entity(0), 3);
+    let uuid = container.get(first).unwrap().uuid;
+    assert_eq!(container.get_handle(uuid), Some(first));
+    assert!(container.remove(first).is_some());
+
+    // the slot is reused, but the old handle doesn't refer to the new entity
+    let second = container.insert(new_entity(1), 5);
+    assert_eq!(second.index, first.index);
+    assert!(container.get(first).is_none());
+    assert!(container.get_verify_mut(first).is_none());
+    assert!(!container.move_entity(first, 7));
+    assert!(container.remove(first).is_none());
+    assert_eq!(container.get_handle(uuid), None);
+
+    assert_eq!(container.get_verify(second).unwrap().0.state, ES(1));
+    assert!(container.move_entity(second, 7));
+    assert_eq!(container.get_entities_at(7), &[second]);
+    assert!(container.get_entities_at(5).is_empty());
+}
+
+#[test]
+fn constant_time_position_index() {
+    use test_util::{new_entity, CS, ES, MES};
+
+    let mut container: EntityContainer<CS, ES, MES> = EntityContainer::new();
+    let handles: Vec<EntityHandle> = (0..6)
+        .map(|i| container.insert(new_entity(0), i % 2))
+        .collect();
+    container.move_entity(handles[0], 1);
+    container.remove(handles[3]);
+    container.move_entity(handles[4], 2);
+    container.remove(handles[1]);
+
+    for (_, handle, universe_index) in container.iter() {
+        let position_index = container.get_position_index(handle).unwrap();
+        assert_eq!(container.get_entities_at(universe_index)[position_index], handle);
+    }
+    assert_eq!(container.get_entities_at(0), &[handles[2]]);
+    assert_eq!(container.get_entities_at(1).len(), 2);
+    assert_eq!(container.get_entities_at(2), &[handles[4]]);
+    assert_eq!(container.get_position_index(handles[3]), None);
+
+    container.remove(handles[2]);
+    assert!(container.get_entities_at(0).is_empty());
+    assert_eq!(container.positions.len(), 2);
+}
+
+#[test]
+fn spatial_queries() {
+    use neighborhood::Stencil;
+    use test_util::{new_entity, CS, ES, MES};
+    use universe::BoundaryPolicy;
+
+    let mut conf = Universe2DConf::square(10);
+    let mut container: EntityContainer<CS, ES, MES> = EntityContainer::new();
+    let center = get_index(5, 5, 10);
+    let adjacent = container.insert(new_entity(0), get_index(6, 5, 10));
+    let diagonal = container.insert(new_entity(1), get_index(6, 6, 10));
+    let far = container.insert(new_entity(1), get_index(8, 5, 10));
+    let corner = container.insert(new_entity(1), get_index(0, 5, 10));
+
+    assert_eq!(container.count_within(center, 1, DistanceMetric::Manhattan, &conf), 1);
+    assert_eq!(container.count_within(center, 1, DistanceMetric::Chebyshev, &conf), 2);
+    assert_eq!(container.count_within(center, 1, DistanceMetric::Euclidean, &conf), 1);
+    assert_eq!(container.count_within(center, 2, DistanceMetric::Euclidean, &conf), 2);
+    assert_eq!(container.count_within(center, 3, DistanceMetric::Manhattan, &conf), 3);
+    let knight = Stencil::new(vec![(1, 2), (2, 1), (3, 0)]);
+    let in_stencil: Vec<EntityHandle> = container
+        .iter_neighborhood(center, &knight, &conf)
+        .map(|(_, handle, _)| handle)
+        .collect();
+    assert_eq!(in_stencil, vec![far]);
+
+    let is_target = |entity: &Entity<CS, ES, MES>| entity.state.0 == 1;
+    let nearest = container.nearest(center, 2, 5, DistanceMetric::Manhattan, &conf, is_target);
+    assert_eq!(
+        nearest.iter().map(|&(h, _, d)| (h, d)).collect::<Vec<_>>(),
+        vec![(diagonal, 2.), (far, 3.)]
+    );
+    let nearest = container.nearest(center, 5, 5, DistanceMetric::Euclidean, &conf, |_| true);
+    assert_eq!(nearest[0].0, adjacent);
+    assert_eq!(nearest.len(), 4);
+    assert_eq!(nearest[3], (corner, get_index(0, 5, 10), 5.));
+
+    // around the torus, the entity at the left edge is adjacent to the right edge
+    let right_edge = get_index(9, 5, 10);
+    assert_eq!(container.count_within(right_edge, 1, DistanceMetric::Manhattan, &conf), 1);
+    conf.boundary = BoundaryPolicy::Wrap;
+    assert_eq!(container.count_within(right_edge, 1, DistanceMetric::Manhattan, &conf), 2);
+
+    assert_eq!(container.region_counts(5, &conf), vec![0, 0, 1, 3]);
+    assert_eq!(container.region_counts(3, &conf).len(), 16);
+}
+
+#[test]
+fn seeded_entity_identities() {
+    use rand::Rng;
+    use test_util::{new_entity, CS, ES, MES};
+
+    let populate = |seed: u64| {
+        let mut container: EntityContainer<CS, ES, MES> = EntityContainer::with_seed(seed);
+        for i in 0..3 {
+            container.insert(new_entity(0), i);
+        }
+        container
+    };
+    let uuids = |container: &EntityContainer<CS, ES, MES>| -> Vec<Uuid> {
+        container.iter().map(|(entity, _, _)| entity.uuid).collect()
+    };
+
+    let (mut a, b, c) = (populate(42), populate(42), populate(43));
+    assert_eq!(uuids(&a), uuids(&b));
+    assert_ne!(uuids(&a), uuids(&c));
+    assert!(uuids(&a).iter().all(|uuid| !uuid.is_nil()));
+
+    let (entity, _, _) = a.iter().next().unwrap();
+    let (other, _, _) = b.iter().next().unwrap();
+    let roll = a.entity_rng(entity).gen::<u64>();
+    assert_eq!(roll, b.entity_rng(other).gen::<u64>());
+
+    // streams change from tick to tick
+    a.advance_tick();
+    let (entity, _, _) = a.iter().next().unwrap();
+    assert_ne!(roll, a.entity_rng(entity).gen::<u64>());
+}
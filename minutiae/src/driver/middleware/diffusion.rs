@@ -0,0 +1,221 @@
+//! Diffuses and decays the values of a two-dimensional universe's field layers after every tick,
+//! which is the basis for things like pheromone trails that spread out and evaporate over time.
+//!
+//! Each tick, every cell passes `diffusion / 4` of its value to each of its four orthogonal
+//! neighbors and then loses `decay` of what it's left with.  How the edges of the universe are
+//! handled depends on the boundary policy: in a wrapping universe values flow around the torus,
+//! `Absorb` lets values flow out of the universe and are lost, and the other policies reflect
+//! values that would flow past an edge back into the cell they came from.
+
+use super::Middleware;
+use action::{CellAction, EntityAction};
+use cell::CellState;
+use engine::{row_workers::RowWorkers, Engine};
+use entity::{EntityState, MutEntityState};
+use universe::{BoundaryPolicy, Universe2D};
+use util::get_index;
+
+/// How the values of a single field layer spread and decay
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DiffusionParams {
+    /// The index of the layer in the universe's `FieldLayers`
+    pub layer: usize,
+    /// The fraction of each cell's value that's spread to its neighbors each tick, from 0 to 1
+    pub diffusion: f32,
+    /// The fraction of each cell's value that's lost each tick, from 0 to 1
+    pub decay: f32,
+    /// The boundary policy applied at the edges of the universe; defaults to the universe's own
+    pub boundary: Option<BoundaryPolicy>,
+}
+
+impl DiffusionParams {
+    pub fn new(layer: usize, diffusion: f32, decay: f32) -> Self {
+        assert!(diffusion >= 0. && diffusion <= 1. && decay >= 0. && decay <= 1.);
+        DiffusionParams {
+            layer,
+            diffusion,
+            decay,
+            boundary: None,
+        }
+    }
+
+    /// Uses the supplied boundary policy instead of the universe's.
+    pub fn with_boundary(mut self, boundary: BoundaryPolicy) -> Self {
+        self.boundary = Some(boundary);
+        self
+    }
+}
+
+/// Applies diffusion and decay to a set of field layers after every tick.  Layers that don't exist
+/// in the universe are skipped.
+pub struct FieldDiffusion {
+    pub layers: Vec<DiffusionParams>,
+    /// The workers across which rows are divided
+    workers: RowWorkers,
+    back_buffer: Vec<f32>,
+}
+
+impl FieldDiffusion {
+    pub fn new(layers: Vec<DiffusionParams>) -> Self {
+        FieldDiffusion {
+            layers,
+            workers: RowWorkers::new(1),
+            back_buffer: Vec::new(),
+        }
+    }
+
+    /// Divides the work of each step across the supplied number of threads, which are spawned once
+    /// and reused for every step.
+    pub fn parallel(mut self, threads: usize) -> Self {
+        self.workers = RowWorkers::new(threads);
+        self
+    }
+
+    /// Applies a single step of diffusion and decay to all of the configured layers.
+    pub fn diffuse<C: CellState, E: EntityState<C>, M: MutEntityState>(
+        &mut self,
+        universe: &mut Universe2D<C, E, M>,
+    ) {
+        let (width, height) = (universe.get_width(), universe.get_height());
+        let (workers, back_buffer) = (&self.workers, &mut self.back_buffer);
+        for params in &self.layers {
+            let boundary = params.boundary.unwrap_or(universe.get_boundary());
+            let values = match universe.fields.get_mut(params.layer) {
+                Some(values) => values,
+                None => continue,
+            };
+            assert_eq!(values.len(), width * height);
+
+            back_buffer.resize(values.len(), 0.);
+            let grid = Grid {
+                src: values,
+                width,
+                height,
+                boundary,
+                params,
+            };
+            workers.for_each_rows(back_buffer, width, |rows, first_row| {
+                grid.diffuse_rows(rows, first_row)
+            });
+
+            values.copy_from_slice(back_buffer);
+        }
+    }
+}
+
+/// The previous values of a layer along with everything needed to calculate its next values
+struct Grid<'a> {
+    src: &'a [f32],
+    width: usize,
+    height: usize,
+    boundary: BoundaryPolicy,
+    params: &'a DiffusionParams,
+}
+
+impl<'a> Grid<'a> {
+    /// Calculates the next values of a contiguous run of full rows starting at `first_row`,
+    /// writing them into `dst`.
+    fn diffuse_rows(&self, dst: &mut [f32], first_row: usize) {
+        let share = self.params.diffusion / 4.;
+        let retained = 1. - self.params.decay;
+
+        for (i, dst_value) in dst.iter_mut().enumerate() {
+            let (x, y) = (i % self.width, first_row + i / self.width);
+            let value = self.src[get_index(x, y, self.width)];
+
+            let mut inflow = 0.;
+            let mut outflow_count = 0;
+            for &(x_offset, y_offset) in &[(0, -1), (-1, 0), (1, 0), (0, 1)] {
+                match self.neighbor(x, y, x_offset, y_offset) {
+                    Some(neighbor) => {
+                        inflow += self.src[neighbor];
+                        outflow_count += 1;
+                    },
+                    // values that would flow past the edge are lost
+                    None if self.boundary == BoundaryPolicy::Absorb => outflow_count += 1,
+                    None => (),
+                }
+            }
+
+            let next = value - share * value * outflow_count as f32 + share * inflow;
+            *dst_value = next * retained;
+        }
+    }
+
+    fn neighbor(&self, x: usize, y: usize, x_offset: isize, y_offset: isize) -> Option<usize> {
+        let x = self
+            .boundary
+            .resolve_visible(x as isize + x_offset, self.width)?;
+        let y = self
+            .boundary
+            .resolve_visible(y as isize + y_offset, self.height)?;
+        Some(get_index(x, y, self.width))
+    }
+}
+
+impl<
+        C: CellState,
+        E: EntityState<C>,
+        M: MutEntityState,
+        CA: CellAction<C>,
+        EA: EntityAction<C, E>,
+        N: Engine<C, E, M, CA, EA, Universe2D<C, E, M>>,
+    > Middleware<C, E, M, CA, EA, Universe2D<C, E, M>, N> for FieldDiffusion
+{
+    fn after_render(&mut self, universe: &mut Universe2D<C, E, M>) { self.diffuse(universe); }
+}
+
+#[test]
+fn field_diffusion() {
+    use cell::Cell;
+    use test_util::{CS, ES, MES};
+    use universe::Universe2DConf;
+
+    let universe_with_spike = |boundary| {
+        let mut universe: Universe2D<CS, ES, MES> = Universe2D::uninitialized();
+        universe.conf = Universe2DConf::square(6);
+        universe.conf.boundary = boundary;
+        universe.cells = vec![Cell { state: CS(0) }; 36];
+        let layer = universe.add_field("pheromone", 0.);
+        universe.fields.set(layer, get_index(0, 2, 6), 100.);
+        (universe, layer)
+    };
+    let total = |universe: &Universe2D<CS, ES, MES>, layer| -> f32 {
+        universe.fields.get(layer).unwrap().iter().sum()
+    };
+
+    for &boundary in &[BoundaryPolicy::Clip, BoundaryPolicy::Wrap] {
+        let (mut universe, layer) = universe_with_spike(boundary);
+        let mut diffusion = FieldDiffusion::new(vec![DiffusionParams::new(layer, 0.4, 0.)]);
+        for _ in 0..10 {
+            diffusion.diffuse(&mut universe);
+        }
+        assert!((total(&universe, layer) - 100.).abs() < 1e-3);
+        assert!(universe.fields.value(layer, get_index(0, 2, 6)).unwrap() < 100.);
+        assert!(universe.fields.value(layer, get_index(1, 2, 6)).unwrap() > 0.);
+        let opposite_edge = universe.fields.value(layer, get_index(5, 2, 6)).unwrap();
+        assert_eq!(opposite_edge > universe.fields.value(layer, get_index(3, 2, 6)).unwrap(), {
+            boundary == BoundaryPolicy::Wrap
+        });
+    }
+
+    // values flow out of an absorbing universe, and decay removes a fixed fraction every tick
+    let (mut universe, layer) = universe_with_spike(BoundaryPolicy::Absorb);
+    FieldDiffusion::new(vec![DiffusionParams::new(layer, 0.4, 0.)]).diffuse(&mut universe);
+    assert!((total(&universe, layer) - 90.).abs() < 1e-3);
+    let (mut universe, layer) = universe_with_spike(BoundaryPolicy::Clip);
+    FieldDiffusion::new(vec![DiffusionParams::new(layer, 0.4, 0.5)]).diffuse(&mut universe);
+    assert!((total(&universe, layer) - 50.).abs() < 1e-3);
+
+    let (mut serial, layer) = universe_with_spike(BoundaryPolicy::Wrap);
+    let (mut parallel, _) = universe_with_spike(BoundaryPolicy::Wrap);
+    let params = vec![DiffusionParams::new(layer, 0.3, 0.1)];
+    let mut serial_diffusion = FieldDiffusion::new(params.clone());
+    let mut parallel_diffusion = FieldDiffusion::new(params).parallel(4);
+    for _ in 0..5 {
+        serial_diffusion.diffuse(&mut serial);
+        parallel_diffusion.diffuse(&mut parallel);
+    }
+    assert_eq!(serial.fields, parallel.fields);
+}
@@ -0,0 +1,70 @@
+//! Records the events emitted on an `EventBus` during every tick, keeping a bounded history that can
+//! be inspected or saved once the simulation has run.
+
+use std::collections::VecDeque;
+
+use super::Middleware;
+use action::{CellAction, EntityAction};
+use cell::CellState;
+use engine::Engine;
+use entity::{EntityState, MutEntityState};
+use event_bus::EventBus;
+use universe::Universe;
+
+/// The events emitted during a single tick
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TickEvents<V> {
+    /// The number of ticks that had been simulated once the events were emitted
+    pub tick: u64,
+    pub events: Vec<V>,
+}
+
+/// Copies the events of every tick in which any were emitted, keeping those of the most recent
+/// `max_ticks` of them.
+pub struct EventRecorder<V: Clone> {
+    bus: EventBus<V>,
+    pub max_ticks: usize,
+    pub history: VecDeque<TickEvents<V>>,
+}
+
+impl<V: Clone> EventRecorder<V> {
+    pub fn new(bus: EventBus<V>, max_ticks: usize) -> Self {
+        assert!(max_ticks > 0);
+        EventRecorder {
+            bus,
+            max_ticks,
+            history: VecDeque::new(),
+        }
+    }
+
+    /// Records the events currently on the bus.  `tick` is the number of ticks simulated so far.
+    pub fn record(&mut self, tick: u64) {
+        let events = self.bus.events();
+        if events.is_empty() {
+            return;
+        }
+
+        if self.history.len() == self.max_ticks {
+            self.history.pop_front();
+        }
+        self.history.push_back(TickEvents { tick, events });
+    }
+}
+
+impl<
+        C: CellState,
+        E: EntityState<C>,
+        M: MutEntityState,
+        CA: CellAction<C>,
+        EA: EntityAction<C, E>,
+        U: Universe<C, E, M>,
+        N: Engine<C, E, M, CA, EA, U>,
+        V: Clone,
+    > Middleware<C, E, M, CA, EA, U, N> for EventRecorder<V>
+{
+    fn after_render(&mut self, universe: &mut U) {
+        let tick = universe.get_entities().tick;
+        self.record(tick);
+    }
+}
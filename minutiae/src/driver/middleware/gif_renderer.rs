@@ -1,5 +1,5 @@
 /// Creates a GIF image with a new frame create for each tick of the simulation.
-use std::fs::File;
+use std::{fs::File, hash::Hash};
 
 use gif::{Encoder, Frame, Repeat, SetParameter};
 
@@ -8,13 +8,25 @@ use action::{CellAction, EntityAction};
 use cell::CellState;
 use engine::Engine;
 use entity::{EntityState, MutEntityState};
-use universe::{Universe, Universe2D};
-use util::ColorCalculator;
+use universe::{PixelGrid, Universe};
+use util::{ColorCalculator, FieldColorCalculator};
 
-pub struct GifRenderer<C: CellState, E: EntityState<C>, M: MutEntityState> {
+/// Renders a `width` x `height` window of any universe that can be displayed as a pixel grid.  The
+/// window starts at `origin`, which defaults to `(0, 0)`; pixels that don't map to any cell in the
+/// universe are rendered black.  If a field layer is selected, cells are colored by their value in
+/// that layer rather than by `colorfn`.
+pub struct GifRenderer<
+    C: CellState,
+    E: EntityState<C>,
+    M: MutEntityState,
+    I: Copy + Eq + Hash = usize,
+> {
     encoder: Encoder<File>,
-    universe_size: u16,
-    colorfn: ColorCalculator<C, E, M>,
+    width: u16,
+    height: u16,
+    origin: (i64, i64),
+    colorfn: ColorCalculator<C, E, M, I>,
+    field: Option<(usize, FieldColorCalculator)>,
 }
 
 impl<
@@ -23,42 +35,96 @@ impl<
         M: MutEntityState,
         CA: CellAction<C>,
         EA: EntityAction<C, E>,
-        N: Engine<C, E, M, CA, EA, Universe2D<C, E, M>>,
-    > Middleware<C, E, M, CA, EA, Universe2D<C, E, M>, N> for GifRenderer<C, E, M>
+        I: Copy + Eq + Hash,
+        U: Universe<C, E, M, Coord = I> + PixelGrid<C, E, M>,
+        N: Engine<C, E, M, CA, EA, U>,
+    > Middleware<C, E, M, CA, EA, U, N> for GifRenderer<C, E, M, I>
 {
-    fn after_render(&mut self, universe: &mut Universe2D<C, E, M>) {
+    fn after_render(&mut self, universe: &mut U) {
         // calculate colors for each of the pixels in the universe and map it into the array format
         // used by `gif`
-        let mut pixels: Vec<u8> =
-            Vec::with_capacity(self.universe_size as usize * self.universe_size as usize * 3);
-        for i in 0..(self.universe_size as usize * self.universe_size as usize) {
-            let entities = universe.get_entities().get_entities_at(i);
-            let color = (self.colorfn)(&universe.cells[i], entities, &universe.entities);
+        let pixel_count = self.width as usize * self.height as usize;
+        let mut pixels: Vec<u8> = Vec::with_capacity(pixel_count * 3);
+        let (origin_x, origin_y) = self.origin;
+        for y in 0..(self.height as i64) {
+            for x in 0..(self.width as i64) {
+                let color = match universe.pixel_coord(origin_x + x, origin_y + y) {
+                    Some(coord) => match self.field_color(universe, coord) {
+                        Some(color) => color,
+                        None => match universe.get_cell(coord) {
+                            Some(cell) => {
+                                let entities = universe.get_entities().get_entities_at(coord);
+                                (self.colorfn)(&cell, entities, universe.get_entities())
+                            },
+                            None => [0, 0, 0, 255],
+                        },
+                    },
+                    None => [0, 0, 0, 255],
+                };
 
-            pixels.push(color[0]);
-            pixels.push(color[1]);
-            pixels.push(color[2]);
+                pixels.push(color[0]);
+                pixels.push(color[1]);
+                pixels.push(color[2]);
+            }
         }
 
-        let frame = Frame::from_rgb(self.universe_size, self.universe_size, &pixels);
+        let frame = Frame::from_rgb(self.width, self.height, &pixels);
         self.encoder
             .write_frame(&frame)
             .expect("Unable to write frame to output file!");
     }
 }
 
-impl<C: CellState, E: EntityState<C>, M: MutEntityState> GifRenderer<C, E, M> {
-    pub fn new(output_path: &str, universe_size: usize, colorfn: ColorCalculator<C, E, M>) -> Self {
+impl<C: CellState, E: EntityState<C>, M: MutEntityState, I: Copy + Eq + Hash>
+    GifRenderer<C, E, M, I>
+{
+    pub fn new(
+        output_path: &str,
+        width: usize,
+        height: usize,
+        colorfn: ColorCalculator<C, E, M, I>,
+    ) -> Self {
         let color_map = &[0xFF, 0xFF, 0xFF, 0, 0, 0];
         let image = File::create(output_path).unwrap();
-        let mut encoder =
-            Encoder::new(image, universe_size as u16, universe_size as u16, color_map).unwrap();
+        let mut encoder = Encoder::new(image, width as u16, height as u16, color_map).unwrap();
         encoder.set(Repeat::Infinite).unwrap();
 
         GifRenderer {
             encoder,
-            universe_size: universe_size as u16,
+            width: width as u16,
+            height: height as u16,
+            origin: (0, 0),
             colorfn,
+            field: None,
         }
     }
+
+    /// Renders the values of the field layer with the supplied index instead of the cells.
+    pub fn with_field(mut self, layer: usize, field_colorfn: FieldColorCalculator) -> Self {
+        self.field = Some((layer, field_colorfn));
+        self
+    }
+
+    /// Selects the field layer to render, or switches back to rendering cells if `None`.
+    pub fn set_field(&mut self, field: Option<(usize, FieldColorCalculator)>) {
+        self.field = field;
+    }
+
+    /// Calculates the color of a cell from the selected field layer, if there is one.
+    fn field_color<U: PixelGrid<C, E, M, Coord = I>>(
+        &self,
+        universe: &U,
+        coord: I,
+    ) -> Option<[u8; 4]> {
+        let (layer, field_colorfn) = self.field?;
+        universe.field_value(layer, coord).map(field_colorfn)
+    }
+
+    /// Sets the pixel coordinates of the top-left corner of the rendered window.
+    pub fn with_origin(mut self, x: i64, y: i64) -> Self {
+        self.origin = (x, y);
+        self
+    }
+
+    pub fn set_origin(&mut self, x: i64, y: i64) { self.origin = (x, y); }
 }
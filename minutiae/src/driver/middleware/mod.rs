@@ -10,8 +10,12 @@ use cell::CellState;
 use entity::{EntityState, MutEntityState};
 use action::{CellAction, EntityAction};
 use engine::Engine;
+use lifecycle::{Death, LifecycleEvent, Spawn};
 
+pub mod diffusion;
+pub mod event_recorder;
 pub mod gif_renderer;
+pub mod spacetime_renderer;
 
 /// Adds some side effect on to the end or beginning of the render cycle
 pub trait Middleware<
@@ -26,6 +30,41 @@ pub trait Middleware<
     fn after_render(&mut self, _: &mut U) {}
 
     fn before_render(&mut self, _: &mut U) {}
+
+    /// Called after every tick, before `after_render`, for each entity added during the tick.
+    fn on_spawn(&mut self, _: &U, _: &Spawn<U::Coord>) {}
+
+    /// Called after every tick, before `after_render`, for each entity removed during the tick.
+    fn on_death(&mut self, _: &U, _: &Death<C, E, M, U::Coord>) {}
+
+    /// Returns `true` if the middleware implements `on_spawn` or `on_death`.  Lifecycle events are
+    /// only recorded while some middleware observes them since every death keeps a copy of the
+    /// removed entity.
+    fn observes_lifecycle(&self) -> bool { false }
+}
+
+/// Passes the entities spawned and removed during the last tick to the lifecycle hooks of all of
+/// the supplied middleware.  This is called by `driver::step_engine` after every tick.
+pub fn dispatch_lifecycle_events<
+    C: CellState,
+    E: EntityState<C>,
+    M: MutEntityState,
+    CA: CellAction<C>,
+    EA: EntityAction<C, E>,
+    U: Universe<C, E, M>,
+    N: Engine<C, E, M, CA, EA, U>,
+>(
+    middleware: &mut [Box<Middleware<C, E, M, CA, EA, U, N>>],
+    universe: &U,
+) {
+    for event in universe.get_entities().lifecycle_events() {
+        for m in middleware.iter_mut() {
+            match *event {
+                LifecycleEvent::Spawn(ref spawn) => m.on_spawn(universe, spawn),
+                LifecycleEvent::Death(ref death) => m.on_death(universe, death),
+            }
+        }
+    }
 }
 
 
@@ -0,0 +1,151 @@
+//! Renders a one-dimensional universe as a space-time diagram: each generation of the simulation is
+//! drawn as a row of pixels below the previous one, producing a GIF image in which the X axis is
+//! space and the Y axis is time.
+use std::fs::File;
+
+use gif::{Encoder, Frame, Repeat, SetParameter};
+
+use super::Middleware;
+use action::{CellAction, EntityAction};
+use cell::CellState;
+use engine::Engine;
+use entity::{EntityState, MutEntityState};
+use universe::Universe;
+use util::ColorCalculator;
+
+/// Accumulates successive generations of a universe with `usize` coordinates `0..width` into rows
+/// of an image.  Once `generations` rows have been recorded they are written to the output file as
+/// a single GIF frame and a new, empty frame is started; running the simulation for exactly
+/// `generations` ticks therefore produces a single still image.  The initial state of the universe
+/// is recorded as the first row.
+pub struct SpaceTimeRenderer<C: CellState, E: EntityState<C>, M: MutEntityState> {
+    encoder: Encoder<File>,
+    width: u16,
+    generations: u16,
+    /// RGB pixel data for all rows of the frame that have been recorded so far
+    rows: Vec<u8>,
+    recorded_initial: bool,
+    colorfn: ColorCalculator<C, E, M>,
+}
+
+impl<C: CellState, E: EntityState<C>, M: MutEntityState> SpaceTimeRenderer<C, E, M> {
+    pub fn new(
+        output_path: &str,
+        width: usize,
+        generations: usize,
+        colorfn: ColorCalculator<C, E, M>,
+    ) -> Self {
+        assert!(width > 0 && generations > 0);
+        assert!(
+            width <= u16::max_value() as usize && generations <= u16::max_value() as usize,
+            "GIF images can't be wider or taller than 65535 pixels!"
+        );
+        let color_map = &[0xFF, 0xFF, 0xFF, 0, 0, 0];
+        let image = File::create(output_path).unwrap();
+        let mut encoder =
+            Encoder::new(image, width as u16, generations as u16, color_map).unwrap();
+        encoder.set(Repeat::Infinite).unwrap();
+
+        SpaceTimeRenderer {
+            encoder,
+            width: width as u16,
+            generations: generations as u16,
+            rows: Vec::with_capacity(width * generations * 3),
+            recorded_initial: false,
+            colorfn,
+        }
+    }
+
+    /// Calculates the colors of the universe's current generation and appends them as a new row,
+    /// writing out the frame if it's full.
+    fn record_generation<U: Universe<C, E, M, Coord = usize>>(&mut self, universe: &U) {
+        for universe_index in 0..(self.width as usize) {
+            let color = match universe.get_cell(universe_index) {
+                Some(cell) => {
+                    let entities = universe.get_entities().get_entities_at(universe_index);
+                    (self.colorfn)(&cell, entities, universe.get_entities())
+                },
+                None => [0, 0, 0, 255],
+            };
+
+            self.rows.push(color[0]);
+            self.rows.push(color[1]);
+            self.rows.push(color[2]);
+        }
+
+        if self.rows.len() == self.width as usize * self.generations as usize * 3 {
+            let frame = Frame::from_rgb(self.width, self.generations, &self.rows);
+            self.encoder
+                .write_frame(&frame)
+                .expect("Unable to write frame to output file!");
+            self.rows.clear();
+        }
+    }
+}
+
+impl<
+        C: CellState,
+        E: EntityState<C>,
+        M: MutEntityState,
+        CA: CellAction<C>,
+        EA: EntityAction<C, E>,
+        U: Universe<C, E, M, Coord = usize>,
+        N: Engine<C, E, M, CA, EA, U>,
+    > Middleware<C, E, M, CA, EA, U, N> for SpaceTimeRenderer<C, E, M>
+{
+    fn before_render(&mut self, universe: &mut U) {
+        if !self.recorded_initial {
+            self.recorded_initial = true;
+            self.record_generation(universe);
+        }
+    }
+
+    fn after_render(&mut self, universe: &mut U) { self.record_generation(universe); }
+}
+
+#[test]
+fn spacetime_rows() {
+    use std::env;
+
+    use cell::Cell;
+    use container::{EntityContainer, EntityHandle};
+    use gif::Decoder;
+    use test_util::{CS, ES, MES};
+    use universe::universe1d::{Universe1D, Universe1DConf};
+
+    fn colorfn(
+        cell: &Cell<CS>,
+        _: &[EntityHandle],
+        _: &EntityContainer<CS, ES, MES>,
+    ) -> [u8; 4] {
+        [cell.state.0 as u8, 0, 0, 255]
+    }
+
+    let mut universe: Universe1D<CS, ES, MES> = Universe1D::uninitialized();
+    universe.conf = Universe1DConf::new(4);
+    universe.cells = (0..4).map(|i| Cell { state: CS(i) }).collect();
+
+    let output_path = env::temp_dir().join("minutiae_spacetime_rows.gif");
+    let mut renderer = SpaceTimeRenderer::new(output_path.to_str().unwrap(), 3, 3, colorfn);
+
+    // only the first `width` cells are recorded, one generation per row
+    renderer.record_generation(&universe);
+    universe.cells[0].state = CS(9);
+    renderer.record_generation(&universe);
+    assert_eq!(renderer.rows, vec![
+        0, 0, 0, 1, 0, 0, 2, 0, 0,
+        9, 0, 0, 1, 0, 0, 2, 0, 0,
+    ]);
+
+    // the frame is written out and a new one started once all generations have been recorded
+    renderer.record_generation(&universe);
+    assert!(renderer.rows.is_empty());
+    drop(renderer);
+
+    let mut reader = Decoder::new(File::open(&output_path).unwrap())
+        .read_info()
+        .unwrap();
+    assert_eq!((reader.width(), reader.height()), (3, 3));
+    let frame = reader.read_next_frame().unwrap().unwrap();
+    assert_eq!((frame.width, frame.height), (3, 3));
+}
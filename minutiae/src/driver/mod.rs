@@ -8,7 +8,33 @@ use entity::{EntityState, MutEntityState};
 use universe::Universe;
 
 pub mod middleware;
-use self::middleware::Middleware;
+use self::middleware::{dispatch_lifecycle_events, Middleware};
+
+/// Runs a single tick of the simulation.  The engine is stepped, then the tick is ended on the
+/// universe's entity container, which moves its random number streams on to the next tick and
+/// publishes the lifecycle events of the tick to the middleware.  Drivers call this rather than
+/// `Engine::step` so that ticks advance in the same way regardless of the engine in use.
+pub fn step_engine<
+    C: CellState,
+    E: EntityState<C>,
+    M: MutEntityState,
+    CA: CellAction<C>,
+    EA: EntityAction<C, E>,
+    U: Universe<C, E, M>,
+    N: Engine<C, E, M, CA, EA, U>,
+>(
+    engine: &mut N,
+    universe: &mut U,
+    middleware: &mut [Box<Middleware<C, E, M, CA, EA, U, N>>],
+) {
+    if middleware.iter().any(|m| m.observes_lifecycle()) {
+        universe.get_entities_mut().record_lifecycle_events(true);
+    }
+
+    engine.step(universe);
+    universe.get_entities_mut().advance_tick();
+    dispatch_lifecycle_events(middleware, universe);
+}
 
 pub trait Driver<
     C: CellState,
@@ -44,7 +70,7 @@ impl<
                 m.before_render(&mut universe);
             }
 
-            engine.step(&mut universe);
+            step_engine(&mut engine, &mut universe, &mut middleware);
 
             for m in middleware.iter_mut() {
                 m.after_render(&mut universe);
@@ -40,7 +40,7 @@ impl<
                 m.before_render(&mut universe);
             }
 
-            engine.step(&mut universe);
+            step_engine(&mut engine, &mut universe, &mut middleware);
 
             for m in middleware.iter_mut() {
                 m.after_render(&mut universe);
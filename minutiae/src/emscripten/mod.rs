@@ -3,14 +3,16 @@
 
 use std::{
     cell::RefCell,
+    hash::Hash,
     mem,
     os::raw::{c_float, c_int, c_void},
     ptr::{self, null_mut},
 };
 
+use driver::step_engine;
 use prelude::*;
-use universe::Universe2D;
-use util::ColorCalculator;
+use universe::PixelGrid;
+use util::{ColorCalculator, FieldColorCalculator};
 
 pub mod driver;
 
@@ -79,7 +81,7 @@ impl<
                 m.before_render(&mut universe);
             }
 
-            engine.step(&mut universe);
+            step_engine(&mut engine, &mut universe, &mut middleware);
 
             for m in middleware.iter_mut() {
                 m.after_render(&mut universe);
@@ -94,10 +96,21 @@ impl<
 /// Middleware that caculates the color of each pixel in the universe using a provided function and
 /// maintains an internal buffer containing that data.  Once all of the data has been calculated, it
 /// calls the provided `canvas_render` function with a pointer to that internal pixeldata buffer in
-/// rgba format (the same format as is accepted by HTML Canvases).
-pub struct CanvasRenderer<C: CellState, E: EntityState<C>, M: MutEntityState> {
+/// rgba format (the same format as is accepted by HTML Canvases).  Any universe that can be
+/// displayed as a pixel grid can be rendered; pixels that don't map to a cell are rendered black.
+/// If a field layer is selected, cells are colored by their value in that layer rather than by
+/// `get_color`.  The image is resized to match the universe whenever its pixel dimensions change.
+pub struct CanvasRenderer<
+    C: CellState,
+    E: EntityState<C>,
+    M: MutEntityState,
+    I: Copy + Eq + Hash = usize,
+> {
+    width: usize,
+    height: usize,
     pixbuf: Vec<u8>,
-    get_color: ColorCalculator<C, E, M>,
+    get_color: ColorCalculator<C, E, M, I>,
+    field: Option<(usize, FieldColorCalculator)>,
     canvas_render: fn(colors: &[u8]),
 }
 
@@ -107,34 +120,44 @@ impl<
         M: MutEntityState,
         CA: CellAction<C>,
         EA: EntityAction<C, E>,
-        N: Engine<C, E, M, CA, EA, Universe2D<C, E, M>>,
-    > Middleware<C, E, M, CA, EA, Universe2D<C, E, M>, N> for CanvasRenderer<C, E, M>
+        I: Copy + Eq + Hash,
+        U: Universe<C, E, M, Coord = I> + PixelGrid<C, E, M>,
+        N: Engine<C, E, M, CA, EA, U>,
+    > Middleware<C, E, M, CA, EA, U, N> for CanvasRenderer<C, E, M, I>
 {
-    fn after_render(&mut self, universe: &mut Universe2D<C, E, M>) {
-        // check if the universe size has changed since the last render and, if it has, re-size our
-        // pixbuf
-        let universe_len = universe.cells.len();
-        let expected_pixbuf_size = universe_len * 4;
-        if expected_pixbuf_size != self.pixbuf.len() && expected_pixbuf_size != 0 {
-            self.pixbuf.resize(expected_pixbuf_size, 255u8);
+    fn after_render(&mut self, universe: &mut U) {
+        // follow the universe if its size has changed since the last render
+        if let Some((width, height)) = universe.pixel_dims() {
+            if (width, height) != (self.width, self.height) && width * height != 0 {
+                self.width = width;
+                self.height = height;
+                self.pixbuf.resize(width * height * 4, 255u8);
+            }
         }
 
         // update our internal pixel data buffer from the universe
-        for universe_index in 0..universe.cells.len() {
-            let entities = universe.entities.get_entities_at(universe_index);
-
-            let dst_ptr =
-                unsafe { self.pixbuf.as_ptr().offset(universe_index as isize * 4) } as *mut u32;
-            unsafe {
-                ptr::write(
-                    dst_ptr,
-                    mem::transmute::<[u8; 4], _>((self.get_color)(
-                        &universe.cells.get_unchecked(universe_index),
-                        entities,
-                        &universe.entities,
-                    )),
-                )
-            };
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let color = match universe.pixel_coord(x as i64, y as i64) {
+                    Some(coord) => match self.field_color(universe, coord) {
+                        Some(color) => color,
+                        None => match universe.get_cell(coord) {
+                            Some(cell) => (self.get_color)(
+                                &cell,
+                                universe.get_entities().get_entities_at(coord),
+                                universe.get_entities(),
+                            ),
+                            None => [0, 0, 0, 255],
+                        },
+                    },
+                    None => [0, 0, 0, 255],
+                };
+
+                let pixel_index = y * self.width + x;
+                let dst_ptr =
+                    unsafe { self.pixbuf.as_ptr().offset(pixel_index as isize * 4) } as *mut u32;
+                unsafe { ptr::write(dst_ptr, mem::transmute::<[u8; 4], _>(color)) };
+            }
         }
 
         // pass a pointer to our internal buffer to the canvas render function
@@ -142,18 +165,45 @@ impl<
     }
 }
 
-impl<C: CellState, E: EntityState<C>, M: MutEntityState> CanvasRenderer<C, E, M> {
+impl<C: CellState, E: EntityState<C>, M: MutEntityState, I: Copy + Eq + Hash>
+    CanvasRenderer<C, E, M, I>
+{
     pub fn new(
-        universe_size: usize,
-        get_color: ColorCalculator<C, E, M>,
+        width: usize,
+        height: usize,
+        get_color: ColorCalculator<C, E, M, I>,
         canvas_render: fn(colors: &[u8]),
     ) -> Self {
         CanvasRenderer {
-            pixbuf: vec![255u8; universe_size * universe_size * 4],
+            width,
+            height,
+            pixbuf: vec![255u8; width * height * 4],
             get_color,
+            field: None,
             canvas_render,
         }
     }
+
+    /// Renders the values of the field layer with the supplied index instead of the cells.
+    pub fn with_field(mut self, layer: usize, field_color: FieldColorCalculator) -> Self {
+        self.field = Some((layer, field_color));
+        self
+    }
+
+    /// Selects the field layer to render, or switches back to rendering cells if `None`.
+    pub fn set_field(&mut self, field: Option<(usize, FieldColorCalculator)>) {
+        self.field = field;
+    }
+
+    /// Calculates the color of a cell from the selected field layer, if there is one.
+    fn field_color<U: PixelGrid<C, E, M, Coord = I>>(
+        &self,
+        universe: &U,
+        coord: I,
+    ) -> Option<[u8; 4]> {
+        let (layer, field_color) = self.field?;
+        universe.field_value(layer, coord).map(field_color)
+    }
 }
 
 pub enum KeyPress {
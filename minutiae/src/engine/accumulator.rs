@@ -0,0 +1,130 @@
+//! Combines cell actions with reducer semantics before they're applied.  Entities often emit
+//! additive effects such as depositing pheromone or consuming food; rather than reading and writing
+//! a cell once for each of them, successive deltas targeting a cell are merged (as defined by
+//! `CellAction::accumulate`) into a single action.  A delta is never merged past an action on the
+//! same cell that it can't be merged with, so applying the merged actions has the same effect as
+//! applying the original ones in order.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use action::{Action, CellAction, EntityAction, OwnedAction};
+use cell::CellState;
+use entity::EntityState;
+
+/// Merges each cell action into the last preceding action that targets the same cell if the two can
+/// be accumulated.  Merged actions take the place and source entity of the first action of their
+/// group; actions that can't be merged, as well as actions that aren't cell actions, are returned
+/// unchanged and in their original order.
+pub fn accumulate_cell_actions<
+    C: CellState,
+    E: EntityState<C>,
+    CA: CellAction<C> + Clone,
+    EA: EntityAction<C, E> + Clone,
+    I: Copy + Eq + Hash,
+>(
+    actions: &[OwnedAction<C, E, CA, EA, I>],
+) -> Vec<OwnedAction<C, E, CA, EA, I>> {
+    let mut accumulated: Vec<OwnedAction<C, E, CA, EA, I>> = Vec::with_capacity(actions.len());
+    // index into `accumulated` of the last cell action targeting each cell
+    let mut last_targeting: HashMap<I, usize> = HashMap::new();
+
+    for owned_action in actions {
+        let (action, universe_index) = match owned_action.action {
+            Action::CellAction {
+                ref action,
+                universe_index,
+            } => (action, universe_index),
+            _ => {
+                accumulated.push(owned_action.clone());
+                continue;
+            },
+        };
+
+        let merged = match last_targeting.get(&universe_index) {
+            Some(&i) => match accumulated[i].action {
+                Action::CellAction {
+                    action: ref mut merged_action,
+                    ..
+                } => merged_action.accumulate(action),
+                _ => false,
+            },
+            None => false,
+        };
+
+        if !merged {
+            last_targeting.insert(universe_index, accumulated.len());
+            accumulated.push(owned_action.clone());
+        }
+    }
+
+    accumulated
+}
+
+#[test]
+fn deltas_are_accumulated_per_cell() {
+    use action::SelfAction;
+    use container::EntityHandle;
+    use test_util::{CS, EA, ES};
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum CA {
+        AddFood(i32),
+        Clear,
+    }
+    impl CellAction<CS> for CA {
+        fn accumulate(&mut self, other: &Self) -> bool {
+            match (self, other) {
+                (&mut CA::AddFood(ref mut food), &CA::AddFood(delta)) => {
+                    *food += delta;
+                    true
+                },
+                _ => false,
+            }
+        }
+    }
+
+    let entity = |index| EntityHandle {
+        index,
+        generation: 0,
+    };
+    let cell_action = |source, action, universe_index| OwnedAction {
+        source_entity: entity(source),
+        action: Action::CellAction {
+            action,
+            universe_index,
+        },
+    };
+    let actions: Vec<OwnedAction<CS, ES, CA, EA>> = vec![
+        cell_action(0, CA::AddFood(1), 3),
+        cell_action(1, CA::AddFood(-4), 7),
+        cell_action(2, CA::Clear, 3),
+        OwnedAction {
+            source_entity: entity(3),
+            action: Action::SelfAction(SelfAction::Suicide),
+        },
+        cell_action(4, CA::AddFood(5), 3),
+        cell_action(5, CA::AddFood(2), 7),
+    ];
+
+    let accumulated: Vec<(usize, Option<(CA, usize)>)> = accumulate_cell_actions(&actions)
+        .into_iter()
+        .map(|owned_action| {
+            let cell_action = match owned_action.action {
+                Action::CellAction {
+                    action,
+                    universe_index,
+                } => Some((action, universe_index)),
+                _ => None,
+            };
+            (owned_action.source_entity.index, cell_action)
+        })
+        .collect();
+    assert_eq!(accumulated, vec![
+        (0, Some((CA::AddFood(1), 3))),
+        (1, Some((CA::AddFood(-2), 7))),
+        (2, Some((CA::Clear, 3))),
+        (3, None),
+        (4, Some((CA::AddFood(5), 3))),
+    ]);
+}
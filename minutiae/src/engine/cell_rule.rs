@@ -0,0 +1,278 @@
+//! Updates the cells of a two-dimensional universe using a cellular automaton rule.  Every tick,
+//! the rule maps each cell and its neighborhood to the cell's next state.  All cells are evaluated
+//! against the previous state of the universe by writing the new states into a back buffer which
+//! is then swapped with the universe's cells, so the order in which they're evaluated doesn't
+//! matter and rows can be evaluated on multiple threads.
+
+use std::mem;
+
+use super::{row_workers::RowWorkers, Engine};
+use action::{CellAction, EntityAction};
+use cell::{Cell, CellState};
+use entity::{EntityState, MutEntityState};
+use neighborhood::{iter_neighborhood, Neighborhood};
+use universe::{BoundaryPolicy, Universe2D};
+use util::get_index;
+
+/// Read-only access to the cells surrounding a cell, addressed by their offset from it.  Lookups
+/// apply the universe's boundary policy, so offsets past the edges of a wrapping universe continue
+/// on the opposite side.
+pub struct CellNeighborhood<'a, C: CellState + 'a> {
+    cells: &'a [Cell<C>],
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+    boundary: BoundaryPolicy,
+}
+
+impl<'a, C: CellState + 'a> Clone for CellNeighborhood<'a, C> {
+    fn clone(&self) -> Self { *self }
+}
+
+impl<'a, C: CellState + 'a> Copy for CellNeighborhood<'a, C> {}
+
+impl<'a, C: CellState + 'a> CellNeighborhood<'a, C> {
+    pub fn new(
+        cells: &'a [Cell<C>],
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        boundary: BoundaryPolicy,
+    ) -> Self {
+        CellNeighborhood {
+            cells,
+            x,
+            y,
+            width,
+            height,
+            boundary,
+        }
+    }
+
+    /// Returns the coordinates of the cell at the center of the neighborhood.
+    pub fn coords(&self) -> (usize, usize) { (self.x, self.y) }
+
+    /// Returns the cell at the supplied offset from the center of the neighborhood or `None` if
+    /// it's outside of the universe.
+    pub fn get(&self, x_offset: isize, y_offset: isize) -> Option<&'a Cell<C>> {
+        let x = self.boundary.resolve_visible(self.x as isize + x_offset, self.width)?;
+        let y = self.boundary.resolve_visible(self.y as isize + y_offset, self.height)?;
+        Some(&self.cells[get_index(x, y, self.width)])
+    }
+
+    /// Visits all cells within `radius` of the center (including diagonals), excluding the center
+    /// itself.  Cells outside of the universe are skipped.
+    pub fn iter(&self, radius: usize) -> impl Iterator<Item = &'a Cell<C>> {
+        let neighborhood = *self;
+        let radius = radius as isize;
+        (-radius..=radius)
+            .flat_map(move |y_offset| (-radius..=radius).map(move |x_offset| (x_offset, y_offset)))
+            .filter(|&offset| offset != (0, 0))
+            .filter_map(move |(x_offset, y_offset)| neighborhood.get(x_offset, y_offset))
+    }
+
+    /// Counts the cells within `radius` of the center, excluding the center itself, whose states
+    /// match the supplied predicate.
+    pub fn count<F: Fn(&C) -> bool>(&self, radius: usize, pred: F) -> usize {
+        self.iter(radius).filter(|cell| pred(&cell.state)).count()
+    }
+
+    /// Visits all cells in the supplied neighborhood of the center.
+    pub fn iter_in<'b, N: Neighborhood + ?Sized>(
+        &self,
+        neighborhood: &'b N,
+    ) -> impl Iterator<Item = &'a Cell<C>> + 'b
+    where
+        'a: 'b,
+    {
+        let cells = self.cells;
+        let width = self.width;
+        iter_neighborhood(
+            neighborhood,
+            self.x,
+            self.y,
+            self.width,
+            self.height,
+            self.boundary,
+        )
+        .map(move |(x, y)| &cells[get_index(x, y, width)])
+    }
+
+    /// Counts the cells in the supplied neighborhood of the center whose states match the
+    /// supplied predicate.
+    pub fn count_in<N: Neighborhood + ?Sized, F: Fn(&C) -> bool>(
+        &self,
+        neighborhood: &N,
+        pred: F,
+    ) -> usize {
+        self.iter_in(neighborhood)
+            .filter(|cell| pred(&cell.state))
+            .count()
+    }
+}
+
+/// Calculates the next state of a cell given its current state and its neighborhood.
+pub type CellRule<C> = fn(&Cell<C>, CellNeighborhood<C>) -> C;
+
+/// Applies a `CellRule` to every cell of the universe each tick before delegating to an inner
+/// engine, such as a `SerialEngine` driving the universe's entities.
+pub struct CellRuleEngine<C: CellState, N> {
+    pub rule: CellRule<C>,
+    /// The engine that's stepped after the cells have been updated
+    pub inner: N,
+    /// The workers across which rows are divided
+    workers: RowWorkers,
+    back_buffer: Vec<Cell<C>>,
+}
+
+impl<C: CellState, N> CellRuleEngine<C, N> {
+    pub fn new(rule: CellRule<C>, inner: N) -> Self {
+        CellRuleEngine {
+            rule,
+            inner,
+            workers: RowWorkers::new(1),
+            back_buffer: Vec::new(),
+        }
+    }
+
+    /// Divides the evaluation of the rule across the supplied number of threads, which are spawned
+    /// once and reused every tick.
+    pub fn parallel(mut self, threads: usize) -> Self {
+        self.workers = RowWorkers::new(threads);
+        self
+    }
+
+    /// Evaluates the rule for all cells on the calling thread and swaps in the new states.
+    pub fn update_cells<E: EntityState<C>, M: MutEntityState>(
+        &mut self,
+        universe: &mut Universe2D<C, E, M>,
+    ) {
+        self.prepare_back_buffer(universe);
+        update_rows(
+            self.rule,
+            &universe.cells,
+            &mut self.back_buffer,
+            0,
+            universe.get_width(),
+            universe.get_height(),
+            universe.get_boundary(),
+        );
+        mem::swap(&mut universe.cells, &mut self.back_buffer);
+    }
+
+    fn prepare_back_buffer<E: EntityState<C>, M: MutEntityState>(
+        &mut self,
+        universe: &Universe2D<C, E, M>,
+    ) {
+        // the back buffer holds the states from two ticks ago, which are overwritten
+        if self.back_buffer.len() != universe.cells.len() {
+            self.back_buffer = universe.cells.clone();
+        }
+    }
+}
+
+impl<C: CellState + Send + Sync, N> CellRuleEngine<C, N> {
+    /// Evaluates the rule for all cells, dividing the rows of the universe between the engine's
+    /// workers, and swaps in the new states.
+    pub fn update_cells_parallel<E: EntityState<C>, M: MutEntityState>(
+        &mut self,
+        universe: &mut Universe2D<C, E, M>,
+    ) {
+        self.prepare_back_buffer(universe);
+        let (width, height, boundary) = (
+            universe.get_width(),
+            universe.get_height(),
+            universe.get_boundary(),
+        );
+        let (rule, cells) = (self.rule, &universe.cells);
+
+        self.workers
+            .for_each_rows(&mut self.back_buffer, width, |rows, first_row| {
+                update_rows(rule, cells, rows, first_row, width, height, boundary)
+            });
+        mem::swap(&mut universe.cells, &mut self.back_buffer);
+    }
+}
+
+/// Evaluates the rule for a contiguous run of full rows starting at `first_row`, writing the new
+/// states into `dst`.
+fn update_rows<C: CellState>(
+    rule: CellRule<C>,
+    cells: &[Cell<C>],
+    dst: &mut [Cell<C>],
+    first_row: usize,
+    width: usize,
+    height: usize,
+    boundary: BoundaryPolicy,
+) {
+    for (i, dst_cell) in dst.iter_mut().enumerate() {
+        let (x, y) = (i % width, first_row + i / width);
+        let neighborhood = CellNeighborhood::new(cells, x, y, width, height, boundary);
+        dst_cell.state = rule(&cells[get_index(x, y, width)], neighborhood);
+    }
+}
+
+impl<
+        C: CellState + Send + Sync,
+        E: EntityState<C>,
+        M: MutEntityState,
+        CA: CellAction<C>,
+        EA: EntityAction<C, E>,
+        N: Engine<C, E, M, CA, EA, Universe2D<C, E, M>>,
+    > Engine<C, E, M, CA, EA, Universe2D<C, E, M>> for CellRuleEngine<C, N>
+{
+    fn step(&mut self, universe: &mut Universe2D<C, E, M>) {
+        if self.workers.worker_count() > 1 {
+            self.update_cells_parallel(universe);
+        } else {
+            self.update_cells(universe);
+        }
+        self.inner.step(universe);
+    }
+}
+
+#[test]
+fn game_of_life_blinker() {
+    use test_util::{CA, CS, EA, ES, MES};
+    use universe::Universe2DConf;
+
+    /// Stands in for an engine driving entities
+    struct StepCounter(usize);
+    impl Engine<CS, ES, MES, CA, EA, Universe2D<CS, ES, MES>> for StepCounter {
+        fn step(&mut self, _: &mut Universe2D<CS, ES, MES>) { self.0 += 1; }
+    }
+
+    fn life(cell: &Cell<CS>, neighborhood: CellNeighborhood<CS>) -> CS {
+        match neighborhood.count(1, |state| state.0 == 1) {
+            3 => CS(1),
+            2 => cell.state,
+            _ => CS(0),
+        }
+    }
+
+    let alive_cells = |universe: &Universe2D<CS, ES, MES>| -> Vec<usize> {
+        (0..universe.cells.len())
+            .filter(|&i| universe.cells[i].state.0 == 1)
+            .collect()
+    };
+    let horizontal = vec![get_index(1, 2, 5), get_index(2, 2, 5), get_index(3, 2, 5)];
+    let vertical = vec![get_index(2, 1, 5), get_index(2, 2, 5), get_index(2, 3, 5)];
+
+    for &threads in &[1, 3] {
+        let mut universe: Universe2D<CS, ES, MES> = Universe2D::uninitialized();
+        universe.conf = Universe2DConf::square(5);
+        universe.cells = vec![Cell { state: CS(0) }; 25];
+        for &i in &horizontal {
+            universe.cells[i].state = CS(1);
+        }
+
+        let mut engine = CellRuleEngine::new(life, StepCounter(0)).parallel(threads);
+        engine.step(&mut universe);
+        assert_eq!(alive_cells(&universe), vertical);
+        engine.step(&mut universe);
+        assert_eq!(alive_cells(&universe), horizontal);
+        assert_eq!(engine.inner.0, 2);
+    }
+}
@@ -0,0 +1,283 @@
+//! Resolves conflicts between actions that are produced during the same tick.  Since all of a
+//! tick's actions are buffered before any of them are applied, two entities may well attempt to
+//! move into the same cell or consume the same entity.  A `ConflictResolver` groups buffered
+//! actions by the cell or entity that they target and applies a `ConflictPolicy` to each group
+//! that contains more than one action, leaving the engine to apply only the surviving actions.
+//!
+//! Resolution only depends on the actions and the state of the universe, so it can be used by the
+//! serial engine as well as by engines that collect actions from multiple threads.
+
+use std::collections::HashMap;
+
+use rand::Rng;
+
+use action::{Action, CellAction, EntityAction, OwnedAction, SelfAction};
+use cell::CellState;
+use container::EntityHandle;
+use entity::{EntityState, MutEntityState};
+use rng::{mix64, StreamKey};
+use universe::{Universe, Universe2D};
+use util::{get_coords, get_index};
+
+/// The RNG stream from which the random conflict policy draws by default
+pub const CONFLICT_RNG_STREAM: u64 = 0xC0_4F_11C7;
+
+/// The object that an action modifies.  Actions with the same target conflict with each other.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ConflictTarget<I> {
+    Cell(I),
+    Entity(EntityHandle),
+}
+
+impl<I: StreamKey> StreamKey for ConflictTarget<I> {
+    fn stream_key(&self) -> u64 {
+        // a cell and an entity with the same index select different streams
+        match *self {
+            ConflictTarget::Cell(ref coord) => coord.stream_key(),
+            ConflictTarget::Entity(handle) => !handle.stream_key(),
+        }
+    }
+}
+
+/// Determines the target of an action, returning `None` for actions that can't conflict.
+pub type TargetFn<C, E, M, CA, EA, U> =
+    fn(&U, &OwnedAction<C, E, CA, EA, <U as Universe<C, E, M>>::Coord>)
+        -> Option<ConflictTarget<<U as Universe<C, E, M>>::Coord>>;
+
+/// Calculates the priority of an action; higher priorities win conflicts.
+pub type PriorityFn<C, E, M, CA, EA, U> =
+    fn(&U, &OwnedAction<C, E, CA, EA, <U as Universe<C, E, M>>::Coord>) -> i64;
+
+/// Decides which of a group of conflicting actions are applied.
+pub enum ConflictPolicy<
+    C: CellState,
+    E: EntityState<C>,
+    M: MutEntityState,
+    CA: CellAction<C>,
+    EA: EntityAction<C, E>,
+    U: Universe<C, E, M>,
+> {
+    /// The action that was produced first is applied.
+    FirstWins,
+    /// A single action is chosen at random using an RNG stream derived from the universe's seed,
+    /// the current tick and the target, so the result is reproducible.
+    Random,
+    /// The action with the highest priority is applied; ties are won by the action that was
+    /// produced first.
+    Priority(PriorityFn<C, E, M, CA, EA, U>),
+    /// None of the conflicting actions are applied.
+    AllFail,
+}
+
+/// Returns the target of cell and entity actions.  Self actions never conflict.
+pub fn default_target<
+    C: CellState,
+    E: EntityState<C>,
+    M: MutEntityState,
+    CA: CellAction<C>,
+    EA: EntityAction<C, E>,
+    U: Universe<C, E, M>,
+>(
+    _: &U,
+    owned_action: &OwnedAction<C, E, CA, EA, U::Coord>,
+) -> Option<ConflictTarget<U::Coord>> {
+    match owned_action.action {
+        Action::CellAction { universe_index, .. } => Some(ConflictTarget::Cell(universe_index)),
+        Action::EntityAction { target_entity, .. } => Some(ConflictTarget::Entity(target_entity)),
+        Action::SelfAction(_) => None,
+    }
+}
+
+/// Like `default_target`, but additionally treats the cell that a `Translate` or `MoveTo` self
+/// action would move its entity into as its target, so that only one entity can move into a cell
+/// per tick.  Moves that would be dropped by the universe's boundary policy don't conflict.
+pub fn destination_target<
+    C: CellState,
+    E: EntityState<C>,
+    M: MutEntityState,
+    CA: CellAction<C>,
+    EA: EntityAction<C, E>,
+>(
+    universe: &Universe2D<C, E, M>,
+    owned_action: &OwnedAction<C, E, CA, EA>,
+) -> Option<ConflictTarget<usize>> {
+    let (width, height, boundary) = (
+        universe.get_width(),
+        universe.get_height(),
+        universe.get_boundary(),
+    );
+
+    match owned_action.action {
+        Action::SelfAction(SelfAction::Translate(x_offset, y_offset)) => {
+            let (_, universe_index) = universe.entities.get_verify(owned_action.source_entity)?;
+            let (cur_x, cur_y) = get_coords(universe_index, width);
+            let dst_x = boundary.resolve_move(cur_x as isize + x_offset, width)?;
+            let dst_y = boundary.resolve_move(cur_y as isize + y_offset, height)?;
+            Some(ConflictTarget::Cell(get_index(dst_x, dst_y, width)))
+        },
+        Action::SelfAction(SelfAction::MoveTo(x, y)) if x < width && y < height =>
+            Some(ConflictTarget::Cell(get_index(x, y, width))),
+        _ => default_target(universe, owned_action),
+    }
+}
+
+pub struct ConflictResolver<
+    C: CellState,
+    E: EntityState<C>,
+    M: MutEntityState,
+    CA: CellAction<C>,
+    EA: EntityAction<C, E>,
+    U: Universe<C, E, M>,
+> {
+    pub policy: ConflictPolicy<C, E, M, CA, EA, U>,
+    pub target: TargetFn<C, E, M, CA, EA, U>,
+    /// Combined with the target of a group of actions to select the RNG stream used by the random
+    /// policy
+    pub rng_stream: u64,
+}
+
+impl<
+        C: CellState,
+        E: EntityState<C>,
+        M: MutEntityState,
+        CA: CellAction<C>,
+        EA: EntityAction<C, E>,
+        U: Universe<C, E, M>,
+    > ConflictResolver<C, E, M, CA, EA, U>
+{
+    /// Creates a resolver that applies `policy` to actions that conflict according to
+    /// `default_target`.
+    pub fn new(policy: ConflictPolicy<C, E, M, CA, EA, U>) -> Self {
+        ConflictResolver {
+            policy,
+            target: default_target,
+            rng_stream: CONFLICT_RNG_STREAM,
+        }
+    }
+
+    /// Replaces the function used to determine which actions conflict.
+    pub fn with_target(mut self, target: TargetFn<C, E, M, CA, EA, U>) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Returns the actions that survive conflict resolution in the order in which they were
+    /// produced.  Actions without a target are always kept.
+    pub fn resolve<'a>(
+        &self,
+        universe: &U,
+        actions: &'a [OwnedAction<C, E, CA, EA, U::Coord>],
+    ) -> Vec<&'a OwnedAction<C, E, CA, EA, U::Coord>> {
+        // groups are kept in order of first appearance so that resolution is deterministic
+        let mut group_indexes: HashMap<ConflictTarget<U::Coord>, usize> = HashMap::new();
+        let mut groups: Vec<(ConflictTarget<U::Coord>, Vec<usize>)> = Vec::new();
+        for (i, owned_action) in actions.iter().enumerate() {
+            if let Some(target) = (self.target)(universe, owned_action) {
+                let group_index = *group_indexes.entry(target).or_insert_with(|| {
+                    groups.push((target, Vec::new()));
+                    groups.len() - 1
+                });
+                groups[group_index].1.push(i);
+            }
+        }
+
+        let mut applied = vec![true; actions.len()];
+        for (target, members) in groups {
+            if members.len() < 2 {
+                continue;
+            }
+
+            let winner = match self.policy {
+                ConflictPolicy::FirstWins => Some(members[0]),
+                ConflictPolicy::Random => {
+                    let stream = mix64(self.rng_stream ^ target.stream_key());
+                    let mut rng = universe.get_entities().rng(stream);
+                    Some(members[rng.gen_range(0, members.len())])
+                },
+                ConflictPolicy::Priority(priority) => {
+                    let mut best = members[0];
+                    let mut best_priority = priority(universe, &actions[best]);
+                    for &i in &members[1..] {
+                        let cur_priority = priority(universe, &actions[i]);
+                        if cur_priority > best_priority {
+                            best = i;
+                            best_priority = cur_priority;
+                        }
+                    }
+                    Some(best)
+                },
+                ConflictPolicy::AllFail => None,
+            };
+
+            for i in members {
+                applied[i] = Some(i) == winner;
+            }
+        }
+
+        actions
+            .iter()
+            .zip(applied)
+            .filter(|&(_, applied)| applied)
+            .map(|(owned_action, _)| owned_action)
+            .collect()
+    }
+}
+
+#[test]
+fn conflicting_moves_are_resolved() {
+    use cell::Cell;
+    use test_util::{new_entity, CA, CS, EA, ES, MES};
+    use universe::Universe2DConf;
+
+    type U = Universe2D<CS, ES, MES>;
+    type Resolver = ConflictResolver<CS, ES, MES, CA, EA, U>;
+
+    let mut universe: U = Universe2D::uninitialized();
+    universe.conf = Universe2DConf::square(5);
+    universe.cells = vec![Cell { state: CS(0) }; 25];
+    // three entities around (2, 2) that all try to move into it
+    let left = universe.entities.insert(new_entity(1), get_index(1, 2, 5));
+    let right = universe.entities.insert(new_entity(3), get_index(3, 2, 5));
+    let above = universe.entities.insert(new_entity(2), get_index(2, 1, 5));
+    let other = universe.entities.insert(new_entity(0), get_index(0, 0, 5));
+
+    let moves: Vec<OwnedAction<CS, ES, CA, EA>> = vec![
+        (left, SelfAction::translate(1, 0)),
+        (other, SelfAction::translate(0, 1)),
+        (right, SelfAction::translate(-1, 0)),
+        (above, SelfAction::move_to(2, 2)),
+    ]
+    .into_iter()
+    .map(|(source_entity, self_action)| OwnedAction {
+        source_entity,
+        action: Action::SelfAction(self_action),
+    })
+    .collect();
+    let sources = |resolver: &Resolver| -> Vec<EntityHandle> {
+        resolver
+            .resolve(&universe, &moves)
+            .into_iter()
+            .map(|owned_action| owned_action.source_entity)
+            .collect()
+    };
+
+    // self actions don't conflict by default
+    assert_eq!(sources(&Resolver::new(ConflictPolicy::FirstWins)).len(), 4);
+
+    let first_wins = Resolver::new(ConflictPolicy::FirstWins).with_target(destination_target);
+    assert_eq!(sources(&first_wins), vec![left, other]);
+    let all_fail = Resolver::new(ConflictPolicy::AllFail).with_target(destination_target);
+    assert_eq!(sources(&all_fail), vec![other]);
+    fn entity_state(universe: &U, owned_action: &OwnedAction<CS, ES, CA, EA>) -> i64 {
+        universe.entities.get(owned_action.source_entity).unwrap().state.0 as i64
+    }
+    let priority =
+        Resolver::new(ConflictPolicy::Priority(entity_state)).with_target(destination_target);
+    assert_eq!(sources(&priority), vec![other, right]);
+
+    let random = Resolver::new(ConflictPolicy::Random).with_target(destination_target);
+    let random_sources = sources(&random);
+    assert_eq!(random_sources.len(), 2);
+    assert!(random_sources.contains(&other));
+    assert_eq!(random_sources, sources(&random));
+}
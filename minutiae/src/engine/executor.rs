@@ -0,0 +1,530 @@
+//! A reusable implementation of action execution.  The built-in self actions are applied directly,
+//! leaving only the actions whose meaning is specific to a simulation to be implemented by an
+//! `ActionHandler`.  Replacing state and suicide are applied the same way in every universe, while
+//! moving and spawning entities are applied by the universe itself through `BuiltinSelfActions`:
+//!
+//!  - `Universe2D`: `Translate`, `MoveTo` and `Spawn`
+//!  - `Universe1D`: `Translate` along the x axis
+//!  - `Universe3D`: `Translate3D`
+//!  - `HexUniverse`: `TranslateHex`
+//!  - `GraphUniverse`: `TraverseEdge`
+//!  - `ChunkedUniverse`: `Translate`, `MoveTo` and `Spawn`
+//!
+//! Built-in actions that don't apply to the universe being simulated are passed to
+//! `ActionHandler::exec_unsupported_self_action`.
+//!
+//! Engines can call `exec_actions` from their own `exec_actions` implementation, optionally
+//! passing it a `ConflictResolver` that decides which of several conflicting actions are applied.
+
+use super::accumulator::accumulate_cell_actions;
+use super::conflict::ConflictResolver;
+use action::{Action, CellAction, EntityAction, OwnedAction, SelfAction};
+use cell::CellState;
+use container::EntityHandle;
+use entity::{Entity, EntityState, MutEntityState};
+use lifecycle::DeathCause;
+use universe::chunked::{CellGenerator, ChunkedUniverse, P2D};
+use universe::graph::GraphUniverse;
+use universe::hex::HexUniverse;
+use universe::universe1d::Universe1D;
+use universe::universe3d::Universe3D;
+use universe::{Universe, Universe2D};
+use util::{get_coords, get_index};
+
+/// Applies the actions that the default executor can't apply itself.
+pub trait ActionHandler<
+    C: CellState,
+    E: EntityState<C>,
+    M: MutEntityState,
+    CA: CellAction<C>,
+    EA: EntityAction<C, E>,
+    U: Universe<C, E, M> = Universe2D<C, E, M>,
+>
+{
+    /// Applies a cell action targeting `universe_index` that was produced by the `source` entity.
+    /// This is also where entities write to the universe's field layers.
+    fn exec_cell_action(
+        &mut self,
+        universe: &mut U,
+        source: EntityHandle,
+        action: &CA,
+        universe_index: U::Coord,
+    );
+
+    /// Applies a `SelfAction::Custom` produced by the `source` entity.
+    fn exec_custom_self_action(&mut self, universe: &mut U, source: EntityHandle, action: &EA);
+
+    /// Applies an entity action produced by the `source` entity and targeting the `target` entity.
+    /// Either of them may have been removed by actions that were applied earlier in the tick.
+    fn exec_entity_action(
+        &mut self,
+        universe: &mut U,
+        source: EntityHandle,
+        action: &EA,
+        target: EntityHandle,
+    );
+
+    /// Called with built-in self actions that don't apply to the kind of universe being simulated,
+    /// such as `Translate3D` in a two-dimensional universe.  The default implementation ignores
+    /// them, the same way that moves out of bounds are dropped; override it to report them instead.
+    fn exec_unsupported_self_action(
+        &mut self,
+        _universe: &mut U,
+        _source: EntityHandle,
+        _action: &SelfAction<C, E, EA>,
+    ) {
+    }
+}
+
+/// A universe that knows how to apply the built-in self actions that move and spawn entities.
+pub trait BuiltinSelfActions<C: CellState, E: EntityState<C>, M: MutEntityState>:
+    Universe<C, E, M>
+{
+    /// Applies a built-in self action, returning `false` without doing anything if the action has
+    /// no meaning in this kind of universe.  Actions of entities that no longer exist, as well as
+    /// moves that are prevented by the universe's boundaries, are ignored.
+    fn exec_builtin_self_action<EA: EntityAction<C, E>>(
+        &mut self,
+        source: EntityHandle,
+        self_action: &SelfAction<C, E, EA>,
+    ) -> bool;
+}
+
+/// Applies all of the actions produced during a tick, first the cell actions, then the self actions
+/// and finally the entity actions, each in the order in which they were produced.  Cell actions
+/// targeting the same cell are accumulated before they're handed to the handler.
+///
+/// If a `resolver` is supplied, conflicts among each of the three kinds of actions are resolved
+/// against the state of the universe before any of them are applied, and only the surviving
+/// actions are applied.
+pub fn exec_actions<
+    C: CellState,
+    E: EntityState<C>,
+    M: MutEntityState,
+    CA: CellAction<C> + Clone,
+    EA: EntityAction<C, E> + Clone,
+    U: BuiltinSelfActions<C, E, M>,
+    H: ActionHandler<C, E, M, CA, EA, U>,
+>(
+    handler: &mut H,
+    universe: &mut U,
+    resolver: Option<&ConflictResolver<C, E, M, CA, EA, U>>,
+    cell_actions: &[OwnedAction<C, E, CA, EA, U::Coord>],
+    self_actions: &[OwnedAction<C, E, CA, EA, U::Coord>],
+    entity_actions: &[OwnedAction<C, E, CA, EA, U::Coord>],
+) {
+    let cell_actions = accumulate_cell_actions(cell_actions);
+    let resolved = {
+        let universe = &*universe;
+        let resolve = |actions| match resolver {
+            Some(resolver) => resolver.resolve(universe, actions),
+            None => actions.iter().collect(),
+        };
+        let mut resolved = resolve(&cell_actions);
+        resolved.extend(resolve(self_actions));
+        resolved.extend(resolve(entity_actions));
+        resolved
+    };
+
+    for owned_action in resolved {
+        exec_action(handler, universe, owned_action);
+    }
+}
+
+/// Applies a single action, delegating to the handler if it isn't a built-in self action.
+pub fn exec_action<
+    C: CellState,
+    E: EntityState<C>,
+    M: MutEntityState,
+    CA: CellAction<C>,
+    EA: EntityAction<C, E>,
+    U: BuiltinSelfActions<C, E, M>,
+    H: ActionHandler<C, E, M, CA, EA, U>,
+>(
+    handler: &mut H,
+    universe: &mut U,
+    owned_action: &OwnedAction<C, E, CA, EA, U::Coord>,
+) {
+    let source = owned_action.source_entity;
+    match owned_action.action {
+        Action::CellAction {
+            ref action,
+            universe_index,
+        } => handler.exec_cell_action(universe, source, action, universe_index),
+        Action::EntityAction {
+            ref action,
+            target_entity,
+        } => handler.exec_entity_action(universe, source, action, target_entity),
+        Action::SelfAction(SelfAction::Custom(ref action)) =>
+            handler.exec_custom_self_action(universe, source, action),
+        Action::SelfAction(SelfAction::SetState(ref state)) =>
+            if let Some(entity) = universe.get_entities_mut().get_mut(source) {
+                entity.state = state.clone();
+            },
+        Action::SelfAction(SelfAction::Suicide) => {
+            universe
+                .get_entities_mut()
+                .remove_with_cause(source, DeathCause::Suicide);
+        },
+        Action::SelfAction(ref self_action) =>
+            if !universe.exec_builtin_self_action(source, self_action) {
+                handler.exec_unsupported_self_action(universe, source, self_action);
+            },
+    }
+}
+
+impl<C: CellState, E: EntityState<C>, M: MutEntityState> BuiltinSelfActions<C, E, M>
+    for Universe2D<C, E, M>
+{
+    fn exec_builtin_self_action<EA: EntityAction<C, E>>(
+        &mut self,
+        source: EntityHandle,
+        self_action: &SelfAction<C, E, EA>,
+    ) -> bool {
+        let (width, height, boundary) = (self.get_width(), self.get_height(), self.get_boundary());
+
+        match *self_action {
+            SelfAction::Translate(x_offset, y_offset) =>
+                self.translate_entity(x_offset, y_offset, source),
+            SelfAction::MoveTo(x, y) =>
+                if x < width && y < height {
+                    self.entities.move_entity(source, get_index(x, y, width));
+                },
+            SelfAction::Spawn(ref state, x_offset, y_offset) => {
+                let universe_index = match self.entities.get_verify(source) {
+                    Some((_, universe_index)) => universe_index,
+                    None => return true,
+                };
+
+                // children that would be spawned out of bounds aren't spawned at all
+                let (cur_x, cur_y) = get_coords(universe_index, width);
+                let dst_x = boundary.resolve_move(cur_x as isize + x_offset, width);
+                let dst_y = boundary.resolve_move(cur_y as isize + y_offset, height);
+                if let (Some(dst_x), Some(dst_y)) = (dst_x, dst_y) {
+                    let child = Entity::new(state.clone(), M::default());
+                    self.entities
+                        .insert_child(child, get_index(dst_x, dst_y, width), Some(source));
+                }
+            },
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl<C: CellState, E: EntityState<C>, M: MutEntityState> BuiltinSelfActions<C, E, M>
+    for Universe1D<C, E, M>
+{
+    fn exec_builtin_self_action<EA: EntityAction<C, E>>(
+        &mut self,
+        source: EntityHandle,
+        self_action: &SelfAction<C, E, EA>,
+    ) -> bool {
+        match *self_action {
+            SelfAction::Translate(offset, 0) => self.translate_entity(offset, source),
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl<C: CellState, E: EntityState<C>, M: MutEntityState> BuiltinSelfActions<C, E, M>
+    for Universe3D<C, E, M>
+{
+    fn exec_builtin_self_action<EA: EntityAction<C, E>>(
+        &mut self,
+        source: EntityHandle,
+        self_action: &SelfAction<C, E, EA>,
+    ) -> bool {
+        match *self_action {
+            SelfAction::Translate3D(x_offset, y_offset, z_offset) =>
+                self.translate_entity(x_offset, y_offset, z_offset, source),
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl<C: CellState, E: EntityState<C>, M: MutEntityState> BuiltinSelfActions<C, E, M>
+    for HexUniverse<C, E, M>
+{
+    fn exec_builtin_self_action<EA: EntityAction<C, E>>(
+        &mut self,
+        source: EntityHandle,
+        self_action: &SelfAction<C, E, EA>,
+    ) -> bool {
+        match *self_action {
+            SelfAction::TranslateHex(direction, distance) =>
+                self.translate_entity(direction, distance, source),
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl<C: CellState, E: EntityState<C>, M: MutEntityState> BuiltinSelfActions<C, E, M>
+    for GraphUniverse<C, E, M>
+{
+    fn exec_builtin_self_action<EA: EntityAction<C, E>>(
+        &mut self,
+        source: EntityHandle,
+        self_action: &SelfAction<C, E, EA>,
+    ) -> bool {
+        match *self_action {
+            // moves along edges that don't exist are ignored like moves out of bounds
+            SelfAction::TraverseEdge(dst_node) => {
+                self.traverse_edge(dst_node, source);
+            },
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl<
+        C: CellState,
+        E: EntityState<C>,
+        M: MutEntityState,
+        G: CellGenerator<C, E, M> + Default,
+    > BuiltinSelfActions<C, E, M> for ChunkedUniverse<C, E, M, G>
+{
+    fn exec_builtin_self_action<EA: EntityAction<C, E>>(
+        &mut self,
+        source: EntityHandle,
+        self_action: &SelfAction<C, E, EA>,
+    ) -> bool {
+        match *self_action {
+            SelfAction::Translate(x_offset, y_offset) =>
+                self.translate_entity(x_offset as i64, y_offset as i64, source),
+            SelfAction::MoveTo(x, y) => {
+                self.move_entity(source, P2D::new(x as i64, y as i64));
+            },
+            SelfAction::Spawn(ref state, x_offset, y_offset) => {
+                let dst = match self.entities.get_verify(source) {
+                    Some((_, src)) => src.offset(x_offset as i64, y_offset as i64),
+                    None => return true,
+                };
+                if let Some(dst) = dst {
+                    let child = Entity::new(state.clone(), M::default());
+                    self.insert_entity(child, dst, Some(source));
+                }
+            },
+            _ => return false,
+        }
+        true
+    }
+}
+
+#[test]
+fn default_self_action_execution() {
+    use cell::Cell;
+    use test_util::{new_entity, NoopHandler, CA, CS, EA, ES, MES};
+    use universe::Universe2DConf;
+
+    let mut universe: Universe2D<CS, ES, MES> = Universe2D::uninitialized();
+    universe.conf = Universe2DConf::square(5);
+    universe.cells = vec![Cell { state: CS(0) }; 25];
+    let entity = universe.entities.insert(new_entity(0), get_index(1, 1, 5));
+
+    let owned = |action| OwnedAction {
+        source_entity: entity,
+        action,
+    };
+    let self_actions = vec![
+        owned(Action::SelfAction(SelfAction::translate(1, 2))),
+        owned(Action::SelfAction(SelfAction::spawn(ES(1), -1, 0))),
+        owned(Action::SelfAction(SelfAction::spawn(ES(2), 0, 10))),
+        owned(Action::SelfAction(SelfAction::set_state(ES(3)))),
+        owned(Action::SelfAction(SelfAction::Custom(EA))),
+        // doesn't apply to two-dimensional universes, so it's ignored
+        owned(Action::SelfAction(SelfAction::translate_3d(1, 1, 1))),
+    ];
+    let cell_actions = vec![owned(Action::CellAction {
+        action: CA,
+        universe_index: 0,
+    })];
+    let entity_actions = vec![owned(Action::EntityAction {
+        action: EA,
+        target_entity: entity,
+    })];
+
+    let mut handler = NoopHandler::default();
+    exec_actions(
+        &mut handler,
+        &mut universe,
+        None,
+        &cell_actions,
+        &self_actions,
+        &entity_actions,
+    );
+
+    assert_eq!(
+        (handler.cell_actions, handler.custom_self_actions, handler.entity_actions),
+        (1, 1, 1)
+    );
+    assert_eq!(universe.entities.len(), 2);
+    let (parent, universe_index) = universe.entities.get_verify(entity).unwrap();
+    assert_eq!((parent.state.clone(), universe_index), (ES(3), get_index(2, 3, 5)));
+    let child = universe.entities.get_entities_at(get_index(1, 3, 5))[0];
+    assert_eq!(universe.entities.get(child).unwrap().state, ES(1));
+
+    exec_action(
+        &mut handler,
+        &mut universe,
+        &owned(Action::SelfAction(SelfAction::move_to(4, 0))),
+    );
+    assert_eq!(universe.entities.get_entities_at(get_index(4, 0, 5)), &[entity]);
+    exec_action(
+        &mut handler,
+        &mut universe,
+        &owned(Action::SelfAction(SelfAction::Suicide)),
+    );
+    assert!(universe.entities.get(entity).is_none());
+}
+
+#[test]
+fn field_deposits_through_cell_actions() {
+    use cell::Cell;
+    use test_util::{new_entity, CS, EA, ES, MES};
+    use universe::Universe2DConf;
+
+    /// Adds pheromone to the targeted cell
+    #[derive(Clone, Debug)]
+    struct Deposit(f32);
+    impl CellAction<CS> for Deposit {
+        fn accumulate(&mut self, other: &Self) -> bool {
+            self.0 += other.0;
+            true
+        }
+    }
+
+    struct Handler {
+        pheromone: usize,
+        applied: usize,
+    }
+    impl ActionHandler<CS, ES, MES, Deposit, EA> for Handler {
+        fn exec_cell_action(
+            &mut self,
+            universe: &mut Universe2D<CS, ES, MES>,
+            _: EntityHandle,
+            action: &Deposit,
+            universe_index: usize,
+        ) {
+            universe.fields.add(self.pheromone, universe_index, action.0);
+            self.applied += 1;
+        }
+
+        fn exec_custom_self_action(
+            &mut self,
+            _: &mut Universe2D<CS, ES, MES>,
+            _: EntityHandle,
+            _: &EA,
+        ) {
+        }
+
+        fn exec_entity_action(
+            &mut self,
+            _: &mut Universe2D<CS, ES, MES>,
+            _: EntityHandle,
+            _: &EA,
+            _: EntityHandle,
+        ) {
+        }
+    }
+
+    let mut universe: Universe2D<CS, ES, MES> = Universe2D::uninitialized();
+    universe.conf = Universe2DConf::square(3);
+    universe.cells = vec![Cell { state: CS(0) }; 9];
+    let pheromone = universe.add_field("pheromone", 0.);
+    let entity = universe.entities.insert(new_entity(0), 4);
+
+    let deposit = |amount, universe_index| OwnedAction {
+        source_entity: entity,
+        action: Action::CellAction {
+            action: Deposit(amount),
+            universe_index,
+        },
+    };
+    let cell_actions = vec![deposit(1., 4), deposit(0.5, 4), deposit(2., 0)];
+    let mut handler = Handler {
+        pheromone,
+        applied: 0,
+    };
+    exec_actions(&mut handler, &mut universe, None, &cell_actions, &[], &[]);
+
+    // the two deposits into the same cell are merged into one
+    assert_eq!(handler.applied, 2);
+    assert_eq!(universe.fields.value(pheromone, 4), Some(1.5));
+    assert_eq!(universe.fields.value(pheromone, 0), Some(2.));
+    assert_eq!(universe.fields.value(pheromone, 1), Some(0.));
+}
+
+#[test]
+fn conflicts_are_resolved_before_execution() {
+    use cell::Cell;
+    use engine::conflict::{destination_target, ConflictPolicy};
+    use test_util::{new_entity, NoopHandler, CA, CS, EA, ES, MES};
+    use universe::Universe2DConf;
+
+    let mut universe: Universe2D<CS, ES, MES> = Universe2D::uninitialized();
+    universe.conf = Universe2DConf::square(3);
+    universe.cells = vec![Cell { state: CS(0) }; 9];
+    let left = universe.entities.insert(new_entity(0), get_index(0, 1, 3));
+    let right = universe.entities.insert(new_entity(0), get_index(2, 1, 3));
+    // both entities try to move into the center, but only the first of them is allowed to
+    let moves: Vec<OwnedAction<CS, ES, CA, EA>> = vec![
+        OwnedAction {
+            source_entity: right,
+            action: Action::SelfAction(SelfAction::translate(-1, 0)),
+        },
+        OwnedAction {
+            source_entity: left,
+            action: Action::SelfAction(SelfAction::translate(1, 0)),
+        },
+    ];
+
+    let resolver =
+        ConflictResolver::new(ConflictPolicy::FirstWins).with_target(destination_target);
+    exec_actions(
+        &mut NoopHandler::default(),
+        &mut universe,
+        Some(&resolver),
+        &[],
+        &moves,
+        &[],
+    );
+    assert_eq!(universe.entities.get_entities_at(get_index(1, 1, 3)), &[right]);
+    assert_eq!(universe.entities.get_entities_at(get_index(0, 1, 3)), &[left]);
+}
+
+#[test]
+fn builtin_self_actions_of_other_universes() {
+    use cell::Cell;
+    use test_util::{new_entity, NoopHandler, CA, CS, EA, ES, MES};
+    use universe::graph::Graph;
+
+    // A path 0 - 1 - 2
+    let mut universe: GraphUniverse<CS, ES, MES> = GraphUniverse::uninitialized();
+    universe.graph = Graph::from_edges(3, &[(0, 1), (1, 2)], false);
+    universe.cells = vec![Cell { state: CS(0) }; 3];
+    let entity = universe.entities.insert(new_entity(0), 0);
+
+    let owned = |action| OwnedAction {
+        source_entity: entity,
+        action: Action::SelfAction(action),
+    };
+    let self_actions: Vec<OwnedAction<CS, ES, CA, EA>> = vec![
+        owned(SelfAction::traverse_edge(1)),
+        // there is no edge between nodes 1 and 1, so the entity stays where it is
+        owned(SelfAction::traverse_edge(1)),
+        owned(SelfAction::translate(1, 0)),
+        owned(SelfAction::set_state(ES(1))),
+        owned(SelfAction::traverse_edge(2)),
+    ];
+
+    let mut handler = NoopHandler::default();
+    exec_actions(&mut handler, &mut universe, None, &[], &self_actions, &[]);
+    assert_eq!(handler.unsupported_self_actions, vec!["Translate(1, 0)"]);
+    let (entity, node) = universe.entities.get_verify(entity).unwrap();
+    assert_eq!((entity.state.clone(), node), (ES(1), 2));
+}
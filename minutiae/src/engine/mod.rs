@@ -8,9 +8,13 @@ use entity::{EntityState, MutEntityState};
 use action::{CellAction, EntityAction};
 
 pub mod serial;
-// 90% sure this hella broken, and the code is terrible anyway.
-// pub mod parallel;
+pub mod parallel;
 pub mod iterator;
+pub mod executor;
+pub mod conflict;
+pub mod accumulator;
+pub mod cell_rule;
+pub mod row_workers;
 
 pub trait Engine<
     C: CellState,
@@ -1,9 +1,17 @@
 //! Engine that makes use of multiple worker threads to enable entity drivers to be evaluated
-//! concurrently.
+//! concurrently.  Entities are handed out to the workers one at a time, and every worker collects
+//! the actions of the entities that it drove into its own buffers.  Once all entities have been
+//! driven, the actions are put back into the order in which the serial engine would have produced
+//! them and applied on the calling thread.  If an entity driver panics, the panic is re-raised on
+//! the calling thread once all of the workers have finished driving entities.
+//!
+//! Like the serial engine, it delivers the messages that entities send each other if it's given a
+//! `Mailbox` to hold them; all workers read the inboxes delivered at the end of the last tick.
 
 use std::{
-    fmt::Debug,
+    collections::HashMap,
     marker::PhantomData,
+    panic::{self, AssertUnwindSafe},
     sync::{
         atomic::{AtomicUsize, Ordering},
         mpsc::{sync_channel, Receiver, SyncSender},
@@ -13,407 +21,597 @@ use std::{
 };
 
 use num_cpus;
-use uuid::Uuid;
 
 use action::{Action, CellAction, EntityAction, OwnedAction, SelfAction};
-use cell::{Cell, CellState};
-use container::EntityContainer;
+use cell::CellState;
+use container::EntityHandle;
 use engine::Engine;
-use entity::{Entity, EntityState, MutEntityState};
+use entity::{EntityState, MutEntityState};
+use mailbox::{Envelope, Mailbox, Recipient, SentMessage};
 use universe::Universe;
-
-type ActionBufs<C, E, CA, EA> = (
-    Vec<OwnedAction<C, E, CA, EA>>,
-    usize,
-    Vec<OwnedAction<C, E, CA, EA>>,
-    usize,
-    Vec<OwnedAction<C, E, CA, EA>>,
-    usize,
+use view::EntityView;
+
+/// Determines the actions taken by an entity given its view of the universe, which includes the
+/// messages that were sent to it during the last tick.  `T` is the type of those messages.
+pub type EntityDriver<C, E, M, CA, EA, U, T = ()> = fn(
+    view: &EntityView<C, E, M, U, T>,
+    cell_action_executor: &mut FnMut(CA, <U as Universe<C, E, M>>::Coord),
+    self_action_executor: &mut FnMut(SelfAction<C, E, EA>),
+    entity_action_executor: &mut FnMut(EA, EntityHandle),
+    message_executor: &mut FnMut(Recipient, T),
 );
 
+/// The inboxes of all entities, shared by the workers during a tick
+type Inboxes<T> = HashMap<EntityHandle, Vec<Envelope<T>>>;
+
+/// The actions and messages produced by a single worker thread during a tick
+pub struct ActionBufs<
+    C: CellState,
+    E: EntityState<C>,
+    CA: CellAction<C>,
+    EA: EntityAction<C, E>,
+    I,
+    T,
+> {
+    cell_actions: Vec<OwnedAction<C, E, CA, EA, I>>,
+    self_actions: Vec<OwnedAction<C, E, CA, EA, I>>,
+    entity_actions: Vec<OwnedAction<C, E, CA, EA, I>>,
+    messages: Vec<SentMessage<T>>,
+}
+
+impl<C: CellState, E: EntityState<C>, CA: CellAction<C>, EA: EntityAction<C, E>, I, T>
+    ActionBufs<C, E, CA, EA, I, T>
+{
+    fn new() -> Self {
+        ActionBufs {
+            cell_actions: Vec::new(),
+            self_actions: Vec::new(),
+            entity_actions: Vec::new(),
+            messages: Vec::new(),
+        }
+    }
+
+    fn clear(&mut self) {
+        self.cell_actions.clear();
+        self.self_actions.clear();
+        self.entity_actions.clear();
+        self.messages.clear();
+    }
+}
+
+/// Message sent over the wakeup channels containing recycled action buffers, the universe whose
+/// entities need to be driven and the messages that were delivered to them
+pub struct WakeupMessage<
+    C: CellState,
+    E: EntityState<C>,
+    M: MutEntityState,
+    CA: CellAction<C>,
+    EA: EntityAction<C, E>,
+    U: Universe<C, E, M>,
+    T,
+> {
+    bufs: ActionBufs<C, E, CA, EA, U::Coord, T>,
+    universe: *const U,
+    inboxes: *const Inboxes<T>,
+    view_distance: usize,
+    index: Arc<AtomicUsize>,
+}
+
+// The universe and the inboxes are only read by the workers, and `step` doesn't return before all
+// of them have sent their buffers back, so the pointers remain valid for as long as they're in use.
+unsafe impl<
+        C: CellState + Send,
+        E: EntityState<C> + Send,
+        M: MutEntityState,
+        CA: CellAction<C> + Send,
+        EA: EntityAction<C, E> + Send,
+        U: Universe<C, E, M> + Sync,
+        T: Send + Sync,
+    > Send for WakeupMessage<C, E, M, CA, EA, U, T>
+where
+    U::Coord: Send,
+{
+}
+
 pub struct ParallelEngine<
     C: CellState + Send + 'static,
     E: EntityState<C> + Send + 'static,
-    M: MutEntityState + Send + 'static,
+    M: MutEntityState + 'static,
     CA: CellAction<C> + Send + 'static,
     EA: EntityAction<C, E> + Send + 'static,
-    U: Universe<C, E, M>,
+    U: Universe<C, E, M> + Sync + 'static,
     F: Fn(
         &mut U,
-        &[OwnedAction<C, E, CA, EA>],
-        &[OwnedAction<C, E, CA, EA>],
-        &[OwnedAction<C, E, CA, EA>],
+        &[OwnedAction<C, E, CA, EA, U::Coord>],
+        &[OwnedAction<C, E, CA, EA, U::Coord>],
+        &[OwnedAction<C, E, CA, EA, U::Coord>],
     ),
-> {
-    worker_count: usize,
+    T: Clone + Send + Sync + 'static = (),
+> where
+    U::Coord: Send,
+{
     // Uses a function trait out of necessity since we have need to do that for the hybrid server.
     exec_actions: F,
-    action_buf_rx: Receiver<ActionBufs<C, E, CA, EA>>,
-    wakeup_senders: Vec<SyncSender<WakeupMessage<C, E, M, CA, EA>>>,
+    view_distance: usize,
+    /// Holds the messages sent by entities during a tick until they're read in the next one.
+    /// Messages are discarded if it's `None`.
+    mailbox: Option<Mailbox<T>>,
+    /// Receives the buffers of each worker along with `true` once it has finished driving entities
+    /// or `false` if an entity driver panicked
+    action_buf_rx: Receiver<(ActionBufs<C, E, CA, EA, U::Coord, T>, bool)>,
+    wakeup_senders: Vec<SyncSender<WakeupMessage<C, E, M, CA, EA, U, T>>>,
     index: Arc<AtomicUsize>,
-    recycled_action_bufs: Vec<ActionBufs<C, E, CA, EA>>,
-    action_buf_buf: Vec<ActionBufs<C, E, CA, EA>>,
+    recycled_action_bufs: Vec<ActionBufs<C, E, CA, EA, U::Coord, T>>,
+    /// The actions and messages of all workers, combined before they're applied
+    actions: ActionBufs<C, E, CA, EA, U::Coord, T>,
     __phantom_u: PhantomData<U>,
 }
 
-/// Message sent over the wakeup channels containing recycled action buffers and the number of
-/// entities that need to be processed
-pub struct WakeupMessage<
-    C: CellState + Send + 'static,
-    E: EntityState<C> + Send + 'static,
-    M: MutEntityState + Send + 'static,
-    CA: CellAction<C> + Send + 'static,
-    EA: EntityAction<C, E> + Send + 'static,
-> {
-    cell_action_buf: Vec<OwnedAction<C, E, CA, EA>>,
-    self_action_buf: Vec<OwnedAction<C, E, CA, EA>>,
-    entity_action_buf: Vec<OwnedAction<C, E, CA, EA>>,
-    entity_count: usize,
-    cells_ptr: *const Cell<C>,
-    entities_ptr: *const EntityContainer<C, E, M>,
-    index: Arc<AtomicUsize>,
-}
-
-unsafe impl<
+impl<
         C: CellState + Send + 'static,
         E: EntityState<C> + Send + 'static,
-        M: MutEntityState + Send + 'static,
+        M: MutEntityState + 'static,
         CA: CellAction<C> + Send + 'static,
         EA: EntityAction<C, E> + Send + 'static,
-    > Send for WakeupMessage<C, E, M, CA, EA>
-{
-}
-
-impl<
-        C: CellState + Send,
-        E: EntityState<C> + Send,
-        M: MutEntityState + Send,
-        CA: CellAction<C> + Send,
-        EA: EntityAction<C, E> + Send,
-        U: Universe<C, E, M>,
+        U: Universe<C, E, M> + Sync + 'static,
         F: Fn(
             &mut U,
-            &[OwnedAction<C, E, CA, EA>],
-            &[OwnedAction<C, E, CA, EA>],
-            &[OwnedAction<C, E, CA, EA>],
+            &[OwnedAction<C, E, CA, EA, U::Coord>],
+            &[OwnedAction<C, E, CA, EA, U::Coord>],
+            &[OwnedAction<C, E, CA, EA, U::Coord>],
         ),
-    > ParallelEngine<C, E, M, CA, EA, U, F>
+        T: Clone + Send + Sync + 'static,
+    > ParallelEngine<C, E, M, CA, EA, U, F, T>
+where
+    U::Coord: Send,
 {
-    pub fn new(
+    /// Creates an engine with one worker thread per CPU.
+    pub fn new(exec_actions: F, entity_driver: EntityDriver<C, E, M, CA, EA, U, T>) -> Self {
+        ParallelEngine::with_workers(num_cpus::get(), exec_actions, entity_driver)
+    }
+
+    pub fn with_workers(
+        worker_count: usize,
         exec_actions: F,
-        entity_driver: fn(
-            universe_index: usize,
-            entity: &Entity<C, E, M>,
-            entities: &EntityContainer<C, E, M>,
-            cells: &[Cell<C>],
-            cell_action_executor: &mut FnMut(CA, usize),
-            self_action_executor: &mut FnMut(SelfAction<C, E, EA>),
-            entity_action_executor: &mut FnMut(EA, usize, Uuid),
-        ),
+        entity_driver: EntityDriver<C, E, M, CA, EA, U, T>,
     ) -> Self {
-        let cpu_count = num_cpus::get();
+        assert!(worker_count > 0);
         // create a container to hold the senders used to wake up the worker threads
-        let mut wakeup_senders = Vec::with_capacity(cpu_count);
+        let mut wakeup_senders = Vec::with_capacity(worker_count);
         // create a channel over which to receive action buffers from the worker threads
-        let (action_buf_tx, action_buf_rx) = sync_channel(cpu_count);
+        let (action_buf_tx, action_buf_rx) = sync_channel(worker_count);
 
         // spawn worker threads that block waiting for a message to be received indicating that they
         // should start pulling and processing work
-        for _ in 0..cpu_count {
+        for _ in 0..worker_count {
             let (wakeup_tx, wakeup_rx) = sync_channel(0);
             wakeup_senders.push(wakeup_tx);
             let action_buf_tx = action_buf_tx.clone();
 
             thread::spawn(move || {
-                let mut entity_index;
-                let mut cell_action_count;
-                let mut self_action_count;
-                let mut entity_action_count;
-
-                // keep blocking and waiting for a wakeup message, then start processing work until
-                // it's all completed
-                loop {
-                    // reset action counts
-                    cell_action_count = 0;
-                    self_action_count = 0;
-                    entity_action_count = 0;
-
-                    let WakeupMessage {
-                        mut cell_action_buf,
-                        mut self_action_buf,
-                        mut entity_action_buf,
-                        entity_count,
-                        cells_ptr,
-                        entities_ptr,
-                        index,
-                    } = wakeup_rx.recv().expect(
-                        "Error while receiving work message over channel in worker thread; sender \
-                         likely gone away!",
-                    );
-
-                    // convert the current cell and entity pointers into references
-                    let entities: &EntityContainer<C, E, M> =
-                        unsafe { &*(entities_ptr as *const EntityContainer<C, E, M>) };
-                    // TODO TODO TODO ------------------- THIS IS BAD \/ \/ \/ \/ \/ \/ \/
-                    let cells: &Vec<Cell<C>> = unsafe { &*(cells_ptr as *const Vec<Cell<C>>) };
-
-                    // keep processing work as long as there's work left to process
-                    loop {
-                        entity_index = index.fetch_add(1, Ordering::Relaxed);
-                        if entity_index < entity_count {
-                            match entities.entities[entity_index] {
-                                EntitySlot::Occupied {
-                                    entity: ref entity_ref,
-                                    ref universe_index,
-                                } => {
-                                    let mut cell_action_executor =
-                                        |cell_action: CA, universe_index: usize| {
-                                            let owned_action = OwnedAction {
-                                                source_entity_index: entity_index,
-                                                source_uuid: entity_ref.uuid,
-                                                action: Action::CellAction {
-                                                    universe_index,
-                                                    action: cell_action,
-                                                },
-                                            };
-
-                                            if cell_action_buf.len() <= cell_action_count {
-                                                cell_action_buf.push(owned_action);
-                                            } else {
-                                                debug_assert!(
-                                                    cell_action_buf.len() > cell_action_count
-                                                );
-                                                unsafe {
-                                                    *cell_action_buf
-                                                        .get_unchecked_mut(cell_action_count) =
-                                                        owned_action
-                                                };
-                                            }
-                                            cell_action_count += 1;
-                                        };
-
-                                    let mut self_action_executor =
-                                        |self_action: SelfAction<C, E, EA>| {
-                                            let owned_action = OwnedAction {
-                                                source_entity_index: entity_index,
-                                                source_uuid: entity_ref.uuid,
-                                                action: Action::SelfAction(self_action),
-                                            };
-
-                                            if self_action_buf.len() <= self_action_count {
-                                                self_action_buf.push(owned_action);
-                                            } else {
-                                                debug_assert!(
-                                                    self_action_buf.len() > self_action_count
-                                                );
-                                                unsafe {
-                                                    *self_action_buf
-                                                        .get_unchecked_mut(self_action_count) =
-                                                        owned_action
-                                                };
-                                            }
-                                            self_action_count += 1;
-                                        };
-
-                                    let mut entity_action_executor = |entity_action: EA, target_entity_index: usize, target_uuid: Uuid| {
-                                        let owned_action = OwnedAction {
-                                            source_entity_index: entity_index,
-                                            source_uuid: entity_ref.uuid,
-                                            action: Action::EntityAction {
-                                                action: entity_action,
-                                                target_entity_index: target_entity_index,
-                                                target_uuid: target_uuid,
-                                            },
-                                        };
-
-                                        if entity_action_buf.len() <= entity_action_count {
-                                            entity_action_buf.push(owned_action);
-                                        } else {
-                                            debug_assert!(entity_action_buf.len() > entity_action_count);
-                                            unsafe { *entity_action_buf.get_unchecked_mut(entity_action_count) = owned_action };
-                                        }
-                                        entity_action_count += 1;
-                                    };
-
-                                    // execute the entity driver
-                                    entity_driver(
-                                        *universe_index,
-                                        entity_ref,
-                                        entities,
-                                        cells,
-                                        &mut cell_action_executor,
-                                        &mut self_action_executor,
-                                        &mut entity_action_executor,
-                                    );
-                                },
-                                EntitySlot::Empty(_) => (),
-                            }
-                        } else {
-                            // we've reached the end of the entities and can exit.
-                            break;
-                        }
-                    }
-
-                    // push the buffers back to the main thread over the `action_buf_tx`
-                    let msg = (
-                        cell_action_buf,
-                        cell_action_count,
-                        self_action_buf,
-                        self_action_count,
-                        entity_action_buf,
-                        entity_action_count,
-                    );
-                    action_buf_tx
-                        .send(msg)
-                        .expect("Unable to send action buffers over `action_buf_tx`!");
-                }
+                run_worker(wakeup_rx, action_buf_tx, entity_driver)
             });
         }
 
-        // create initial, empty `ActionBufs` to insert into the struct
-        let mut recycled_action_bufs = Vec::with_capacity(cpu_count);
-        for _ in 0..cpu_count {
-            let bufs = (Vec::new(), 0, Vec::new(), 0, Vec::new(), 0);
-            recycled_action_bufs.push(bufs);
-        }
-
-        // create vector to hold the results from the worker threads without allocating.  Will
-        // always have the same length as the number of worker threads (currently the number
-        // of CPUs)
-        let action_buf_buf = Vec::with_capacity(cpu_count);
-
         ParallelEngine {
-            worker_count: cpu_count,
             exec_actions,
+            view_distance: 1,
+            mailbox: None,
             action_buf_rx,
             wakeup_senders,
             index: Arc::new(AtomicUsize::new(0)),
-            recycled_action_bufs,
-            action_buf_buf,
+            recycled_action_bufs: (0..worker_count).map(|_| ActionBufs::new()).collect(),
+            actions: ActionBufs::new(),
             __phantom_u: PhantomData,
         }
     }
+
+    /// Sets the number of cells in each direction that entities can see through their
+    /// `EntityView`s, which defaults to 1.
+    pub fn with_view_distance(mut self, view_distance: usize) -> Self {
+        self.view_distance = view_distance;
+        self
+    }
+
+    /// Sets the mailbox that holds the messages sent by entities until they're read in the next
+    /// tick.  Without one, messages are discarded.
+    pub fn with_mailbox(mut self, mailbox: Mailbox<T>) -> Self {
+        self.mailbox = Some(mailbox);
+        self
+    }
+
+    pub fn mailbox(&self) -> Option<&Mailbox<T>> { self.mailbox.as_ref() }
+}
+
+/// Keeps blocking and waiting for a wakeup message, then drives entities until all of them have
+/// been claimed by a worker.  Returns once the engine has been dropped.
+fn run_worker<
+    C: CellState,
+    E: EntityState<C>,
+    M: MutEntityState,
+    CA: CellAction<C>,
+    EA: EntityAction<C, E>,
+    U: Universe<C, E, M>,
+    T,
+>(
+    wakeup_rx: Receiver<WakeupMessage<C, E, M, CA, EA, U, T>>,
+    action_buf_tx: SyncSender<(ActionBufs<C, E, CA, EA, U::Coord, T>, bool)>,
+    entity_driver: EntityDriver<C, E, M, CA, EA, U, T>,
+) {
+    while let Ok(msg) = wakeup_rx.recv() {
+        let WakeupMessage {
+            mut bufs,
+            universe,
+            inboxes,
+            view_distance,
+            index,
+        } = msg;
+        let (universe, inboxes): (&U, &Inboxes<T>) = unsafe { (&*universe, &*inboxes) };
+        let completed = panic::catch_unwind(AssertUnwindSafe(|| {
+            drive_entities(universe, inboxes, view_distance, &index, &mut bufs, entity_driver)
+        }))
+        .is_ok();
+
+        // push the buffers back to the main thread over the `action_buf_tx`
+        if action_buf_tx.send((bufs, completed)).is_err() {
+            return;
+        }
+    }
+}
+
+/// Claims entities one at a time and drives them, collecting their actions and messages into
+/// `bufs`, until all of the universe's entities have been claimed.
+fn drive_entities<
+    C: CellState,
+    E: EntityState<C>,
+    M: MutEntityState,
+    CA: CellAction<C>,
+    EA: EntityAction<C, E>,
+    U: Universe<C, E, M>,
+    T,
+>(
+    universe: &U,
+    inboxes: &Inboxes<T>,
+    view_distance: usize,
+    index: &AtomicUsize,
+    bufs: &mut ActionBufs<C, E, CA, EA, U::Coord, T>,
+    entity_driver: EntityDriver<C, E, M, CA, EA, U, T>,
+) {
+    let ActionBufs {
+        ref mut cell_actions,
+        ref mut self_actions,
+        ref mut entity_actions,
+        ref mut messages,
+    } = *bufs;
+    let entities = universe.get_entities();
+    let slot_count = entities.generations.len();
+
+    // keep processing work as long as there's work left to process
+    loop {
+        let slot = index.fetch_add(1, Ordering::Relaxed);
+        if slot >= slot_count {
+            break;
+        }
+        let (entity, universe_index) = match entities.entities.get(slot) {
+            Some(&(ref entity, universe_index)) => (entity, universe_index),
+            None => continue,
+        };
+        let entity_handle = EntityHandle {
+            index: slot,
+            generation: entities.generations[slot],
+        };
+
+        let mut cell_action_executor = |cell_action: CA, universe_index: U::Coord| {
+            cell_actions.push(OwnedAction {
+                source_entity: entity_handle,
+                action: Action::CellAction {
+                    universe_index,
+                    action: cell_action,
+                },
+            });
+        };
+        let mut self_action_executor = |self_action: SelfAction<C, E, EA>| {
+            self_actions.push(OwnedAction {
+                source_entity: entity_handle,
+                action: Action::SelfAction(self_action),
+            });
+        };
+        let mut entity_action_executor = |entity_action: EA, target_entity: EntityHandle| {
+            entity_actions.push(OwnedAction {
+                source_entity: entity_handle,
+                action: Action::EntityAction {
+                    action: entity_action,
+                    target_entity,
+                },
+            });
+        };
+        let mut message_executor = |recipient: Recipient, message: T| {
+            messages.push(SentMessage {
+                sender: entity_handle,
+                recipient,
+                message,
+            });
+        };
+
+        let inbox = inboxes.get(&entity_handle).map(|inbox| inbox.as_slice()).unwrap_or(&[]);
+        let view = EntityView::new(universe, entity, entity_handle, universe_index, view_distance)
+            .with_inbox(inbox);
+        entity_driver(
+            &view,
+            &mut cell_action_executor,
+            &mut self_action_executor,
+            &mut entity_action_executor,
+            &mut message_executor,
+        );
+    }
 }
 
 impl<
-        C: CellState + Send + Debug + 'static,
-        E: EntityState<C> + Send + Debug + 'static,
-        M: MutEntityState + Send + 'static,
-        CA: CellAction<C> + Send + Debug + 'static,
-        EA: EntityAction<C, E> + Send + Debug + 'static,
-        U: Universe<C, E, M>,
+        C: CellState + Send + 'static,
+        E: EntityState<C> + Send + 'static,
+        M: MutEntityState + 'static,
+        CA: CellAction<C> + Send + 'static,
+        EA: EntityAction<C, E> + Send + 'static,
+        U: Universe<C, E, M> + Sync + 'static,
         F: Fn(
             &mut U,
-            &[OwnedAction<C, E, CA, EA>],
-            &[OwnedAction<C, E, CA, EA>],
-            &[OwnedAction<C, E, CA, EA>],
+            &[OwnedAction<C, E, CA, EA, U::Coord>],
+            &[OwnedAction<C, E, CA, EA, U::Coord>],
+            &[OwnedAction<C, E, CA, EA, U::Coord>],
         ),
-    > Engine<C, E, M, CA, EA, U> for Box<ParallelEngine<C, E, M, CA, EA, U, F>>
+        T: Clone + Send + Sync + 'static,
+    > Engine<C, E, M, CA, EA, U> for Box<ParallelEngine<C, E, M, CA, EA, U, F, T>>
+where
+    U::Coord: Send,
 {
-    fn step(&mut self, mut universe: &mut U) {
-        let &mut ParallelEngine {
-            ref index,
-            worker_count,
-            ref exec_actions,
-            ref action_buf_rx,
-            ref mut wakeup_senders,
-            ref mut recycled_action_bufs,
-            ref mut action_buf_buf,
-            ..
-        } = &mut **self;
-
-        // TODO: Look into bullying Rust into letting us do without the `Arc` since that's a Heap
-        // allocation plus pointer overhead that has to happen every cycle.
-        let entity_count = universe.get_entities().len();
-        let cells_ptr = universe.get_cells().as_ptr();
-        let entities_ptr = universe.get_entities() as *const EntityContainer<C, E, M>;
-        // reset current entity count to 0
-        index.store(0, Ordering::Relaxed);
-
-        debug_assert_eq!(wakeup_senders.len(), worker_count);
-        // construct wakeup messages to send to all the workers and then send them over to get them
-        // doing work
-        let mut i = 0;
+    fn step(&mut self, universe: &mut U) {
+        let engine = &mut **self;
+        // reset the index of the next entity to be driven
+        engine.index.store(0, Ordering::Relaxed);
+
+        // send the recycled buffers to all of the workers to get them doing work
+        let inboxes = match engine.mailbox {
+            Some(ref mut mailbox) => mailbox.take_inboxes(),
+            None => HashMap::new(),
+        };
+        let universe_ptr = &*universe as *const U;
+        for (wakeup_sender, bufs) in engine
+            .wakeup_senders
+            .iter()
+            .zip(engine.recycled_action_bufs.drain(..))
         {
-            for (cell_action_buf, _, self_action_buf, _, entity_action_buf, _) in
-                recycled_action_bufs.drain(..)
-            {
-                let msg = WakeupMessage {
-                    cell_action_buf,
-                    self_action_buf,
-                    entity_action_buf,
-                    cells_ptr,
-                    entities_ptr,
-                    entity_count,
-                    index: index.clone(),
-                };
-                unsafe { wakeup_senders.get_unchecked_mut(i) }
-                    .send(msg)
-                    .expect("Unable to send wakeup message to worker thread!");
-                i += 1;
-            }
+            let msg = WakeupMessage {
+                bufs,
+                universe: universe_ptr,
+                inboxes: &inboxes,
+                view_distance: engine.view_distance,
+                index: Arc::clone(&engine.index),
+            };
+            wakeup_sender
+                .send(msg)
+                .expect("Unable to send wakeup message to worker thread!");
         }
 
-        debug_assert_eq!(action_buf_buf.len(), 0);
-        // collect the results from the worker threads
-        for _ in 0..worker_count {
-            let bufs = action_buf_rx.recv().expect(
+        // collect the results from the worker threads.  All of them have to report back before a
+        // panic is re-raised since they may still be reading the universe until they do.
+        let actions = &mut engine.actions;
+        let mut completed = true;
+        for _ in 0..engine.wakeup_senders.len() {
+            let (mut bufs, worker_completed) = engine.action_buf_rx.recv().expect(
                 "Error while receiving action buffers from worker thread; thread probably died.",
             );
-            action_buf_buf.push(bufs);
+            completed &= worker_completed;
+            actions.cell_actions.extend(bufs.cell_actions.drain(..));
+            actions.self_actions.extend(bufs.self_actions.drain(..));
+            actions.entity_actions.extend(bufs.entity_actions.drain(..));
+            actions.messages.extend(bufs.messages.drain(..));
+            // recycle the action buffers to avoid having to re-allocate them later
+            engine.recycled_action_bufs.push(bufs);
         }
-        debug_assert_eq!(action_buf_buf.len(), worker_count);
-
-        // execute all the queued actions once all workers have finished.
-        let exec_actions = exec_actions;
-        let mut i = 0;
-        for (
-            mut cell_action_buf,
-            cell_action_count,
-            mut self_action_buf,
-            self_action_count,
-            mut entity_action_buf,
-            entity_action_count,
-        ) in action_buf_buf.drain(..)
-        {
-            // since we're re-using the buffers without clearing out old values for performance, set
-            // their lengths manually
-            let (real_cell_len, real_self_len, real_entity_len) = (
-                cell_action_buf.len(),
-                self_action_buf.len(),
-                entity_action_buf.len(),
-            );
-            unsafe {
-                cell_action_buf.set_len(cell_action_count);
-                self_action_buf.set_len(self_action_count);
-                entity_action_buf.set_len(entity_action_count);
+        if !completed {
+            actions.clear();
+            panic!("An entity driver panicked on a worker thread!");
+        }
+
+        // every entity is driven by a single worker, so a stable sort restores the order in which
+        // the serial engine would have produced the actions regardless of how work was divided
+        actions.cell_actions.sort_by_key(|action| action.source_entity.index);
+        actions.self_actions.sort_by_key(|action| action.source_entity.index);
+        actions.entity_actions.sort_by_key(|action| action.source_entity.index);
+        actions.messages.sort_by_key(|message| message.sender.index);
+
+        // evaluate all pending actions simultaneously, allowing the engine to handle any conflicts
+        (engine.exec_actions)(
+            universe,
+            &actions.cell_actions,
+            &actions.self_actions,
+            &actions.entity_actions,
+        );
+
+        // messages are delivered to the recipients' positions after all actions have been applied
+        if let Some(ref mut mailbox) = engine.mailbox {
+            mailbox.deliver(actions.messages.drain(..), &*universe);
+        }
+        actions.clear();
+    }
+}
+
+#[test]
+fn parallel_engine_advances_ticks() {
+    use cell::Cell;
+    use driver::step_engine;
+    use rand::Rng;
+    use test_util::{new_entity, CS, EA, ES, MES};
+    use universe::{Universe2D, Universe2DConf};
+
+    /// Stores a random number in a cell
+    #[derive(Clone, Debug)]
+    struct Roll(usize);
+    impl CellAction<CS> for Roll {}
+
+    type U = Universe2D<CS, ES, MES>;
+    fn roll(
+        view: &EntityView<CS, ES, MES, U>,
+        cell_action_executor: &mut FnMut(Roll, usize),
+        _: &mut FnMut(SelfAction<CS, ES, EA>),
+        _: &mut FnMut(EA, EntityHandle),
+        _: &mut FnMut(Recipient, ()),
+    ) {
+        cell_action_executor(Roll(view.rng().gen()), view.position());
+    }
+    fn exec(
+        universe: &mut U,
+        cell_actions: &[OwnedAction<CS, ES, Roll, EA>],
+        _: &[OwnedAction<CS, ES, Roll, EA>],
+        _: &[OwnedAction<CS, ES, Roll, EA>],
+    ) {
+        for owned_action in cell_actions {
+            if let Action::CellAction {
+                action: Roll(n),
+                universe_index,
+            } = owned_action.action
+            {
+                universe.cells[universe_index].state = CS(n);
             }
+        }
+    }
 
-            // evaluate all pending actions, allowing the engine to handle any conflicts
-            exec_actions(
-                &mut universe,
-                &cell_action_buf,
-                &self_action_buf,
-                &entity_action_buf,
-            );
+    // returns the cells after each of two ticks
+    let run = |worker_count| -> Vec<Vec<usize>> {
+        let mut universe: U = Universe2D::uninitialized();
+        universe.conf = Universe2DConf::square(4);
+        universe.cells = vec![Cell { state: CS(0) }; 16];
+        for universe_index in 0..16 {
+            universe.entities.insert(new_entity(0), universe_index);
+        }
 
-            // recycle the action buffers to avoid having to re-allocate them later
-            unsafe {
-                cell_action_buf.set_len(real_cell_len);
-                self_action_buf.set_len(real_self_len);
-                entity_action_buf.set_len(real_entity_len);
+        let mut engine = Box::new(ParallelEngine::with_workers(worker_count, exec, roll));
+        let ticks = (0..2)
+            .map(|_| {
+                step_engine(&mut engine, &mut universe, &mut []);
+                universe.cells.iter().map(|cell| cell.state.0).collect()
+            })
+            .collect();
+        assert_eq!(universe.entities.tick, 2);
+        ticks
+    };
+
+    let ticks = run(3);
+    assert_eq!(ticks, run(1));
+    // entities get new random streams every tick
+    assert_ne!(ticks[0], ticks[1]);
+}
+
+#[test]
+fn parallel_engine_reraises_driver_panics() {
+    use cell::Cell;
+    use test_util::{new_entity, CA, CS, EA, ES, MES};
+    use universe::{Universe2D, Universe2DConf};
+
+    type U = Universe2D<CS, ES, MES>;
+    fn driver(
+        view: &EntityView<CS, ES, MES, U>,
+        _: &mut FnMut(CA, usize),
+        self_action_executor: &mut FnMut(SelfAction<CS, ES, EA>),
+        _: &mut FnMut(EA, EntityHandle),
+        _: &mut FnMut(Recipient, ()),
+    ) {
+        if view.entity().state == ES(1) {
+            panic!("The entity driver panicked!");
+        }
+        self_action_executor(SelfAction::Suicide);
+    }
+    fn exec(
+        universe: &mut U,
+        _: &[OwnedAction<CS, ES, CA, EA>],
+        self_actions: &[OwnedAction<CS, ES, CA, EA>],
+        _: &[OwnedAction<CS, ES, CA, EA>],
+    ) {
+        for owned_action in self_actions {
+            universe.entities.remove(owned_action.source_entity);
+        }
+    }
+
+    let mut universe: U = Universe2D::uninitialized();
+    universe.conf = Universe2DConf::square(4);
+    universe.cells = vec![Cell { state: CS(0) }; 16];
+    for universe_index in 0..16 {
+        universe.entities.insert(new_entity((universe_index == 5) as usize), universe_index);
+    }
+
+    let mut engine = Box::new(ParallelEngine::with_workers(3, exec, driver));
+    let result = panic::catch_unwind(AssertUnwindSafe(|| engine.step(&mut universe)));
+    assert!(result.is_err());
+    // none of the actions of the tick in which the panic happened are applied
+    assert_eq!(universe.entities.len(), 16);
+
+    // the workers survive the panic and keep driving entities
+    let panicking = universe.entities.get_entities_at(5)[0];
+    universe.entities.remove(panicking);
+    engine.step(&mut universe);
+    assert_eq!(universe.entities.len(), 0);
+}
+
+#[test]
+fn parallel_engine_delivers_messages() {
+    use cell::Cell;
+    use test_util::{new_entity, CS, EA, ES, MES};
+    use universe::{BoundaryPolicy, Universe2D, Universe2DConf};
+
+    /// Stores the messages that an entity received, in order, in its cell
+    #[derive(Clone, Debug)]
+    struct Received(usize);
+    impl CellAction<CS> for Received {}
+
+    type U = Universe2D<CS, ES, MES>;
+    fn driver(
+        view: &EntityView<CS, ES, MES, U, usize>,
+        cell_action_executor: &mut FnMut(Received, usize),
+        _: &mut FnMut(SelfAction<CS, ES, EA>),
+        _: &mut FnMut(EA, EntityHandle),
+        message_executor: &mut FnMut(Recipient, usize),
+    ) {
+        let received = view.inbox().iter().fold(0, |acc, envelope| acc * 100 + envelope.message);
+        cell_action_executor(Received(received), view.position());
+        message_executor(Recipient::Within(1), view.position() + 1);
+    }
+    fn exec(
+        universe: &mut U,
+        cell_actions: &[OwnedAction<CS, ES, Received, EA>],
+        _: &[OwnedAction<CS, ES, Received, EA>],
+        _: &[OwnedAction<CS, ES, Received, EA>],
+    ) {
+        for owned_action in cell_actions {
+            if let Action::CellAction {
+                action: Received(received),
+                universe_index,
+            } = owned_action.action
+            {
+                universe.cells[universe_index].state = CS(received);
             }
-            recycled_action_bufs.push((
-                cell_action_buf,
-                0,
-                self_action_buf,
-                0,
-                entity_action_buf,
-                0,
-            ));
-
-            i += 1;
         }
-        debug_assert_eq!(i as usize, worker_count);
     }
+
+    // returns the cells of the four entities after two ticks
+    let run = |worker_count| -> Vec<usize> {
+        let mut universe: U = Universe2D::uninitialized();
+        universe.conf = Universe2DConf::square(5);
+        universe.conf.boundary = BoundaryPolicy::Clip;
+        universe.cells = vec![Cell { state: CS(0) }; 25];
+        for &universe_index in &[18, 12, 7, 6] {
+            universe.entities.insert(new_entity(0), universe_index);
+        }
+
+        let mut engine = Box::new(
+            ParallelEngine::with_workers(worker_count, exec, driver).with_mailbox(Mailbox::new(4)),
+        );
+        engine.step(&mut universe);
+        engine.step(&mut universe);
+        [18, 12, 7, 6].iter().map(|&i| universe.cells[i].state.0).collect()
+    };
+
+    let cells = run(3);
+    assert_eq!(cells, run(1));
+    // messages show up in the order in which the serial engine would have sent them
+    assert_eq!(cells, vec![13, 19_08_07, 13_07, 13_08]);
 }
@@ -0,0 +1,135 @@
+//! A persistent set of worker threads across which the rows of a two-dimensional grid are divided.
+//! It's used by the parts of the library that compute a new value for every cell of a universe each
+//! tick, such as `CellRuleEngine` and the `FieldDiffusion` middleware.  The threads are spawned
+//! once and reused every tick rather than being spawned anew for every pass over the grid.
+
+use std::{
+    mem,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        mpsc::{sync_channel, Receiver, SyncSender},
+        Mutex,
+    },
+    thread,
+};
+
+/// A pass over the grid, called with the index of the run of rows to process
+type Job = Fn(usize) + Sync;
+
+/// Message sent over the wakeup channels containing the job to run and the run of rows to run it on
+struct WakeupMessage {
+    job: *const Job,
+    chunk: usize,
+}
+
+// The job is borrowed from the stack of `for_each_rows`, which doesn't return before all of the
+// workers that it woke up have reported back, so the pointer remains valid for as long as it's in
+// use.
+unsafe impl Send for WakeupMessage {}
+
+pub struct RowWorkers {
+    wakeup_senders: Vec<SyncSender<WakeupMessage>>,
+    /// Receives `true` from a worker once it has finished its run of rows or `false` if it panicked
+    done_rx: Receiver<bool>,
+}
+
+impl RowWorkers {
+    /// Creates a set of `worker_count` workers.  If it's `1`, no threads are spawned and all rows
+    /// are processed on the calling thread.
+    pub fn new(worker_count: usize) -> Self {
+        assert!(worker_count > 0);
+        let (done_tx, done_rx) = sync_channel(worker_count);
+        let wakeup_senders = if worker_count == 1 {
+            Vec::new()
+        } else {
+            (0..worker_count)
+                .map(|_| {
+                    let (wakeup_tx, wakeup_rx) = sync_channel(1);
+                    let done_tx = done_tx.clone();
+                    thread::spawn(move || run_worker(wakeup_rx, done_tx));
+                    wakeup_tx
+                })
+                .collect()
+        };
+
+        RowWorkers {
+            wakeup_senders,
+            done_rx,
+        }
+    }
+
+    pub fn worker_count(&self) -> usize { self.wakeup_senders.len().max(1) }
+
+    /// Divides `dst`, which holds full rows of `width` values each, into one contiguous run of rows
+    /// per worker and calls `f` with each run and the index of its first row.  Returns once all of
+    /// the rows have been processed.
+    pub fn for_each_rows<T: Send, F: Fn(&mut [T], usize) + Sync>(
+        &self,
+        dst: &mut [T],
+        width: usize,
+        f: F,
+    ) {
+        if self.wakeup_senders.is_empty() || dst.is_empty() {
+            return f(dst, 0);
+        }
+
+        let height = dst.len() / width;
+        let rows_per_worker = (height + self.worker_count() - 1) / self.worker_count();
+        let chunks: Vec<Mutex<Option<&mut [T]>>> = dst
+            .chunks_mut(rows_per_worker * width)
+            .map(|rows| Mutex::new(Some(rows)))
+            .collect();
+        let job = |chunk: usize| {
+            let rows = chunks[chunk].lock().unwrap().take().unwrap();
+            f(rows, chunk * rows_per_worker)
+        };
+        let job: &(Fn(usize) + Sync) = &job;
+        let job: *const Job = unsafe { mem::transmute(job) };
+
+        for (chunk, wakeup_sender) in self.wakeup_senders.iter().take(chunks.len()).enumerate() {
+            wakeup_sender
+                .send(WakeupMessage { job, chunk })
+                .expect("A row worker exited unexpectedly!");
+        }
+
+        let mut completed = true;
+        for _ in 0..chunks.len() {
+            completed &= self
+                .done_rx
+                .recv()
+                .expect("A row worker exited unexpectedly!");
+        }
+        assert!(completed, "A row worker panicked while processing its rows!");
+    }
+}
+
+/// Keeps blocking and waiting for a wakeup message, then runs the job on its run of rows.  Returns
+/// once the `RowWorkers` have been dropped.
+fn run_worker(wakeup_rx: Receiver<WakeupMessage>, done_tx: SyncSender<bool>) {
+    while let Ok(WakeupMessage { job, chunk }) = wakeup_rx.recv() {
+        let job = unsafe { &*job };
+        let completed = panic::catch_unwind(AssertUnwindSafe(|| job(chunk))).is_ok();
+        if done_tx.send(completed).is_err() {
+            return;
+        }
+    }
+}
+
+#[test]
+fn row_workers_cover_every_row() {
+    for &worker_count in &[1, 3, 8] {
+        let workers = RowWorkers::new(worker_count);
+        // more workers than rows leaves some of them idle
+        for &height in &[0, 1, 5, 16] {
+            let mut dst = vec![(0, 0); height * 4];
+            workers.for_each_rows(&mut dst, 4, |rows, first_row| {
+                for (i, value) in rows.iter_mut().enumerate() {
+                    *value = (first_row + i / 4, value.1 + 1);
+                }
+            });
+
+            let expected: Vec<(usize, usize)> = (0..height * 4).map(|i| (i / 4, 1)).collect();
+            assert_eq!(dst, expected);
+        }
+    }
+}
@@ -3,14 +3,17 @@
 
 use universe::{Universe};
 use cell::CellState;
-use entity::{Entity, EntityState, MutEntityState};
+use entity::{EntityState, MutEntityState};
 use action::{Action, OwnedAction, CellAction, SelfAction, EntityAction};
+use container::EntityHandle;
+use mailbox::{Mailbox, Recipient, SentMessage};
+use view::EntityView;
 
 use super::Engine;
 use super::iterator::EntityIterator;
 
-use uuid::Uuid;
-
+/// `T` is the type of the messages that entities can send each other; they're only delivered if
+/// the engine provides a `Mailbox` to hold them.
 pub trait SerialEngine<
     C: CellState + 'static,
     E: EntityState<C>,
@@ -19,25 +22,37 @@ pub trait SerialEngine<
     EA: EntityAction<C, E>,
     EI: EntityIterator<C, E, M>,
     U: Universe<C, E, M>,
+    T: Clone = (),
 > {
     fn iter_entities(&self, &U) -> EI;
 
+    /// Applies the cell, self, and entity actions produced during a tick.  A `ConflictResolver` held
+    /// by the engine can be passed to `executor::exec_actions` to decide which of several actions
+    /// targeting the same cell or entity are applied.
     fn exec_actions(
         &self,
         &mut U,
-        &[OwnedAction<C, E, CA, EA>],
-        &[OwnedAction<C, E, CA, EA>],
-        &[OwnedAction<C, E, CA, EA>]
+        &[OwnedAction<C, E, CA, EA, U::Coord>],
+        &[OwnedAction<C, E, CA, EA, U::Coord>],
+        &[OwnedAction<C, E, CA, EA, U::Coord>]
     );
 
+    /// The number of cells in each direction that entities can see through their `EntityView`s
+    fn view_distance(&self) -> usize { 1 }
+
+    /// Holds the messages sent by entities during a tick until they're read in the next one.
+    /// Messages are discarded if this returns `None`.
+    fn mailbox(&mut self) -> Option<&mut Mailbox<T>> { None }
+
+    /// Determines the actions taken by an entity given its view of the universe, which includes
+    /// the messages that were sent to it during the last tick.
     fn drive_entity(
         &mut self,
-        universe_index: usize,
-        entity: &Entity<C, E, M>,
-        universe: &U,
-        cell_action_executor: &mut FnMut(CA, usize),
+        view: &EntityView<C, E, M, U, T>,
+        cell_action_executor: &mut FnMut(CA, U::Coord),
         self_action_executor: &mut FnMut(SelfAction<C, E, EA>),
-        entity_action_executor: &mut FnMut(EA, usize, Uuid)
+        entity_action_executor: &mut FnMut(EA, EntityHandle),
+        message_executor: &mut FnMut(Recipient, T)
     );
 }
 
@@ -49,20 +64,25 @@ impl<
     EA: EntityAction<C, E>,
     EI: EntityIterator<C, E, M>,
     U: Universe<C, E, M>,
-> Engine<C, E, M, CA, EA, U> for Box<SerialEngine<C, E, M, CA, EA, EI, U>> {
+    T: Clone,
+> Engine<C, E, M, CA, EA, U> for Box<SerialEngine<C, E, M, CA, EA, EI, U, T>> {
     // #[inline(never)]
     fn step<'a>(&'a mut self, mut universe: &'a mut U) {
         // iterate over the universe's entities one at a time, passing their requested actions into the engine's core
         // and applying the results immediately based on its rules
         // TODO: Implement preallocation and preallocation metrics
-        let mut cell_action_buf: Vec<OwnedAction<C, E, CA, EA>>   = Vec::new();
-        let mut self_action_buf: Vec<OwnedAction<C, E, CA, EA>>   = Vec::new();
-        let mut entity_action_buf: Vec<OwnedAction<C, E, CA, EA>> = Vec::new();
-        for (entity_ref, entity_index, universe_index) in universe.get_entities().iter() {
-            let mut cell_action_executor = |cell_action: CA, universe_index: usize| {
+        let mut cell_action_buf: Vec<OwnedAction<C, E, CA, EA, U::Coord>>   = Vec::new();
+        let mut self_action_buf: Vec<OwnedAction<C, E, CA, EA, U::Coord>>   = Vec::new();
+        let mut entity_action_buf: Vec<OwnedAction<C, E, CA, EA, U::Coord>> = Vec::new();
+        let mut message_buf: Vec<SentMessage<T>> = Vec::new();
+        let inboxes = match self.mailbox() {
+            Some(mailbox) => mailbox.take_inboxes(),
+            None => Default::default(),
+        };
+        for (entity_ref, entity_handle, universe_index) in universe.get_entities().iter() {
+            let mut cell_action_executor = |cell_action: CA, universe_index: U::Coord| {
                 let owned_action = OwnedAction {
-                    source_entity_index: entity_index,
-                    source_uuid: entity_ref.uuid,
+                    source_entity: entity_handle,
                     action: Action::CellAction {
                         universe_index: universe_index,
                         action: cell_action,
@@ -74,35 +94,47 @@ impl<
 
             let mut self_action_executor = |self_action: SelfAction<C, E, EA>| {
                 let owned_action = OwnedAction {
-                    source_entity_index: entity_index,
-                    source_uuid: entity_ref.uuid,
+                    source_entity: entity_handle,
                     action: Action::SelfAction(self_action),
                 };
 
                 self_action_buf.push(owned_action);
             };
 
-            let mut entity_action_executor = |entity_action: EA, target_entity_index: usize, target_uuid: Uuid| {
+            let mut entity_action_executor = |entity_action: EA, target_entity: EntityHandle| {
                 let owned_action = OwnedAction {
-                    source_entity_index: entity_index,
-                    source_uuid: entity_ref.uuid,
+                    source_entity: entity_handle,
                     action: Action::EntityAction {
                         action: entity_action,
-                        target_entity_index: target_entity_index,
-                        target_uuid: target_uuid,
+                        target_entity: target_entity,
                     },
                 };
 
                 entity_action_buf.push(owned_action);
             };
 
-            (**self).drive_entity(
+            let mut message_executor = |recipient: Recipient, message: T| {
+                message_buf.push(SentMessage {
+                    sender: entity_handle,
+                    recipient: recipient,
+                    message: message,
+                });
+            };
+
+            let inbox = inboxes.get(&entity_handle).map(|inbox| inbox.as_slice()).unwrap_or(&[]);
+            let view = EntityView::new(
+                &*universe,
+                entity_ref,
+                entity_handle,
                 universe_index,
-                &entity_ref,
-                universe,
+                self.view_distance()
+            ).with_inbox(inbox);
+            (**self).drive_entity(
+                &view,
                 &mut cell_action_executor,
                 &mut self_action_executor,
-                &mut entity_action_executor
+                &mut entity_action_executor,
+                &mut message_executor
             );
         }
 
@@ -113,5 +145,10 @@ impl<
             &self_action_buf,
             &entity_action_buf
         );
+
+        // messages are delivered to the recipients' positions after all actions have been applied
+        if let Some(mailbox) = self.mailbox() {
+            mailbox.deliver(message_buf, &*universe);
+        }
     }
 }
@@ -5,10 +5,7 @@
 use std::clone::Clone;
 use std::fmt::{self, Debug, Formatter};
 use std::marker::PhantomData;
-use std::mem;
 
-use rand::Rng;
-use rand_pcg::Pcg32;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -39,6 +36,9 @@ pub trait MutEntityState: Clone + Default {}
 pub struct Entity<C: CellState, S: EntityState<C>, M: MutEntityState> {
     pub state: S,
     pub mut_state: M,
+    /// Identifies the entity for its entire lifetime.  Entities that haven't been inserted into a
+    /// universe yet have a nil UUID; they're assigned one derived from the universe's seed when
+    /// they're inserted.
     pub uuid: Uuid,
     phantom: PhantomData<C>,
 }
@@ -52,18 +52,8 @@ where
     }
 }
 
-#[thread_local]
-static mut RNG: Pcg32 = unsafe { mem::transmute(0u128) };
-
-pub fn rng() -> &'static mut Pcg32 {
-    unsafe { &mut RNG }
-}
-
-fn uuidv4() -> Uuid {
-    let entropy: (u64, u64) = rng().gen();
-    unsafe { mem::transmute(entropy) }
-}
-
+/// Cloning an entity creates an exact copy of it, including its UUID; the clone represents the same
+/// entity.  Use `spawn_like` to create a new entity with the same state.
 impl<C: CellState, E: EntityState<C>, M: MutEntityState> Clone for Entity<C, E, M>
 where
     E: Clone,
@@ -73,21 +63,29 @@ where
         Entity {
             state: self.state.clone(),
             mut_state: self.mut_state.clone(),
-            uuid: uuidv4(),
+            uuid: self.uuid,
             phantom: PhantomData,
         }
     }
 }
 
 impl<C: CellState, E: EntityState<C>, M: MutEntityState> Entity<C, E, M> {
+    /// Creates a new entity.  It will be assigned a UUID once it's inserted into a universe.
     pub fn new(state: E, mut_state: M) -> Entity<C, E, M> {
         Entity {
             state: state,
             mut_state: mut_state,
-            uuid: uuidv4(),
+            uuid: Uuid::nil(),
             phantom: PhantomData,
         }
     }
+
+    /// Creates a new entity with the same state as this one but its own identity, for example as
+    /// the offspring of this entity.  Like entities created with `new`, it will be assigned a UUID
+    /// once it's inserted into a universe.
+    pub fn spawn_like(&self) -> Self {
+        Entity::new(self.state.clone(), self.mut_state.clone())
+    }
 }
 
 unsafe impl<C: CellState, E: EntityState<C>, M: MutEntityState> Send for Entity<C, E, M>
@@ -96,3 +94,28 @@ where
     E: Send,
 {
 }
+
+#[test]
+fn entity_identity() {
+    use container::EntityContainer;
+    use test_util::{new_entity, CS, ES, MES};
+
+    let mut container: EntityContainer<CS, ES, MES> = EntityContainer::new();
+    let parent = container.insert(new_entity(1), 0);
+    let parent = container.get(parent).unwrap().clone();
+    assert!(!parent.uuid.is_nil());
+
+    let child = parent.spawn_like();
+    assert_eq!(child.state, parent.state);
+    assert!(child.uuid.is_nil());
+    let child = container.insert(child, 1);
+    assert_ne!(container.get(child).unwrap().uuid, parent.uuid);
+
+    // cloning the whole container keeps every entity's identity
+    let cloned = container.clone();
+    let uuids = |container: &EntityContainer<CS, ES, MES>| -> Vec<Uuid> {
+        container.iter().map(|(entity, _, _)| entity.uuid).collect()
+    };
+    assert_eq!(uuids(&cloned), uuids(&container));
+    assert!(uuids(&cloned).contains(&parent.uuid));
+}
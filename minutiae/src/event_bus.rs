@@ -0,0 +1,132 @@
+//! Declares a typed event bus through which the code that applies actions can announce what
+//! happened in the universe ("fish eaten at X", "food spawned") as it happens, rather than having
+//! middleware and servers infer it afterwards from the raw actions or by comparing universe states.
+//!
+//! An `EventBus` is a handle to a shared buffer; clones of it refer to the same buffer, so one can
+//! be held by the engine's action executor and others by any middleware or servers that consume
+//! the events.  The bus must itself be registered as middleware: it's cleared before every tick, so
+//! consumers see exactly the events emitted during the last tick from their `after_render`.
+
+use std::sync::{Arc, Mutex};
+
+use action::{CellAction, EntityAction};
+use cell::CellState;
+use driver::middleware::Middleware;
+use engine::Engine;
+use entity::{EntityState, MutEntityState};
+use universe::Universe;
+
+pub struct EventBus<V> {
+    events: Arc<Mutex<Vec<V>>>,
+}
+
+impl<V> Clone for EventBus<V> {
+    fn clone(&self) -> Self {
+        EventBus {
+            events: Arc::clone(&self.events),
+        }
+    }
+}
+
+impl<V> Default for EventBus<V> {
+    fn default() -> Self { EventBus::new() }
+}
+
+impl<V> EventBus<V> {
+    pub fn new() -> Self {
+        EventBus {
+            events: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Adds an event to the current tick.
+    pub fn emit(&self, event: V) { self.events.lock().unwrap().push(event); }
+
+    /// Discards all events, starting a new tick.
+    pub fn clear(&self) { self.events.lock().unwrap().clear(); }
+
+    /// Returns the number of events emitted during the current tick.
+    pub fn len(&self) -> usize { self.events.lock().unwrap().len() }
+
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    /// Calls the supplied function with all events emitted during the current tick in the order in
+    /// which they were emitted.
+    pub fn with_events<R, F: FnOnce(&[V]) -> R>(&self, f: F) -> R {
+        f(&self.events.lock().unwrap())
+    }
+}
+
+impl<V: Clone> EventBus<V> {
+    /// Returns copies of all events emitted during the current tick.
+    pub fn events(&self) -> Vec<V> { self.with_events(|events| events.to_vec()) }
+}
+
+impl<
+        C: CellState,
+        E: EntityState<C>,
+        M: MutEntityState,
+        CA: CellAction<C>,
+        EA: EntityAction<C, E>,
+        U: Universe<C, E, M>,
+        N: Engine<C, E, M, CA, EA, U>,
+        V,
+    > Middleware<C, E, M, CA, EA, U, N> for EventBus<V>
+{
+    fn before_render(&mut self, _: &mut U) { self.clear(); }
+}
+
+#[test]
+fn events_are_collected_per_tick() {
+    use cell::Cell;
+    use container::EntityHandle;
+    use driver::middleware::event_recorder::{EventRecorder, TickEvents};
+    use lifecycle::DeathCause;
+    use test_util::{new_entity, CS, ES, MES};
+    use universe::{Universe2D, Universe2DConf};
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Eaten {
+        at: usize,
+    }
+
+    /// Removes the eaten entity, announcing where it was eaten, the way an action handler would
+    fn eat(
+        universe: &mut Universe2D<CS, ES, MES>,
+        events: &EventBus<Eaten>,
+        source: EntityHandle,
+        target: EntityHandle,
+    ) {
+        let at = match universe.entities.get_verify(target) {
+            Some((_, universe_index)) => universe_index,
+            None => return,
+        };
+        universe
+            .entities
+            .remove_with_cause(target, DeathCause::Killed(source));
+        events.emit(Eaten { at });
+    }
+
+    let mut universe: Universe2D<CS, ES, MES> = Universe2D::uninitialized();
+    universe.conf = Universe2DConf::square(4);
+    universe.cells = vec![Cell { state: CS(0) }; 16];
+    let shark = universe.entities.insert(new_entity(0), 5);
+    let fish = universe.entities.insert(new_entity(0), 6);
+
+    let bus = EventBus::new();
+    let mut recorder = EventRecorder::new(bus.clone(), 8);
+
+    // the fish can only be eaten once; the second attempt finds it already gone
+    for _ in 0..2 {
+        bus.clear();
+        eat(&mut universe, &bus, shark, fish);
+        universe.entities.advance_tick();
+        recorder.record(universe.entities.tick);
+    }
+
+    assert!(bus.is_empty());
+    assert_eq!(recorder.history, vec![TickEvents {
+        tick: 1,
+        events: vec![Eaten { at: 6 }],
+    }]);
+}
@@ -4,10 +4,14 @@ use universe::Universe2DConf;
 use cell::{Cell, CellState};
 use entity::{Entity, EntityState, MutEntityState};
 
+/// Generates the initial cells and entities of a universe given its configuration.  The returned
+/// vectors are indexed by universe index.  `K` is the configuration type of the universe being
+/// generated, defaulting to that of `Universe2D`.
 pub trait Generator<
     C: CellState,
     E: EntityState<C>,
     M: MutEntityState,
+    K = Universe2DConf,
 > {
-    fn gen(&mut self, conf: &Universe2DConf) -> (Vec<Cell<C>>, Vec<Vec<Entity<C, E, M>>>);
+    fn gen(&mut self, conf: &K) -> (Vec<Cell<C>>, Vec<Vec<Entity<C, E, M>>>);
 }
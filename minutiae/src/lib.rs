@@ -29,7 +29,7 @@ extern crate serde;
 #[cfg(any(feature = "serde", feature = "client"))]
 extern crate serde_derive;
 
-#[cfg(any(feature = "server", feature = "client"))]
+#[cfg(any(feature = "server", feature = "client", all(test, feature = "serde")))]
 extern crate bincode;
 
 #[cfg(any(feature = "server", feature = "client"))]
@@ -54,21 +54,32 @@ pub mod driver;
 pub mod emscripten;
 pub mod engine;
 pub mod entity;
+pub mod event_bus;
 pub mod generator;
+pub mod lifecycle;
+pub mod mailbox;
+pub mod neighborhood;
+pub mod rng;
 #[cfg(any(feature = "server", feature = "client"))]
 pub mod server;
 pub mod universe;
+#[cfg(test)]
+mod test_util;
 pub mod util;
+pub mod view;
 
 pub mod prelude {
     //! Utility module for re-exporting some commonly used traits
     pub use action::{Action, CellAction, EntityAction, OwnedAction, SelfAction};
     pub use cell::{Cell, CellState};
-    pub use container::EntityContainer;
+    pub use container::{EntityContainer, EntityHandle};
     pub use driver::{middleware::Middleware, Driver};
     pub use engine::Engine;
     pub use entity::{Entity, EntityState, MutEntityState};
     pub use generator::Generator;
-    pub use universe::{Universe, Universe2DConf, Universe2DConf as UniverseConf};
+    pub use universe::{
+        BoundaryPolicy, ContiguousUniverse, Universe, Universe2DConf,
+        Universe2DConf as UniverseConf,
+    };
     pub use util::*;
 }
@@ -0,0 +1,144 @@
+//! Records the entities that are added to and removed from a universe during each tick, allowing
+//! middleware and servers to react to spawns and deaths without having to compare the contents of
+//! the universe between ticks.
+//!
+//! Events are recorded by the `EntityContainer` as they happen once recording has been enabled with
+//! `EntityContainer::record_lifecycle_events`.  When the tick ends, they become available through
+//! `EntityContainer::lifecycle_events` until the end of the following tick.
+
+use std::{hash::Hash, mem};
+
+use cell::CellState;
+use container::EntityHandle;
+use entity::{Entity, EntityState, MutEntityState};
+use uuid::Uuid;
+
+/// The reason that an entity was removed from the universe
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DeathCause {
+    /// The entity removed itself with `SelfAction::Suicide`
+    Suicide,
+    /// The entity was removed by an action of another entity, such as being eaten
+    Killed(EntityHandle),
+    /// The entity moved out of bounds of a universe with the `Absorb` boundary policy
+    Absorbed,
+    /// The entity was removed directly without a more specific cause
+    Removed,
+}
+
+/// An entity that was added to the universe
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Spawn<I> {
+    pub handle: EntityHandle,
+    pub uuid: Uuid,
+    /// Where the entity was placed
+    pub position: I,
+    /// The entity that spawned it, if any
+    pub parent: Option<EntityHandle>,
+}
+
+/// An entity that was removed from the universe
+#[derive(Clone, Debug)]
+pub struct Death<C: CellState, E: EntityState<C>, M: MutEntityState, I> {
+    pub handle: EntityHandle,
+    /// Where the entity was when it was removed
+    pub position: I,
+    pub cause: DeathCause,
+    /// The entity's final state
+    pub entity: Entity<C, E, M>,
+}
+
+#[derive(Clone, Debug)]
+pub enum LifecycleEvent<C: CellState, E: EntityState<C>, M: MutEntityState, I> {
+    Spawn(Spawn<I>),
+    Death(Death<C, E, M, I>),
+}
+
+/// The lifecycle events of the current tick and of the last completed one
+#[derive(Clone, Debug)]
+pub struct Lifecycle<C: CellState, E: EntityState<C>, M: MutEntityState, I: Copy + Eq + Hash> {
+    /// Whether events are being recorded
+    pub recording: bool,
+    current: Vec<LifecycleEvent<C, E, M, I>>,
+    last_tick: Vec<LifecycleEvent<C, E, M, I>>,
+}
+
+impl<C: CellState, E: EntityState<C>, M: MutEntityState, I: Copy + Eq + Hash> Default
+    for Lifecycle<C, E, M, I>
+{
+    fn default() -> Self {
+        Lifecycle {
+            recording: false,
+            current: Vec::new(),
+            last_tick: Vec::new(),
+        }
+    }
+}
+
+impl<C: CellState, E: EntityState<C>, M: MutEntityState, I: Copy + Eq + Hash>
+    Lifecycle<C, E, M, I>
+{
+    pub fn record(&mut self, event: LifecycleEvent<C, E, M, I>) { self.current.push(event); }
+
+    /// Returns the events recorded during the last completed tick in the order they happened.
+    pub fn last_tick(&self) -> &[LifecycleEvent<C, E, M, I>] { &self.last_tick }
+
+    /// Marks the end of a tick, discarding the events of the previous one.
+    pub fn advance_tick(&mut self) {
+        mem::swap(&mut self.current, &mut self.last_tick);
+        self.current.clear();
+    }
+}
+
+#[test]
+fn lifecycle_events() {
+    use container::EntityContainer;
+    use test_util::{new_entity, CS, ES, MES};
+    use universe::BoundaryPolicy;
+    use util::translate_entity;
+
+    let mut container: EntityContainer<CS, ES, MES> = EntityContainer::new();
+    // nothing is recorded unless something consumes the events
+    container.insert(new_entity(0), 0);
+    container.advance_tick();
+    assert!(container.lifecycle_events().is_empty());
+
+    container.record_lifecycle_events(true);
+    let parent = container.insert(new_entity(1), 0);
+    let child = container.insert_child(new_entity(2), 1, Some(parent));
+    // events only become visible once the tick has ended
+    assert!(container.lifecycle_events().is_empty());
+    container.advance_tick();
+
+    let spawns: Vec<_> = container
+        .lifecycle_events()
+        .iter()
+        .map(|event| match *event {
+            LifecycleEvent::Spawn(ref spawn) => (spawn.handle, spawn.position, spawn.parent),
+            LifecycleEvent::Death(_) => panic!("no entities were removed"),
+        })
+        .collect();
+    assert_eq!(spawns, vec![(parent, 0, None), (child, 1, Some(parent))]);
+
+    container.remove_with_cause(child, DeathCause::Killed(parent));
+    translate_entity(-1, 0, &mut container, parent, 4, 4, BoundaryPolicy::Absorb);
+    container.advance_tick();
+
+    let deaths: Vec<_> = container
+        .lifecycle_events()
+        .iter()
+        .map(|event| match *event {
+            LifecycleEvent::Death(ref death) => (death.handle, death.cause, death.entity.state.0),
+            LifecycleEvent::Spawn(_) => panic!("no entities were spawned"),
+        })
+        .collect();
+    assert_eq!(deaths, vec![
+        (child, DeathCause::Killed(parent), 2),
+        (parent, DeathCause::Absorbed, 1),
+    ]);
+
+    container.advance_tick();
+    assert!(container.lifecycle_events().is_empty());
+}
@@ -0,0 +1,175 @@
+//! Allows entities to send each other messages.  Messages sent by entity drivers during a tick are
+//! collected and delivered once all of the tick's actions have been applied, so the recipients see
+//! them through their `EntityView`s in the next tick.  Every entity's inbox holds a bounded number
+//! of messages; messages sent to an entity whose inbox is full are dropped.
+
+use std::{collections::HashMap, mem};
+
+use cell::CellState;
+use container::EntityHandle;
+use entity::{EntityState, MutEntityState};
+use universe::Universe;
+
+/// The entities to which a message is sent
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Recipient {
+    /// A single entity
+    Entity(EntityHandle),
+    /// All entities other than the sender that are within the supplied view distance of the
+    /// sender once the tick's actions have been applied
+    Within(usize),
+}
+
+/// A message along with the entity that sent it
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Envelope<T> {
+    pub sender: EntityHandle,
+    pub message: T,
+}
+
+/// A message that's been sent but not yet delivered
+#[derive(Clone, Debug)]
+pub struct SentMessage<T> {
+    pub sender: EntityHandle,
+    pub recipient: Recipient,
+    pub message: T,
+}
+
+/// Holds the messages that were delivered at the end of the last tick until they're read.
+#[derive(Clone, Debug)]
+pub struct Mailbox<T> {
+    /// The maximum number of messages that each inbox can hold
+    pub capacity: usize,
+    inboxes: HashMap<EntityHandle, Vec<Envelope<T>>>,
+    /// The number of messages that have been dropped because their recipients' inboxes were full
+    pub dropped: usize,
+}
+
+impl<T: Clone> Mailbox<T> {
+    pub fn new(capacity: usize) -> Self {
+        Mailbox {
+            capacity,
+            inboxes: HashMap::new(),
+            dropped: 0,
+        }
+    }
+
+    /// Returns the messages that were delivered to the supplied entity at the end of the last tick.
+    pub fn inbox(&self, handle: EntityHandle) -> &[Envelope<T>] {
+        match self.inboxes.get(&handle) {
+            Some(inbox) => inbox,
+            None => &[],
+        }
+    }
+
+    /// Removes and returns the contents of all inboxes.
+    pub fn take_inboxes(&mut self) -> HashMap<EntityHandle, Vec<Envelope<T>>> {
+        mem::replace(&mut self.inboxes, HashMap::new())
+    }
+
+    /// Replaces the contents of all inboxes with the supplied messages.  Messages to entities that
+    /// no longer exist, as well as broadcasts from them, are discarded.
+    pub fn deliver<
+        C: CellState,
+        E: EntityState<C>,
+        M: MutEntityState,
+        U: Universe<C, E, M>,
+        I: IntoIterator<Item = SentMessage<T>>,
+    >(
+        &mut self,
+        sent: I,
+        universe: &U,
+    ) {
+        self.inboxes.clear();
+        let entities = universe.get_entities();
+
+        for SentMessage {
+            sender,
+            recipient,
+            message,
+        } in sent
+        {
+            match recipient {
+                Recipient::Entity(handle) =>
+                    if entities.contains(handle) {
+                        self.push(handle, sender, message);
+                    },
+                Recipient::Within(view_distance) => {
+                    let position = match entities.get_verify(sender) {
+                        Some((_, position)) => position,
+                        None => continue,
+                    };
+
+                    for handle in universe.entities_within(position, view_distance) {
+                        if handle != sender {
+                            self.push(handle, sender, message.clone());
+                        }
+                    }
+                },
+            }
+        }
+    }
+
+    fn push(&mut self, recipient: EntityHandle, sender: EntityHandle, message: T) {
+        let inbox = self.inboxes.entry(recipient).or_insert_with(Vec::new);
+        if inbox.len() >= self.capacity {
+            self.dropped += 1;
+            return;
+        }
+
+        inbox.push(Envelope { sender, message });
+    }
+}
+
+#[test]
+fn messages_are_delivered_and_bounded() {
+    use cell::Cell;
+    use test_util::{new_entity, CS, ES, MES};
+    use universe::{Universe2D, Universe2DConf};
+    use util::get_index;
+
+    let mut universe: Universe2D<CS, ES, MES> = Universe2D::uninitialized();
+    universe.conf = Universe2DConf::square(8);
+    universe.cells = vec![Cell { state: CS(0) }; 64];
+    let leader = universe.entities.insert(new_entity(0), get_index(2, 2, 8));
+    let near = universe.entities.insert(new_entity(0), get_index(3, 4, 8));
+    let far = universe.entities.insert(new_entity(0), get_index(7, 7, 8));
+    let dead = universe.entities.insert(new_entity(0), get_index(0, 0, 8));
+    universe.entities.remove(dead);
+
+    let sent = |sender, recipient, message| SentMessage {
+        sender,
+        recipient,
+        message,
+    };
+    let mut mailbox: Mailbox<&'static str> = Mailbox::new(2);
+    mailbox.deliver(
+        vec![
+            sent(leader, Recipient::Within(2), "follow me"),
+            sent(near, Recipient::Entity(far), "hello"),
+            sent(near, Recipient::Entity(dead), "hello?"),
+            sent(leader, Recipient::Entity(near), "one"),
+            sent(far, Recipient::Entity(near), "two"),
+        ],
+        &universe,
+    );
+
+    let messages = |mailbox: &Mailbox<&'static str>, handle| -> Vec<(EntityHandle, &str)> {
+        mailbox
+            .inbox(handle)
+            .iter()
+            .map(|envelope| (envelope.sender, envelope.message))
+            .collect()
+    };
+    assert_eq!(messages(&mailbox, near), vec![(leader, "follow me"), (leader, "one")]);
+    assert_eq!(messages(&mailbox, far), vec![(near, "hello")]);
+    assert!(messages(&mailbox, leader).is_empty());
+    assert!(messages(&mailbox, dead).is_empty());
+    assert_eq!(mailbox.dropped, 1);
+
+    // messages are only delivered for a single tick
+    mailbox.deliver(Vec::new(), &universe);
+    assert!(messages(&mailbox, near).is_empty());
+}
@@ -0,0 +1,180 @@
+//! Defines the shapes of the neighborhoods that cells and entities can see.  A `Neighborhood` is a
+//! set of offsets from a center cell which is independent of any particular universe; it's resolved
+//! into the coordinates of actual cells by applying the universe's boundary policy.  Neighborhoods
+//! never include the center itself.
+//!
+//! For two-dimensional universes, offsets are `(x, y)` offsets.  For hexagonal universes, they're
+//! `(q, r)` offsets in axial coordinates.
+
+use std::collections::HashSet;
+
+use universe::BoundaryPolicy;
+use util::DistanceMetric;
+
+pub trait Neighborhood {
+    /// The largest offset along either axis of any cell in the neighborhood
+    fn extent(&self) -> usize;
+
+    /// Returns `true` if the cell at the supplied offset from the center is part of the
+    /// neighborhood.  Only called for offsets within `extent` of the center.
+    fn contains(&self, x_offset: isize, y_offset: isize) -> bool;
+}
+
+/// All cells within a square of the supplied radius, including diagonals
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Moore(pub usize);
+
+impl Neighborhood for Moore {
+    fn extent(&self) -> usize { self.0 }
+
+    fn contains(&self, x_offset: isize, y_offset: isize) -> bool {
+        DistanceMetric::Chebyshev.is_within((x_offset, y_offset), self.0)
+    }
+}
+
+/// All cells within the supplied Manhattan distance, forming a diamond
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VonNeumann(pub usize);
+
+impl Neighborhood for VonNeumann {
+    fn extent(&self) -> usize { self.0 }
+
+    fn contains(&self, x_offset: isize, y_offset: isize) -> bool {
+        DistanceMetric::Manhattan.is_within((x_offset, y_offset), self.0)
+    }
+}
+
+/// All cells within the supplied straight-line distance
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Circular(pub usize);
+
+impl Neighborhood for Circular {
+    fn extent(&self) -> usize { self.0 }
+
+    fn contains(&self, x_offset: isize, y_offset: isize) -> bool {
+        DistanceMetric::Euclidean.is_within((x_offset, y_offset), self.0)
+    }
+}
+
+/// All hexes within the supplied number of steps.  Offsets are axial, so this is meant to be used
+/// with a `HexUniverse`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HexRange(pub usize);
+
+impl Neighborhood for HexRange {
+    fn extent(&self) -> usize { self.0 }
+
+    fn contains(&self, q_offset: isize, r_offset: isize) -> bool {
+        let s_offset = -q_offset - r_offset;
+        let distance = (q_offset.abs() + r_offset.abs() + s_offset.abs()) / 2;
+        distance as usize <= self.0
+    }
+}
+
+/// An arbitrary, user-defined set of offsets
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Stencil {
+    offsets: Vec<(isize, isize)>,
+    extent: usize,
+}
+
+impl Stencil {
+    /// Creates a stencil made up of the supplied offsets.  The `(0, 0)` offset is ignored.
+    pub fn new(offsets: Vec<(isize, isize)>) -> Self {
+        let extent = offsets
+            .iter()
+            .map(|&(x, y)| x.abs().max(y.abs()) as usize)
+            .max()
+            .unwrap_or(0);
+        Stencil { offsets, extent }
+    }
+
+    pub fn offsets(&self) -> &[(isize, isize)] { &self.offsets }
+}
+
+impl Neighborhood for Stencil {
+    fn extent(&self) -> usize { self.extent }
+
+    fn contains(&self, x_offset: isize, y_offset: isize) -> bool {
+        self.offsets.contains(&(x_offset, y_offset))
+    }
+}
+
+/// Visits all offsets that are part of the neighborhood in row-major order.
+pub fn iter_offsets<'a, N: Neighborhood + ?Sized>(
+    neighborhood: &'a N,
+) -> impl Iterator<Item = (isize, isize)> + 'a {
+    let extent = neighborhood.extent() as isize;
+    (-extent..=extent)
+        .flat_map(move |y_offset| (-extent..=extent).map(move |x_offset| (x_offset, y_offset)))
+        .filter(move |&(x_offset, y_offset)| {
+            (x_offset, y_offset) != (0, 0) && neighborhood.contains(x_offset, y_offset)
+        })
+}
+
+/// Visits the coordinates of all cells in the neighborhood of `(cur_x, cur_y)` in a universe with
+/// the supplied dimensions and boundary policy.  Cells outside of the universe are skipped; in a
+/// wrapping universe, neighborhoods that are larger than the universe never visit the same cell
+/// twice.
+pub fn iter_neighborhood<'a, N: Neighborhood + ?Sized>(
+    neighborhood: &'a N,
+    cur_x: usize,
+    cur_y: usize,
+    universe_width: usize,
+    universe_height: usize,
+    boundary: BoundaryPolicy,
+) -> impl Iterator<Item = (usize, usize)> + 'a {
+    // a wrapping neighborhood that's larger than the universe reaches some cells through more than
+    // one offset; only the first of them is visited
+    let diameter = 2 * neighborhood.extent() + 1;
+    let may_repeat = boundary == BoundaryPolicy::Wrap
+        && (diameter > universe_width || diameter > universe_height);
+    let mut visited = HashSet::new();
+
+    iter_offsets(neighborhood).filter_map(move |(x_offset, y_offset)| {
+        let x = boundary.resolve_visible(cur_x as isize + x_offset, universe_width)?;
+        let y = boundary.resolve_visible(cur_y as isize + y_offset, universe_height)?;
+        if (x, y) == (cur_x, cur_y) || (may_repeat && !visited.insert((x, y))) {
+            return None;
+        }
+        Some((x, y))
+    })
+}
+
+#[test]
+fn neighborhood_shapes() {
+    assert_eq!(iter_offsets(&Moore(1)).count(), 8);
+    assert_eq!(iter_offsets(&VonNeumann(1)).collect::<Vec<_>>(), vec![
+        (0, -1),
+        (-1, 0),
+        (1, 0),
+        (0, 1)
+    ]);
+    assert_eq!(iter_offsets(&VonNeumann(2)).count(), 12);
+    assert_eq!(iter_offsets(&Circular(2)).count(), 12);
+    assert_eq!(iter_offsets(&Circular(3)).count(), 28);
+    assert_eq!(iter_offsets(&HexRange(1)).count(), 6);
+    assert_eq!(iter_offsets(&HexRange(2)).count(), 18);
+
+    let knight = Stencil::new(vec![(1, 2), (2, 1), (-1, -2), (0, 0)]);
+    assert_eq!(knight.extent(), 2);
+    assert_eq!(iter_offsets(&knight).collect::<Vec<_>>(), vec![(-1, -2), (2, 1), (1, 2)]);
+
+    let at_corner = |boundary| iter_neighborhood(&VonNeumann(1), 0, 0, 4, 4, boundary).count();
+    assert_eq!(at_corner(BoundaryPolicy::Clip), 2);
+    assert_eq!(at_corner(BoundaryPolicy::Wrap), 4);
+    // a neighborhood larger than a wrapping universe visits every other cell exactly once
+    let mut wrapped: Vec<_> =
+        iter_neighborhood(&Moore(3), 1, 1, 3, 3, BoundaryPolicy::Wrap).collect();
+    assert_eq!(wrapped.len(), 8);
+    wrapped.sort();
+    wrapped.dedup();
+    assert_eq!(wrapped.len(), 8);
+    // offsets of asymmetric shapes are only dropped if they reach a cell that was already visited
+    let wrapped = |offsets, size| -> Vec<(usize, usize)> {
+        iter_neighborhood(&Stencil::new(offsets), 0, 0, size, size, BoundaryPolicy::Wrap).collect()
+    };
+    assert_eq!(wrapped(vec![(1, 2), (2, 1), (-1, -2)], 4), vec![(3, 2), (2, 1), (1, 2)]);
+    assert_eq!(wrapped(vec![(1, 0), (2, 0)], 3), vec![(1, 0), (2, 0)]);
+    assert_eq!(wrapped(vec![(1, 0), (-2, 0)], 3), vec![(1, 0)]);
+}
@@ -7,7 +7,8 @@ use std::{
     cmp::{Ord, Ordering},
     fmt::{self, Debug, Formatter},
     marker::PhantomData,
-    sync::{Arc, RwLock},
+    mem,
+    sync::{Arc, Mutex, RwLock},
 };
 
 use futures::{
@@ -18,6 +19,8 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use action::OwnedAction;
+use event_bus::EventBus;
+use lifecycle::{Death, LifecycleEvent, Spawn};
 
 use super::{ClientMessage, ServerLogic, ServerMessage, Tys};
 use prelude::*;
@@ -190,15 +193,23 @@ pub struct HybridServer<
     event_generator: fn(
         universe: &mut T::U,
         seq: u32,
-        cell_actions: &[OwnedAction<T::C, T::E, T::CA, T::EA>],
-        self_actions: &[OwnedAction<T::C, T::E, T::CA, T::EA>],
-        entity_actions: &[OwnedAction<T::C, T::E, T::CA, T::EA>],
+        cell_actions: &[OwnedAction<T::C, T::E, T::CA, T::EA, T::I>],
+        self_actions: &[OwnedAction<T::C, T::E, T::CA, T::EA, T::I>],
+        entity_actions: &[OwnedAction<T::C, T::E, T::CA, T::EA, T::I>],
     ) -> Option<Vec<T::V>>,
     client_event_handler:
         fn(universe: &mut T::U, seq: u32, custom_action: HCMT) -> ClientEventAction<T>,
-    self_actions: Arc<RwLock<Vec<OwnedAction<T::C, T::E, T::CA, T::EA>>>>,
-    cell_actions: Arc<RwLock<Vec<OwnedAction<T::C, T::E, T::CA, T::EA>>>>,
-    entity_actions: Arc<RwLock<Vec<OwnedAction<T::C, T::E, T::CA, T::EA>>>>,
+    lifecycle_event_generator: Option<
+        fn(universe: &T::U, event: &LifecycleEvent<T::C, T::E, T::M, T::I>) -> Option<T::V>,
+    >,
+    /// Events generated from lifecycle events that haven't been sent yet.  This is only ever
+    /// accessed through `&mut self`; the mutex allows the server to be `Sync` without requiring
+    /// events to be.
+    pending_events: Mutex<Vec<T::V>>,
+    event_bus: Option<EventBus<T::V>>,
+    self_actions: Arc<RwLock<Vec<OwnedAction<T::C, T::E, T::CA, T::EA, T::I>>>>,
+    cell_actions: Arc<RwLock<Vec<OwnedAction<T::C, T::E, T::CA, T::EA, T::I>>>>,
+    entity_actions: Arc<RwLock<Vec<OwnedAction<T::C, T::E, T::CA, T::EA, T::I>>>>,
 }
 
 impl<
@@ -252,7 +263,15 @@ where
             };
         }
 
-        let pending_messages: Option<Vec<T::V>> = None;
+        let mut pending_events = mem::replace(self.pending_events.get_mut().unwrap(), Vec::new());
+        if let Some(ref event_bus) = self.event_bus {
+            event_bus.with_events(|events| pending_events.extend_from_slice(events));
+        }
+        let pending_messages: Option<Vec<T::V>> = if pending_events.is_empty() {
+            None
+        } else {
+            Some(pending_events)
+        };
 
         // use the user-defined logic to get the events to pass through to the clients.  The
         // internal action buffers are used as the sources for this.
@@ -300,6 +319,16 @@ where
             HybridClientMessageContents::Custom(hcmt) => box self.handle_custom_message(hcmt),
         }
     }
+
+    fn on_spawn(&mut self, universe: &T::U, spawn: &Spawn<T::I>) {
+        self.generate_lifecycle_event(universe, LifecycleEvent::Spawn(spawn.clone()));
+    }
+
+    fn on_death(&mut self, universe: &T::U, death: &Death<T::C, T::E, T::M, T::I>) {
+        self.generate_lifecycle_event(universe, LifecycleEvent::Death(death.clone()));
+    }
+
+    fn observes_lifecycle(&self) -> bool { self.lifecycle_event_generator.is_some() }
 }
 
 impl<
@@ -321,7 +350,7 @@ impl<
         >,
     > HybridServer<HCMT, T>
 where
-    OwnedAction<T::C, T::E, T::CA, T::EA>: Clone,
+    OwnedAction<T::C, T::E, T::CA, T::EA, T::I>: Clone,
     T::V: Clone,
     T::Snapshot: Clone,
 {
@@ -331,16 +360,16 @@ where
     pub fn hook_handler(
         action_executor: fn(
             &mut U,
-            &[OwnedAction<CS, ES, CA, EA>],
-            &[OwnedAction<CS, ES, CA, EA>],
-            &[OwnedAction<CS, ES, CA, EA>],
+            &[OwnedAction<CS, ES, CA, EA, T::I>],
+            &[OwnedAction<CS, ES, CA, EA, T::I>],
+            &[OwnedAction<CS, ES, CA, EA, T::I>],
         ),
         event_generator: fn(
             universe: &mut U,
             seq: u32,
-            cell_actions: &[OwnedAction<CS, ES, CA, EA>],
-            self_actions: &[OwnedAction<CS, ES, CA, EA>],
-            entity_actions: &[OwnedAction<CS, ES, CA, EA>],
+            cell_actions: &[OwnedAction<CS, ES, CA, EA, T::I>],
+            self_actions: &[OwnedAction<CS, ES, CA, EA, T::I>],
+            entity_actions: &[OwnedAction<CS, ES, CA, EA, T::I>],
         ) -> Option<Vec<T::V>>,
         client_event_handler: fn(
             universe: &mut T::U,
@@ -350,9 +379,9 @@ where
     ) -> (
         impl Fn(
             &mut U,
-            &[OwnedAction<CS, ES, CA, EA>],
-            &[OwnedAction<CS, ES, CA, EA>],
-            &[OwnedAction<CS, ES, CA, EA>],
+            &[OwnedAction<CS, ES, CA, EA, T::I>],
+            &[OwnedAction<CS, ES, CA, EA, T::I>],
+            &[OwnedAction<CS, ES, CA, EA, T::I>],
         ),
         Self,
     ) {
@@ -366,9 +395,9 @@ where
         // original logic
         let hooked_handler =
             move |universe: &mut T::U,
-                  self_actions: &[OwnedAction<T::C, T::E, T::CA, T::EA>],
-                  cell_actions: &[OwnedAction<T::C, T::E, T::CA, T::EA>],
-                  entity_actions: &[OwnedAction<T::C, T::E, T::CA, T::EA>]| {
+                  self_actions: &[OwnedAction<T::C, T::E, T::CA, T::EA, T::I>],
+                  cell_actions: &[OwnedAction<T::C, T::E, T::CA, T::EA, T::I>],
+                  entity_actions: &[OwnedAction<T::C, T::E, T::CA, T::EA, T::I>]| {
                 // copy the actions into our internal buffers so that they can be used later once we
                 // have access to the universe to create our final events.
                 *&mut *self_action_buf.write().unwrap() = self_actions.into();
@@ -382,13 +411,46 @@ where
         (hooked_handler, hybrid_server)
     }
 
+    /// Uses the supplied function to create events from the entities spawned and removed during
+    /// each tick.  They're sent to the clients along with the events from the event generator.
+    pub fn with_lifecycle_events(
+        mut self,
+        lifecycle_event_generator: fn(
+            universe: &T::U,
+            event: &LifecycleEvent<CS, ES, MES, T::I>,
+        ) -> Option<T::V>,
+    ) -> Self {
+        self.lifecycle_event_generator = Some(lifecycle_event_generator);
+        self
+    }
+
+    /// Sends the events emitted on the supplied bus during each tick to the clients along with the
+    /// events from the event generator.  The bus must also be registered as middleware.
+    pub fn with_event_bus(mut self, event_bus: EventBus<T::V>) -> Self {
+        self.event_bus = Some(event_bus);
+        self
+    }
+
+    fn generate_lifecycle_event(
+        &mut self,
+        universe: &T::U,
+        event: LifecycleEvent<CS, ES, MES, T::I>,
+    ) {
+        let generated = self
+            .lifecycle_event_generator
+            .and_then(|generator| generator(universe, &event));
+        if let Some(generated) = generated {
+            self.pending_events.get_mut().unwrap().push(generated);
+        }
+    }
+
     fn new(
         event_generator: fn(
             universe: &mut T::U,
             seq: u32,
-            cell_actions: &[OwnedAction<T::C, T::E, T::CA, T::EA>],
-            self_actions: &[OwnedAction<T::C, T::E, T::CA, T::EA>],
-            entity_actions: &[OwnedAction<T::C, T::E, T::CA, T::EA>],
+            cell_actions: &[OwnedAction<T::C, T::E, T::CA, T::EA, T::I>],
+            self_actions: &[OwnedAction<T::C, T::E, T::CA, T::EA, T::I>],
+            entity_actions: &[OwnedAction<T::C, T::E, T::CA, T::EA, T::I>],
         ) -> Option<Vec<T::V>>,
         client_event_handler: fn(
             universe: &mut T::U,
@@ -399,6 +461,9 @@ where
         HybridServer {
             event_generator,
             client_event_handler,
+            lifecycle_event_generator: None,
+            pending_events: Mutex::new(Vec::new()),
+            event_bus: None,
             snapshot_requests: Vec::new(),
             custom_actions: Vec::new(),
             self_actions: Arc::new(RwLock::new(Vec::new())),
@@ -2,6 +2,7 @@
 
 use std::cmp::{PartialOrd, Ord};
 use std::fmt::Debug;
+use std::hash::Hash;
 use std::io::BufReader;
 use std::sync::{Arc, RwLock};
 
@@ -13,6 +14,7 @@ use futures::Future;
 use serde::{Serialize, Deserialize};
 use uuid::Uuid;
 
+use lifecycle::{Death, Spawn};
 use prelude::*;
 
 #[cfg(feature = "server")]
@@ -39,15 +41,15 @@ pub trait Tys : Clone + Copy + Send {
     type M: MutEntityState;
     type CA: CellAction<Self::C>;
     type EA: EntityAction<Self::C, Self::E>;
-    type I: Ord + Copy;
-    type U: Universe<Self::C, Self::E, Self::M>;
+    type I: Ord + Copy + Hash;
+    type U: Universe<Self::C, Self::E, Self::M, Coord = Self::I>;
     type V: Event<Self> = ();
 
     #[cfg(not(any(feature = "thin", feature = "hybrid", feature = "fat")))]
     type Snapshot = ();
 
     #[cfg(feature = "thin")]
-    type Snapshot = Vec<Color>;
+    type Snapshot = ColorSnapshot;
 
     #[cfg(feature = "hybrid")]
     type Snapshot = Self::U;
@@ -151,6 +153,13 @@ pub trait ServerLogic<T: Tys>: Sync {
         seq: u32,
         T::ClientMessage
     ) -> Box<Future<Item=Option<T::ServerMessage>, Error=!>>;
+    /// Called after every tick, before `tick`, for each entity that was added to the universe.
+    fn on_spawn(&mut self, _: &T::U, _: &Spawn<T::I>) {}
+    /// Called after every tick, before `tick`, for each entity that was removed from the universe.
+    fn on_death(&mut self, _: &T::U, _: &Death<T::C, T::E, T::M, T::I>) {}
+    /// Returns `true` if `on_spawn` or `on_death` are implemented; see
+    /// `Middleware::observes_lifecycle`.
+    fn observes_lifecycle(&self) -> bool { false }
 }
 
 impl<'d, T> CompressedMessage for T where T:Debug + Eq + CompressedMessage, for<'de> Self: Deserialize<'de> {}
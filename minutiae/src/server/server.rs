@@ -27,6 +27,7 @@ use websocket::client::async::Framed;
 
 use engine::Engine;
 use driver::middleware::Middleware;
+use lifecycle::{Death, Spawn};
 
 use super::*;
 
@@ -369,4 +370,14 @@ impl<
         }
         self.seq.fetch_add(1, Ordering::Relaxed);
     }
+
+    fn on_spawn(&mut self, universe: &T::U, spawn: &Spawn<T::I>) {
+        self.logic.lock().unwrap().on_spawn(universe, spawn);
+    }
+
+    fn on_death(&mut self, universe: &T::U, death: &Death<T::C, T::E, T::M, T::I>) {
+        self.logic.lock().unwrap().on_death(universe, death);
+    }
+
+    fn observes_lifecycle(&self) -> bool { self.logic.lock().unwrap().observes_lifecycle() }
 }
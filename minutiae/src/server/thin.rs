@@ -25,7 +25,7 @@ pub struct ThinServerMessage {
     pub contents: ThinServerMessageContents,
 }
 
-impl ServerMessage<Vec<Color>> for ThinServerMessage {
+impl ServerMessage<ColorSnapshot> for ThinServerMessage {
     fn get_seq(&self) -> u32 { self.seq }
 
     fn is_snapshot(&self) -> bool {
@@ -36,7 +36,7 @@ impl ServerMessage<Vec<Color>> for ThinServerMessage {
         }
     }
 
-    fn get_snapshot(self) -> Option<Vec<Color>> {
+    fn get_snapshot(self) -> Option<ColorSnapshot> {
         match self.contents {
             ThinServerMessageContents::Snapshot(snap) => Some(snap),
             _ => None,
@@ -55,7 +55,16 @@ impl Ord for ThinServerMessage {
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ThinServerMessageContents {
     Diff(Vec<Diff>),
-    Snapshot(Vec<Color>),
+    Snapshot(ColorSnapshot),
+}
+
+/// The full pixel-level representation of the universe along with its dimensions, sent to clients
+/// so that they can initialize or resynchronize their image of the universe.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColorSnapshot {
+    pub width: u32,
+    pub height: u32,
+    pub colors: Vec<Color>,
 }
 
 /// Encodes the difference between two different steps of a simulation.  Currently simply contains a
@@ -97,31 +106,44 @@ impl ClientMessage for ThinClientMessage {
     }
 }
 
+/// Server logic that renders the universe into colors and transmits the differences between ticks.
+/// The iterator produces the coordinates of the universe that make up the pixels of the image in
+/// row-major order.
 #[derive(Clone)]
-pub struct ColorServer<T: Tys, I: ExactSizeIterator<Item = usize>> {
+pub struct ColorServer<T: Tys, I: ExactSizeIterator<Item = T::I>> {
     pub colors: Arc<RwLock<Vec<Color>>>,
     pub color_calculator: fn(
         &Cell<T::C>,
-        entity_indexes: &[usize],
-        entity_container: &EntityContainer<T::C, T::E, T::M>,
+        entity_handles: &[EntityHandle],
+        entity_container: &EntityContainer<T::C, T::E, T::M, T::I>,
     ) -> Color,
     pub iterator: fn(usize, usize) -> I,
     pub start_index: usize,
     pub end_index: usize,
+    /// Dimensions of the image produced by the iterator, sent along with snapshots
+    pub width: u32,
+    pub height: u32,
 }
 
-impl<T: Tys, I: ExactSizeIterator<Item = usize> + Clone + 'static> ColorServer<T, I> {
+impl<T: Tys, I: ExactSizeIterator<Item = T::I> + Clone + 'static> ColorServer<T, I> {
     pub fn new(
         color_calculator: fn(
             &Cell<T::C>,
-            entity_indexes: &[usize],
-            entity_container: &EntityContainer<T::C, T::E, T::M>,
+            entity_handles: &[EntityHandle],
+            entity_container: &EntityContainer<T::C, T::E, T::M, T::I>,
         ) -> Color,
         iterator: fn(usize, usize) -> I,
         default_start_index: usize,
         default_end_index: usize,
+        width: u32,
+        height: u32,
     ) -> Self {
         let grid_size = iterator(default_start_index, default_end_index).len();
+        assert_eq!(
+            grid_size,
+            width as usize * height as usize,
+            "The iterator must visit exactly `width * height` universe indexes!"
+        );
 
         ColorServer {
             colors: Arc::new(RwLock::new(vec![Color([0, 0, 0,]); grid_size])),
@@ -129,13 +151,15 @@ impl<T: Tys, I: ExactSizeIterator<Item = usize> + Clone + 'static> ColorServer<T
             iterator,
             start_index: default_start_index,
             end_index: default_end_index,
+            width,
+            height,
         }
     }
 }
 
 impl<
         T: Tys<ServerMessage = ThinServerMessage, ClientMessage = ThinClientMessage>,
-        I: ExactSizeIterator<Item = usize> + Clone + 'static,
+        I: ExactSizeIterator<Item = T::I> + Clone + 'static,
     > ServerLogic<T> for ColorServer<T, I>
 where
     ThinServerMessage: ServerMessage<<T as Tys>::Snapshot>,
@@ -154,12 +178,12 @@ where
             .into_iter()
             .enumerate()
         {
-            let entity_indexes = universe.get_entities().get_entities_at(coord);
+            let entity_handles = universe.get_entities().get_entities_at(coord);
             // let cell = unsafe { universe.get_cell_unchecked(coord) };
             let cell = universe.get_cell(coord).unwrap();
 
             let new_color =
-                (self.color_calculator)(cell.as_ref(), entity_indexes, universe.get_entities());
+                (self.color_calculator)(cell.as_ref(), entity_handles, universe.get_entities());
             // let mut last_color = unsafe { colors.get_unchecked_mut(i) };
             let mut last_color = colors.get_mut(i).unwrap();
             if &new_color != last_color {
@@ -190,7 +214,11 @@ where
         match client_message.content {
             ThinClientMessageContent::SendSnapshot => {
                 // create the snapshot by cloning the colors from the server.
-                let snap: Vec<Color> = self.colors.read().unwrap().clone();
+                let snap = ColorSnapshot {
+                    width: self.width,
+                    height: self.height,
+                    colors: self.colors.read().unwrap().clone(),
+                };
                 box ok(Some(ThinServerMessage {
                     seq,
                     contents: ThinServerMessageContents::Snapshot(snap),
@@ -0,0 +1,89 @@
+//! Minimal state and action types shared by the crate's tests.  Tests that need states or actions
+//! with behavior of their own declare them locally and combine them with these.
+
+use std::fmt::Debug;
+
+use action::{CellAction, EntityAction, SelfAction};
+use cell::CellState;
+use container::EntityHandle;
+use engine::executor::ActionHandler;
+use entity::{Entity, EntityState, MutEntityState};
+use universe::Universe;
+
+/// A cell state holding a single number
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CS(pub usize);
+
+impl CellState for CS {}
+
+/// An entity state holding a single number, usable with any cell state
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ES(pub usize);
+
+impl<C: CellState> EntityState<C> for ES {}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MES(pub usize);
+
+impl MutEntityState for MES {}
+
+#[derive(Clone, Debug)]
+pub struct CA;
+
+impl<C: CellState> CellAction<C> for CA {}
+
+#[derive(Clone, Debug)]
+pub struct EA;
+
+impl<C: CellState, E: EntityState<C>> EntityAction<C, E> for EA {}
+
+/// Creates an entity with the supplied state that hasn't been inserted into a universe yet.
+pub fn new_entity<C: CellState>(state: usize) -> Entity<C, ES, MES> {
+    Entity::new(ES(state), MES(0))
+}
+
+/// An action handler that applies none of the actions delegated to it, only counting them and
+/// recording the built-in self actions that don't apply to the universe.
+#[derive(Debug, Default)]
+pub struct NoopHandler {
+    pub cell_actions: usize,
+    pub custom_self_actions: usize,
+    pub entity_actions: usize,
+    pub unsupported_self_actions: Vec<String>,
+}
+
+impl<
+        C: CellState,
+        E: EntityState<C>,
+        M: MutEntityState,
+        CA: CellAction<C>,
+        EA: EntityAction<C, E>,
+        U: Universe<C, E, M>,
+    > ActionHandler<C, E, M, CA, EA, U> for NoopHandler
+where
+    SelfAction<C, E, EA>: Debug,
+{
+    fn exec_cell_action(&mut self, _: &mut U, _: EntityHandle, _: &CA, _: U::Coord) {
+        self.cell_actions += 1;
+    }
+
+    fn exec_custom_self_action(&mut self, _: &mut U, _: EntityHandle, _: &EA) {
+        self.custom_self_actions += 1;
+    }
+
+    fn exec_entity_action(&mut self, _: &mut U, _: EntityHandle, _: &EA, _: EntityHandle) {
+        self.entity_actions += 1;
+    }
+
+    fn exec_unsupported_self_action(
+        &mut self,
+        _: &mut U,
+        _: EntityHandle,
+        action: &SelfAction<C, E, EA>,
+    ) {
+        self.unsupported_self_actions.push(format!("{:?}", action));
+    }
+}
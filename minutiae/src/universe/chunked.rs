@@ -0,0 +1,521 @@
+//! Declares a sparse two-dimensional universe that is effectively unbounded.  Cells are stored in
+//! fixed-size square chunks which are only allocated once they're needed; until then, the state
+//! of a cell is produced on demand by a `CellGenerator`.  Chunks that haven't been modified and
+//! that contain no entities can be evicted to free memory, after which their cells are once again
+//! produced by the generator.
+
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+};
+
+use super::{CellContainer, Into2DIndex, PixelGrid, Universe};
+use cell::{Cell, CellState};
+use container::{EntityContainer, EntityHandle};
+use entity::{Entity, EntityState, MutEntityState};
+use lifecycle::DeathCause;
+use rng::{mix64, StreamKey};
+use util::get_coords;
+
+/// A point in a `ChunkedUniverse`, which is the universe's coordinate type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct P2D {
+    pub x: i64,
+    pub y: i64,
+}
+
+impl P2D {
+    pub fn new(x: i64, y: i64) -> Self { P2D { x, y } }
+
+    pub fn offset(self, x_offset: i64, y_offset: i64) -> Option<Self> {
+        Some(P2D {
+            x: self.x.checked_add(x_offset)?,
+            y: self.y.checked_add(y_offset)?,
+        })
+    }
+}
+
+impl StreamKey for P2D {
+    fn stream_key(&self) -> u64 { mix64(self.x as u64 ^ mix64(self.y as u64)) }
+}
+
+/// Maps the quadrant of non-negative coordinates onto a row-major grid.
+impl Into2DIndex for P2D {
+    fn into_2d_index(self, universe_width: usize) -> Option<usize> {
+        if self.x < 0 || self.y < 0 || self.x as u64 >= universe_width as u64
+            || self.y as u64 > usize::max_value() as u64
+        {
+            return None;
+        }
+
+        (self.y as usize)
+            .checked_mul(universe_width)?
+            .checked_add(self.x as usize)
+    }
+
+    fn from_2d_index(universe_width: usize, universe_index: usize) -> Self {
+        let (x, y) = get_coords(universe_index, universe_width);
+        P2D::new(x as i64, y as i64)
+    }
+}
+
+/// Produces the initial state of the cells of a `ChunkedUniverse` one at a time.  It must be
+/// deterministic since the cells of evicted chunks are re-generated when they're next accessed.
+pub trait CellGenerator<C: CellState, E: EntityState<C>, M: MutEntityState> {
+    fn gen_cell(&self, coord: P2D) -> C;
+
+    /// Returns the entities that should be spawned at a coordinate.  This is only called once for
+    /// each coordinate, the first time that the chunk containing it is loaded.
+    fn gen_initial_entities(&self, _coord: P2D) -> Vec<Entity<C, E, M>> { Vec::new() }
+}
+
+#[derive(Clone)]
+pub struct Chunk<C: CellState> {
+    /// The cells of the chunk in row-major order
+    pub cells: Vec<Cell<C>>,
+    /// Set once any cell in the chunk has been changed, preventing it from being evicted
+    pub modified: bool,
+}
+
+#[derive(Clone)]
+pub struct ChunkedUniverse<
+    C: CellState,
+    E: EntityState<C>,
+    M: MutEntityState,
+    G: CellGenerator<C, E, M>,
+> {
+    pub chunk_size: usize,
+    pub chunks: HashMap<(i64, i64), Chunk<C>>,
+    /// Coordinates of all chunks that have ever been loaded and so have had their initial entities
+    /// spawned
+    spawned_chunks: HashSet<(i64, i64)>,
+    /// The handles of the entities residing in each chunk.  Entities inserted, moved or removed
+    /// through the container directly rather than through the universe aren't reflected here.
+    /// Handles of entities that have since been removed are skipped and pruned lazily.
+    chunk_entities: HashMap<(i64, i64), HashSet<EntityHandle>>,
+    pub entities: EntityContainer<C, E, M, P2D>,
+    pub gen: G,
+}
+
+impl<C: CellState, E: EntityState<C>, M: MutEntityState, G: CellGenerator<C, E, M>>
+    ChunkedUniverse<C, E, M, G>
+{
+    pub fn new(chunk_size: usize, gen: G) -> Self {
+        assert!(chunk_size > 0);
+
+        ChunkedUniverse {
+            chunk_size,
+            chunks: HashMap::new(),
+            spawned_chunks: HashSet::new(),
+            chunk_entities: HashMap::new(),
+            entities: EntityContainer::new(),
+            gen,
+        }
+    }
+
+    /// Sets the seed from which the UUIDs of the universe's entities and its random number streams
+    /// are derived.  This should be done before any chunks are loaded so that all entities spawned
+    /// by the generator receive UUIDs derived from it.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.entities.seed = seed;
+        self
+    }
+
+    /// Returns the coordinates of the chunk containing the supplied point.
+    pub fn chunk_of(&self, coord: P2D) -> (i64, i64) {
+        let chunk_size = self.chunk_size as i64;
+        (coord.x.div_euclid(chunk_size), coord.y.div_euclid(chunk_size))
+    }
+
+    /// Returns the coordinates of the chunk containing the supplied point along with the offset of
+    /// the point within that chunk's cells.
+    fn locate(&self, coord: P2D) -> ((i64, i64), usize) {
+        let chunk_size = self.chunk_size as i64;
+        let (x, y) = (coord.x.rem_euclid(chunk_size), coord.y.rem_euclid(chunk_size));
+        (self.chunk_of(coord), (y * chunk_size + x) as usize)
+    }
+
+    /// Returns the coordinates of the cell at the supplied offset within a chunk, clamped to the
+    /// range of `i64`, along with whether they were within it.  When the chunk size doesn't evenly
+    /// divide that range, the chunks at its edges extend past it and those cells can't be accessed.
+    fn clamped_cell_coord(&self, chunk: (i64, i64), x: usize, y: usize) -> (P2D, bool) {
+        let chunk_size = self.chunk_size as i128;
+        let clamp = |chunk: i64, offset: usize| {
+            let coord = chunk as i128 * chunk_size + offset as i128;
+            let clamped = coord.max(i64::min_value() as i128).min(i64::max_value() as i128);
+            (clamped as i64, clamped == coord)
+        };
+        let ((x, x_in_range), (y, y_in_range)) = (clamp(chunk.0, x), clamp(chunk.1, y));
+
+        (P2D::new(x, y), x_in_range && y_in_range)
+    }
+
+    pub fn is_loaded(&self, chunk: (i64, i64)) -> bool { self.chunks.contains_key(&chunk) }
+
+    /// Generates a chunk if it isn't already loaded, spawning its initial entities if it's being
+    /// loaded for the first time.
+    pub fn load_chunk(&mut self, chunk: (i64, i64)) -> &mut Chunk<C> {
+        if !self.chunks.contains_key(&chunk) {
+            let spawn_entities = self.spawned_chunks.insert(chunk);
+
+            let mut cells = Vec::with_capacity(self.chunk_size * self.chunk_size);
+            for y in 0..self.chunk_size {
+                for x in 0..self.chunk_size {
+                    let (coord, in_range) = self.clamped_cell_coord(chunk, x, y);
+                    cells.push(Cell {
+                        state: self.gen.gen_cell(coord),
+                    });
+
+                    if spawn_entities && in_range {
+                        for entity in self.gen.gen_initial_entities(coord) {
+                            let entity_handle = self.entities.insert(entity, coord);
+                            self.chunk_entities
+                                .entry(chunk)
+                                .or_insert_with(HashSet::new)
+                                .insert(entity_handle);
+                        }
+                    }
+                }
+            }
+
+            self.chunks.insert(chunk, Chunk {
+                cells,
+                modified: false,
+            });
+        }
+
+        self.chunks.get_mut(&chunk).unwrap()
+    }
+
+    /// Loads all chunks that contain at least one cell within `radius` of `center`.
+    pub fn load_area(&mut self, center: P2D, radius: usize) {
+        let radius = radius.min(i64::max_value() as usize) as i64;
+        let (min_x, min_y) = self.chunk_of(P2D::new(
+            center.x.saturating_sub(radius),
+            center.y.saturating_sub(radius),
+        ));
+        let (max_x, max_y) = self.chunk_of(P2D::new(
+            center.x.saturating_add(radius),
+            center.y.saturating_add(radius),
+        ));
+        for chunk_y in min_y..=max_y {
+            for chunk_x in min_x..=max_x {
+                self.load_chunk((chunk_x, chunk_y));
+            }
+        }
+    }
+
+    /// Returns the `(entity_handle, coord)` of all entities that reside in a chunk.
+    pub fn get_chunk_entities(&self, chunk: (i64, i64)) -> Vec<(EntityHandle, P2D)> {
+        let entity_handles = match self.chunk_entities.get(&chunk) {
+            Some(entity_handles) => entity_handles,
+            None => return Vec::new(),
+        };
+
+        entity_handles
+            .iter()
+            .filter_map(|&entity_handle| {
+                self.entities
+                    .get_verify(entity_handle)
+                    .map(|(_, coord)| (entity_handle, coord))
+            })
+            .collect()
+    }
+
+    /// Drops all loaded chunks that haven't been modified and that don't contain any entities,
+    /// returning the number of chunks that were evicted.  Their cells will be re-generated the next
+    /// time that they're accessed, but their initial entities will not be spawned again.
+    pub fn evict_unmodified(&mut self) -> usize {
+        let entities = &self.entities;
+        self.chunk_entities.retain(|_, entity_handles| {
+            entity_handles.retain(|&entity_handle| entities.contains(entity_handle));
+            !entity_handles.is_empty()
+        });
+
+        let chunk_entities = &self.chunk_entities;
+        let chunk_count = self.chunks.len();
+        self.chunks
+            .retain(|chunk, contents| contents.modified || chunk_entities.contains_key(chunk));
+        chunk_count - self.chunks.len()
+    }
+
+    /// Inserts an entity at the supplied coordinate, loading the chunk that contains it.  Entities
+    /// spawned by another entity should supply it as their `parent`.
+    pub fn insert_entity(
+        &mut self,
+        entity: Entity<C, E, M>,
+        coord: P2D,
+        parent: Option<EntityHandle>,
+    ) -> EntityHandle {
+        let chunk = self.chunk_of(coord);
+        self.load_chunk(chunk);
+        let entity_handle = self.entities.insert_child(entity, coord, parent);
+        self.chunk_entities
+            .entry(chunk)
+            .or_insert_with(HashSet::new)
+            .insert(entity_handle);
+        entity_handle
+    }
+
+    /// Moves an entity to the supplied coordinate, loading the chunk that it moves into.  Returns
+    /// `false` without doing anything if the entity no longer exists.
+    pub fn move_entity(&mut self, entity_handle: EntityHandle, dst: P2D) -> bool {
+        let src = match self.entities.get_verify(entity_handle) {
+            Some((_, src)) => src,
+            None => return false,
+        };
+
+        let (src_chunk, dst_chunk) = (self.chunk_of(src), self.chunk_of(dst));
+        self.load_chunk(dst_chunk);
+        self.entities.move_entity(entity_handle, dst);
+        if src_chunk != dst_chunk {
+            self.unindex_entity(entity_handle, src_chunk);
+            self.chunk_entities
+                .entry(dst_chunk)
+                .or_insert_with(HashSet::new)
+                .insert(entity_handle);
+        }
+        true
+    }
+
+    /// Removes an entity from the universe, recording why it was removed.  Returns `None` if the
+    /// entity has already been removed.
+    pub fn remove_entity(
+        &mut self,
+        entity_handle: EntityHandle,
+        cause: DeathCause,
+    ) -> Option<Entity<C, E, M>> {
+        let chunk = match self.entities.get_verify(entity_handle) {
+            Some((_, coord)) => self.chunk_of(coord),
+            None => return None,
+        };
+
+        self.unindex_entity(entity_handle, chunk);
+        self.entities.remove_with_cause(entity_handle, cause)
+    }
+
+    /// Drops an entity from the set of entities residing in a chunk.
+    fn unindex_entity(&mut self, entity_handle: EntityHandle, chunk: (i64, i64)) {
+        let now_empty = match self.chunk_entities.get_mut(&chunk) {
+            Some(entity_handles) => {
+                entity_handles.remove(&entity_handle);
+                entity_handles.is_empty()
+            },
+            None => false,
+        };
+        if now_empty {
+            self.chunk_entities.remove(&chunk);
+        }
+    }
+
+    /// Moves an entity by the supplied offset, loading the chunk that it moves into.  Moves that
+    /// would overflow the coordinate range are dropped, as are moves of entities that no longer
+    /// exist.
+    pub fn translate_entity(
+        &mut self,
+        x_offset: i64,
+        y_offset: i64,
+        entity_handle: EntityHandle,
+    ) {
+        let dst = match self.entities.get_verify(entity_handle) {
+            Some((_, src)) => match src.offset(x_offset, y_offset) {
+                Some(dst) => dst,
+                None => return,
+            },
+            None => return,
+        };
+
+        self.move_entity(entity_handle, dst);
+    }
+
+    /// Visits all coordinates within `view_distance` of `center`, including diagonals.
+    pub fn iter_visible(center: P2D, view_distance: usize) -> impl Iterator<Item = P2D> {
+        let view_distance = view_distance as i64;
+        (-view_distance..=view_distance).flat_map(move |y_offset| {
+            (-view_distance..=view_distance)
+                .filter_map(move |x_offset| center.offset(x_offset, y_offset))
+        })
+    }
+}
+
+impl<
+        C: CellState,
+        E: EntityState<C>,
+        M: MutEntityState,
+        G: CellGenerator<C, E, M> + Default,
+    > Default for ChunkedUniverse<C, E, M, G>
+{
+    fn default() -> Self { ChunkedUniverse::new(64, G::default()) }
+}
+
+impl<
+        C: CellState,
+        E: EntityState<C>,
+        M: MutEntityState,
+        G: CellGenerator<C, E, M> + Default,
+    > Universe<C, E, M> for ChunkedUniverse<C, E, M, G>
+{
+    type Coord = P2D;
+
+    /// Returns the cell from its chunk if the chunk is loaded and generates it otherwise.
+    fn get_cell(&self, coord: P2D) -> Option<Cow<Cell<C>>> {
+        let (chunk, offset) = self.locate(coord);
+        Some(match self.chunks.get(&chunk) {
+            Some(chunk) => Cow::Borrowed(&chunk.cells[offset]),
+            None => Cow::Owned(Cell {
+                state: self.gen.gen_cell(coord),
+            }),
+        })
+    }
+
+    unsafe fn get_cell_unchecked(&self, coord: P2D) -> Cow<Cell<C>> {
+        self.get_cell(coord).unwrap()
+    }
+
+    fn set_cell(&mut self, coord: P2D, new_state: C) {
+        let (chunk, offset) = self.locate(coord);
+        let chunk = self.load_chunk(chunk);
+        chunk.cells[offset].state = new_state;
+        chunk.modified = true;
+    }
+
+    fn set_cell_unchecked(&mut self, coord: P2D, new_state: C) { self.set_cell(coord, new_state) }
+
+    fn get_entities<'a>(&'a self) -> &'a EntityContainer<C, E, M, P2D> { &self.entities }
+
+    fn get_entities_mut<'a>(&'a mut self) -> &'a mut EntityContainer<C, E, M, P2D> {
+        &mut self.entities
+    }
+
+    fn entities_within(&self, coord: P2D, view_distance: usize) -> Vec<EntityHandle> {
+        Self::iter_visible(coord, view_distance)
+            .flat_map(|coord| self.entities.get_entities_at(coord))
+            .cloned()
+            .collect()
+    }
+
+    fn empty() -> Self { Self::default() }
+}
+
+impl<C: CellState, E: EntityState<C>, M: MutEntityState, G: CellGenerator<C, E, M>>
+    CellContainer<C, P2D> for ChunkedUniverse<C, E, M, G>
+{
+    fn get_cell_direct(&self, coord: P2D) -> Cell<C> {
+        let (chunk, offset) = self.locate(coord);
+        match self.chunks.get(&chunk) {
+            Some(chunk) => chunk.cells[offset].clone(),
+            None => Cell {
+                state: self.gen.gen_cell(coord),
+            },
+        }
+    }
+}
+
+impl<
+        C: CellState,
+        E: EntityState<C>,
+        M: MutEntityState,
+        G: CellGenerator<C, E, M> + Default,
+    > PixelGrid<C, E, M> for ChunkedUniverse<C, E, M, G>
+{
+    fn pixel_coord(&self, x: i64, y: i64) -> Option<P2D> { Some(P2D::new(x, y)) }
+}
+
+#[test]
+fn chunked_universe_lazy_generation() {
+    use test_util::{new_entity, CS, ES, MES};
+
+    #[derive(Clone, Default)]
+    struct Gen;
+    impl CellGenerator<CS, ES, MES> for Gen {
+        fn gen_cell(&self, coord: P2D) -> CS { CS((coord.x + coord.y).abs() as usize) }
+
+        fn gen_initial_entities(&self, coord: P2D) -> Vec<Entity<CS, ES, MES>> {
+            if coord == P2D::new(0, 0) {
+                vec![new_entity(0)]
+            } else {
+                Vec::new()
+            }
+        }
+    }
+
+    let mut universe: ChunkedUniverse<CS, ES, MES, Gen> = ChunkedUniverse::new(16, Gen);
+    let far = P2D::new(1 << 40, -(1 << 40));
+    assert_eq!(universe.get_cell(far).unwrap().state, CS(0));
+    assert!(universe.chunks.is_empty());
+
+    universe.set_cell(far, CS(42));
+    universe.load_area(P2D::new(0, 0), 20);
+    assert_eq!(universe.entities.len(), 1);
+    assert_eq!(universe.get_chunk_entities((0, 0)).len(), 1);
+
+    // Only the modified chunk and the one holding the entity survive eviction
+    universe.evict_unmodified();
+    assert_eq!(universe.chunks.len(), 2);
+    assert_eq!(universe.get_cell(far).unwrap().state, CS(42));
+
+    // Entities aren't spawned again when a chunk is re-loaded after being evicted
+    let (_, entity_handle, _) = universe.entities.iter().next().unwrap();
+    universe.translate_entity(100, 0, entity_handle);
+    assert!(universe.get_chunk_entities((0, 0)).is_empty());
+    assert_eq!(universe.get_chunk_entities((6, 0)), vec![(entity_handle, P2D::new(100, 0))]);
+    assert_eq!(universe.evict_unmodified(), 1);
+    universe.load_chunk((0, 0));
+    assert_eq!(universe.entities.len(), 1);
+
+    // Entities removed through the container directly are skipped and no longer pin their chunk
+    universe.entities.remove(entity_handle);
+    assert!(universe.get_chunk_entities((6, 0)).is_empty());
+    assert_eq!(universe.evict_unmodified(), 2);
+
+    // Areas at the edge of the coordinate range are clamped rather than overflowing
+    universe.load_area(P2D::new(i64::max_value(), i64::min_value()), 1);
+    assert_eq!(universe.chunks.len(), 2);
+}
+
+#[test]
+fn p2d_into_2d_index() {
+    assert_eq!(P2D::new(3, 2).into_2d_index(10), Some(23));
+    assert_eq!(P2D::from_2d_index(10, 23), P2D::new(3, 2));
+    assert_eq!(P2D::new(-1, 2).into_2d_index(10), None);
+    assert_eq!(P2D::new(3, -2).into_2d_index(10), None);
+    assert_eq!(P2D::new(10, 2).into_2d_index(10), None);
+    assert_eq!(P2D::new(0, i64::max_value()).into_2d_index(10), None);
+}
+
+#[test]
+fn chunked_universe_coordinate_extremes() {
+    use test_util::{new_entity, CS, ES, MES};
+
+    #[derive(Clone, Default)]
+    struct Gen;
+    impl CellGenerator<CS, ES, MES> for Gen {
+        fn gen_cell(&self, coord: P2D) -> CS { CS((coord.x % 10).abs() as usize) }
+
+        fn gen_initial_entities(&self, coord: P2D) -> Vec<Entity<CS, ES, MES>> {
+            if coord == P2D::new(i64::min_value(), i64::min_value()) {
+                vec![new_entity(0)]
+            } else {
+                Vec::new()
+            }
+        }
+    }
+
+    // 10 doesn't evenly divide the range of `i64`, so the chunks at its edges extend past it
+    let mut universe: ChunkedUniverse<CS, ES, MES, Gen> = ChunkedUniverse::new(10, Gen);
+    let max = P2D::new(i64::max_value(), i64::max_value());
+    let min = P2D::new(i64::min_value(), i64::min_value());
+    universe.set_cell(max, CS(42));
+    assert_eq!(universe.get_cell(max).unwrap().state, CS(42));
+    assert_eq!(universe.get_cell(P2D::new(i64::max_value() - 1, 0)).unwrap().state, CS(6));
+
+    universe.set_cell(min, CS(42));
+    assert_eq!(universe.get_cell(min).unwrap().state, CS(42));
+    assert_eq!(universe.get_cell(P2D::new(i64::min_value() + 1, 0)).unwrap().state, CS(7));
+
+    // Cells past the edge don't spawn the initial entities of the cell that they're clamped to
+    assert_eq!(universe.entities.len(), 1);
+    let (_, entity_handle, _) = universe.entities.iter().next().unwrap();
+    assert_eq!(universe.get_chunk_entities(universe.chunk_of(min)), vec![(entity_handle, min)]);
+}
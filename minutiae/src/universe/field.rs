@@ -0,0 +1,133 @@
+//! Declares scalar field layers: named buffers holding one continuous `f32` value per cell, such
+//! as pheromone concentrations or temperatures, that are stored alongside a universe's cells but
+//! separately from their states.  Values are indexed by universe index, just like cells.
+//!
+//! Entity drivers read field values from the universe.  Entities write to them with cell actions:
+//! `ActionHandler::exec_cell_action` is handed the universe along with the targeted universe index
+//! and can update `universe.fields` directly.  Actions that add to a value can implement
+//! `CellAction::accumulate` so that deposits into the same cell are merged before being applied.
+
+/// A single named layer of per-cell values
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FieldLayer {
+    pub name: String,
+    pub values: Vec<f32>,
+}
+
+/// Any number of field layers, each of which has one value for every cell of the universe.  Layers
+/// can be looked up by name, but accessing them by the index returned when they're added avoids the
+/// name lookup.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FieldLayers {
+    layers: Vec<FieldLayer>,
+}
+
+impl FieldLayers {
+    pub fn new() -> Self { FieldLayers { layers: Vec::new() } }
+
+    /// Adds a layer of `len` values set to `initial` and returns its index.  If a layer with the
+    /// same name already exists, it's reset instead.
+    pub fn add_layer(&mut self, name: &str, len: usize, initial: f32) -> usize {
+        let values = vec![initial; len];
+        match self.layer_index(name) {
+            Some(layer) => {
+                self.layers[layer].values = values;
+                layer
+            },
+            None => {
+                self.layers.push(FieldLayer {
+                    name: name.into(),
+                    values,
+                });
+                self.layers.len() - 1
+            },
+        }
+    }
+
+    /// Returns the index of the layer with the supplied name.
+    pub fn layer_index(&self, name: &str) -> Option<usize> {
+        self.layers.iter().position(|layer| layer.name == name)
+    }
+
+    /// Returns the number of layers.
+    pub fn len(&self) -> usize { self.layers.len() }
+
+    pub fn is_empty(&self) -> bool { self.layers.is_empty() }
+
+    pub fn iter(&self) -> impl Iterator<Item = &FieldLayer> { self.layers.iter() }
+
+    /// Returns the values of the layer with the supplied index.
+    pub fn get(&self, layer: usize) -> Option<&[f32]> {
+        self.layers.get(layer).map(|layer| layer.values.as_slice())
+    }
+
+    pub fn get_mut(&mut self, layer: usize) -> Option<&mut [f32]> {
+        self.layers
+            .get_mut(layer)
+            .map(|layer| layer.values.as_mut_slice())
+    }
+
+    /// Returns the values of the layer with the supplied name.
+    pub fn get_by_name(&self, name: &str) -> Option<&[f32]> {
+        self.layer_index(name).and_then(move |layer| self.get(layer))
+    }
+
+    pub fn get_by_name_mut(&mut self, name: &str) -> Option<&mut [f32]> {
+        match self.layer_index(name) {
+            Some(layer) => self.get_mut(layer),
+            None => None,
+        }
+    }
+
+    /// Returns the value of a layer at a universe index or `None` if either doesn't exist.
+    pub fn value(&self, layer: usize, universe_index: usize) -> Option<f32> {
+        self.get(layer)
+            .and_then(|values| values.get(universe_index).cloned())
+    }
+
+    /// Sets the value of a layer at a universe index.  Does nothing if either doesn't exist.
+    pub fn set(&mut self, layer: usize, universe_index: usize, value: f32) {
+        if let Some(slot) = self
+            .get_mut(layer)
+            .and_then(|values| values.get_mut(universe_index))
+        {
+            *slot = value;
+        }
+    }
+
+    /// Adds `delta` to the value of a layer at a universe index.  Does nothing if either doesn't
+    /// exist.
+    pub fn add(&mut self, layer: usize, universe_index: usize, delta: f32) {
+        if let Some(slot) = self
+            .get_mut(layer)
+            .and_then(|values| values.get_mut(universe_index))
+        {
+            *slot += delta;
+        }
+    }
+}
+
+#[test]
+fn field_layers() {
+    let mut fields = FieldLayers::new();
+    let pheromone = fields.add_layer("pheromone", 4, 0.);
+    let heat = fields.add_layer("heat", 4, 20.);
+    assert_eq!((pheromone, heat), (0, 1));
+    assert_eq!(fields.layer_index("heat"), Some(heat));
+
+    fields.add(pheromone, 2, 1.5);
+    fields.add(pheromone, 2, 1.);
+    fields.set(heat, 0, -5.);
+    fields.add(heat, 4, 1.);
+    assert_eq!(fields.get(pheromone), Some(&[0., 0., 2.5, 0.][..]));
+    assert_eq!(fields.get_by_name("heat"), Some(&[-5., 20., 20., 20.][..]));
+    assert_eq!(fields.value(heat, 4), None);
+    assert_eq!(fields.value(2, 0), None);
+
+    // adding an existing layer resets it
+    assert_eq!(fields.add_layer("pheromone", 4, 0.), pheromone);
+    assert_eq!(fields.value(pheromone, 2), Some(0.));
+    assert_eq!(fields.len(), 2);
+}
@@ -0,0 +1,303 @@
+//! Declares a universe whose cells are the nodes of an arbitrary graph rather than points on a
+//! grid.  Nodes are identified by their index, which is the universe's coordinate type, and the
+//! graph's edges are stored as adjacency lists.  Entities reside on nodes and move by traversing
+//! edges to adjacent nodes.
+
+use std::{borrow::Cow, collections::VecDeque, fs::File, io::Read};
+
+#[cfg(feature = "serde")]
+use serde::Deserialize;
+
+use super::{ContiguousUniverse, Universe};
+use cell::{Cell, CellState};
+use container::{EntityContainer, EntityHandle};
+use entity::{EntityState, MutEntityState};
+use generator::Generator;
+
+/// The topology of a `GraphUniverse`.  Acts as the universe's configuration.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Graph {
+    /// For each node, the indexes of all nodes that can be reached by traversing one of its edges
+    pub adjacency: Vec<Vec<usize>>,
+}
+
+impl Graph {
+    /// Creates a graph with `node_count` nodes from a list of `(src, dst)` edges.  If the graph
+    /// isn't directed, every edge can be traversed in both directions.
+    pub fn from_edges(node_count: usize, edges: &[(usize, usize)], directed: bool) -> Self {
+        let mut adjacency = vec![Vec::new(); node_count];
+        for &(src, dst) in edges {
+            assert!(
+                src < node_count && dst < node_count,
+                "Edge references a node that doesn't exist!"
+            );
+            adjacency[src].push(dst);
+            if !directed && src != dst {
+                adjacency[dst].push(src);
+            }
+        }
+
+        Graph { adjacency }
+    }
+
+    /// Parses a graph from an edge list containing one edge per line in the form `src dst`, where
+    /// `src` and `dst` are node indexes.  Blank lines and lines starting with `#` are ignored.  The
+    /// graph has as many nodes as are needed to contain the highest referenced node index.
+    pub fn parse_edge_list(edge_list: &str, directed: bool) -> Result<Self, String> {
+        let mut edges = Vec::new();
+        for (line_number, line) in edge_list.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let nodes: Vec<&str> = line.split_whitespace().collect();
+            if nodes.len() != 2 {
+                return Err(format!(
+                    "Expected two node indexes on line {} but found {}",
+                    line_number + 1,
+                    nodes.len()
+                ));
+            }
+            let parse_node = |node: &str| {
+                node.parse::<usize>().map_err(|err| {
+                    format!("Invalid node index on line {}: {:?}", line_number + 1, err)
+                })
+            };
+            edges.push((parse_node(nodes[0])?, parse_node(nodes[1])?));
+        }
+
+        let node_count = edges
+            .iter()
+            .map(|&(src, dst)| src.max(dst) + 1)
+            .max()
+            .unwrap_or(0);
+        Ok(Graph::from_edges(node_count, &edges, directed))
+    }
+
+    /// Reads and parses an edge list file.  See `parse_edge_list` for the format.
+    pub fn from_edge_list_file(path: &str, directed: bool) -> Result<Self, String> {
+        let mut edge_list = String::new();
+        File::open(path)
+            .and_then(|mut file| file.read_to_string(&mut edge_list))
+            .map_err(|err| format!("Unable to read edge list file: {:?}", err))?;
+
+        Graph::parse_edge_list(&edge_list, directed)
+    }
+
+    pub fn node_count(&self) -> usize { self.adjacency.len() }
+
+    /// Returns the nodes adjacent to `node`, which is empty if the node doesn't exist.
+    pub fn neighbors(&self, node: usize) -> &[usize] {
+        self.adjacency
+            .get(node)
+            .map(|neighbors| neighbors.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Returns `true` if there's an edge leading from `src` to `dst`, which is never the case if
+    /// `src` doesn't exist.
+    pub fn is_adjacent(&self, src: usize, dst: usize) -> bool { self.neighbors(src).contains(&dst) }
+
+    /// Returns all nodes that can be reached from `node` by traversing at most `hops` edges,
+    /// including `node` itself, in breadth-first order.  Returns nothing if the node doesn't exist.
+    pub fn k_hop_neighborhood(&self, node: usize, hops: usize) -> Vec<usize> {
+        if node >= self.node_count() {
+            return Vec::new();
+        }
+
+        let mut visited = vec![false; self.node_count()];
+        let mut neighborhood = vec![node];
+        let mut queue = VecDeque::new();
+        visited[node] = true;
+        queue.push_back((node, 0));
+
+        while let Some((cur, depth)) = queue.pop_front() {
+            if depth == hops {
+                continue;
+            }
+
+            for &neighbor in self.neighbors(cur) {
+                if !visited[neighbor] {
+                    visited[neighbor] = true;
+                    neighborhood.push(neighbor);
+                    queue.push_back((neighbor, depth + 1));
+                }
+            }
+        }
+
+        neighborhood
+    }
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = "C: for<'d> Deserialize<'d>"))]
+pub struct GraphUniverse<C: CellState, E: EntityState<C>, M: MutEntityState> {
+    pub graph: Graph,
+    pub cells: Vec<Cell<C>>,
+    pub entities: EntityContainer<C, E, M>,
+}
+
+impl<C: CellState, E: EntityState<C>, M: MutEntityState> GraphUniverse<C, E, M> {
+    pub fn new(graph: Graph, gen: &mut Generator<C, E, M, Graph>) -> GraphUniverse<C, E, M> {
+        GraphUniverse::new_with_seed(graph, 0, gen)
+    }
+
+    /// Creates a universe whose entities' UUIDs and random number streams are derived from the
+    /// supplied seed.
+    pub fn new_with_seed(
+        graph: Graph,
+        seed: u64,
+        gen: &mut Generator<C, E, M, Graph>,
+    ) -> GraphUniverse<C, E, M> {
+        let node_count = graph.node_count();
+
+        let mut universe = GraphUniverse {
+            graph,
+            cells: Vec::new(),
+            entities: EntityContainer::with_seed(seed),
+        };
+
+        let (cells, entities) = gen.gen(&universe.graph);
+        assert_eq!(
+            cells.len(),
+            node_count,
+            "Generator produced the wrong number of cells for the graph's nodes!"
+        );
+
+        universe.cells = cells;
+        for (node, entity_vec) in entities.into_iter().enumerate() {
+            for entity in entity_vec {
+                universe.entities.insert(entity, node);
+            }
+        }
+
+        universe
+    }
+
+    /// Creates a new shell universe without any defined logic designed for use in a hybrid client.
+    pub fn uninitialized() -> Self {
+        GraphUniverse {
+            graph: Graph::default(),
+            cells: Vec::new(),
+            entities: EntityContainer::new(),
+        }
+    }
+
+    pub fn get_graph<'a>(&'a self) -> &'a Graph { &self.graph }
+
+    /// Returns all nodes within `hops` edges of the supplied node, including itself.
+    pub fn iter_visible(&self, node: usize, hops: usize) -> impl Iterator<Item = usize> {
+        self.graph.k_hop_neighborhood(node, hops).into_iter()
+    }
+
+    /// Moves an entity along an edge from the node it currently resides on to `dst_node`.  Returns
+    /// `false` without moving the entity if it no longer exists or if there is no edge leading to
+    /// the destination node.
+    pub fn traverse_edge(
+        &mut self,
+        dst_node: usize,
+        entity_handle: EntityHandle,
+    ) -> bool {
+        let src_node = match self.entities.get_verify(entity_handle) {
+            Some((_, node)) => node,
+            None => return false,
+        };
+
+        if !self.graph.is_adjacent(src_node, dst_node) {
+            return false;
+        }
+
+        self.entities.move_entity(entity_handle, dst_node);
+        true
+    }
+}
+
+impl<C: CellState, E: EntityState<C>, M: MutEntityState> Default for GraphUniverse<C, E, M> {
+    fn default() -> Self { GraphUniverse::uninitialized() }
+}
+
+impl<C: CellState, E: EntityState<C>, M: MutEntityState> Universe<C, E, M>
+    for GraphUniverse<C, E, M>
+{
+    type Coord = usize;
+
+    fn get_cell(&self, coord: usize) -> Option<Cow<Cell<C>>> {
+        self.cells.get(coord).map(|c| Cow::Borrowed(c))
+    }
+
+    unsafe fn get_cell_unchecked(&self, coord: usize) -> Cow<Cell<C>> {
+        Cow::Borrowed(self.cells.get_unchecked(coord))
+    }
+
+    fn set_cell(&mut self, coord: usize, new_state: C) {
+        if let Some(cell) = self.cells.get_mut(coord) {
+            cell.state = new_state;
+        }
+    }
+
+    fn set_cell_unchecked(&mut self, coord: usize, new_state: C) {
+        self.cells[coord].state = new_state;
+    }
+
+    fn get_entities<'a>(&'a self) -> &'a EntityContainer<C, E, M> { &self.entities }
+
+    fn get_entities_mut<'a>(&'a mut self) -> &'a mut EntityContainer<C, E, M> { &mut self.entities }
+
+    fn entities_within(&self, coord: usize, view_distance: usize) -> Vec<EntityHandle> {
+        self.iter_visible(coord, view_distance)
+            .flat_map(|universe_index| self.entities.get_entities_at(universe_index))
+            .cloned()
+            .collect()
+    }
+
+    fn empty() -> Self { Self::uninitialized() }
+}
+
+impl<C: CellState, E: EntityState<C>, M: MutEntityState> ContiguousUniverse<C, E, M>
+    for GraphUniverse<C, E, M>
+{
+    fn get_cells<'a>(&'a self) -> &'a [Cell<C>] { &self.cells }
+
+    fn get_cells_mut<'a>(&'a mut self) -> &'a mut [Cell<C>] { &mut self.cells }
+}
+
+#[test]
+fn graph_universe_traversal() {
+    use entity::Entity;
+    use test_util::{new_entity, CS, ES, MES};
+
+    struct Gen;
+    impl Generator<CS, ES, MES, Graph> for Gen {
+        fn gen(&mut self, graph: &Graph) -> (Vec<Cell<CS>>, Vec<Vec<Entity<CS, ES, MES>>>) {
+            let mut entities = vec![Vec::new(); graph.node_count()];
+            entities[0].push(new_entity(0));
+            (vec![Cell { state: CS(0) }; graph.node_count()], entities)
+        }
+    }
+
+    // A path 0 - 1 - 2 - 3 with an extra edge 1 - 4
+    let edge_list = "# comment\n0 1\n1 2\n\n2 3\n1 4\n";
+    let graph = Graph::parse_edge_list(edge_list, false).unwrap();
+    assert_eq!(graph.node_count(), 5);
+    assert!(Graph::parse_edge_list("0 1 2", false).is_err());
+
+    let mut universe = GraphUniverse::new(graph, &mut Gen);
+    assert_eq!(universe.iter_visible(0, 0).collect::<Vec<_>>(), vec![0]);
+    assert_eq!(universe.iter_visible(0, 2).collect::<Vec<_>>(), vec![
+        0, 1, 2, 4
+    ]);
+
+    // nodes that don't exist have no neighbors
+    assert!(universe.graph.neighbors(5).is_empty());
+    assert!(!universe.graph.is_adjacent(5, 0));
+    assert_eq!(universe.iter_visible(5, 1).count(), 0);
+
+    let (_, entity_handle, _) = universe.entities.iter().next().unwrap();
+    assert!(!universe.traverse_edge(2, entity_handle));
+    assert!(universe.traverse_edge(1, entity_handle));
+    assert!(universe.traverse_edge(4, entity_handle));
+    assert_eq!(universe.entities.get_entities_at(4), &[entity_handle]);
+}
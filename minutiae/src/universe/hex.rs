@@ -0,0 +1,476 @@
+//! Declares a universe made up of a grid of pointy-topped hexagons.  Hexes are addressed using
+//! axial coordinates (`Hex`) for all hex math such as distances and neighborhoods, but cells are
+//! stored in a rectangular, row-major buffer using "odd-r" offset coordinates: every odd row is
+//! shifted right by half a hex.  The universe's coordinate type is the index into that buffer, so
+//! the index of the hex at offset `(col, row)` is `row * width + col`.
+//!
+//! Movement that would take an entity off of the edge of the grid is dropped.
+
+use std::borrow::Cow;
+
+#[cfg(feature = "serde")]
+use serde::Deserialize;
+
+use super::{ContiguousUniverse, PixelGrid, Universe};
+use cell::{Cell, CellState};
+use container::{EntityContainer, EntityHandle};
+use entity::{EntityState, MutEntityState};
+use generator::Generator;
+use neighborhood::{iter_offsets, Neighborhood};
+
+/// One of the six directions in which a hex has a neighbor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum HexDirection {
+    East,
+    NorthEast,
+    NorthWest,
+    West,
+    SouthWest,
+    SouthEast,
+}
+
+impl HexDirection {
+    /// All directions in counter-clockwise order starting from east.
+    pub const ALL: [HexDirection; 6] = [
+        HexDirection::East,
+        HexDirection::NorthEast,
+        HexDirection::NorthWest,
+        HexDirection::West,
+        HexDirection::SouthWest,
+        HexDirection::SouthEast,
+    ];
+
+    /// Returns the `(q, r)` offset of the neighbor in this direction in axial coordinates.
+    pub fn axial_offset(self) -> (i64, i64) {
+        match self {
+            HexDirection::East => (1, 0),
+            HexDirection::NorthEast => (1, -1),
+            HexDirection::NorthWest => (0, -1),
+            HexDirection::West => (-1, 0),
+            HexDirection::SouthWest => (-1, 1),
+            HexDirection::SouthEast => (0, 1),
+        }
+    }
+
+    pub fn opposite(self) -> HexDirection {
+        match self {
+            HexDirection::East => HexDirection::West,
+            HexDirection::NorthEast => HexDirection::SouthWest,
+            HexDirection::NorthWest => HexDirection::SouthEast,
+            HexDirection::West => HexDirection::East,
+            HexDirection::SouthWest => HexDirection::NorthEast,
+            HexDirection::SouthEast => HexDirection::NorthWest,
+        }
+    }
+}
+
+/// A hex in axial coordinates.  The implicit third cube coordinate is `s = -q - r`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Hex {
+    pub q: i64,
+    pub r: i64,
+}
+
+impl Hex {
+    pub fn new(q: i64, r: i64) -> Self { Hex { q, r } }
+
+    pub fn s(self) -> i64 { -self.q - self.r }
+
+    /// Converts "odd-r" offset coordinates into axial coordinates.
+    pub fn from_offset(col: i64, row: i64) -> Self {
+        Hex {
+            q: col - (row - (row & 1)) / 2,
+            r: row,
+        }
+    }
+
+    /// Converts the hex into "odd-r" offset coordinates, returning `(col, row)`.
+    pub fn to_offset(self) -> (i64, i64) { (self.q + (self.r - (self.r & 1)) / 2, self.r) }
+
+    /// Returns the number of steps needed to get from this hex to the other.
+    pub fn distance(self, other: Hex) -> usize {
+        let (dq, dr, ds) = (self.q - other.q, self.r - other.r, self.s() - other.s());
+        ((dq.abs() + dr.abs() + ds.abs()) / 2) as usize
+    }
+
+    /// Returns the hex `distance` steps away in the supplied direction.
+    pub fn offset(self, direction: HexDirection, distance: i64) -> Self {
+        let (dq, dr) = direction.axial_offset();
+        Hex {
+            q: self.q + dq * distance,
+            r: self.r + dr * distance,
+        }
+    }
+
+    pub fn neighbor(self, direction: HexDirection) -> Self { self.offset(direction, 1) }
+
+    /// Returns all hexes that are exactly `radius` steps away, walking counter-clockwise around the
+    /// ring.  A radius of 0 returns only the hex itself.
+    pub fn ring(self, radius: usize) -> Vec<Hex> {
+        if radius == 0 {
+            return vec![self];
+        }
+
+        let mut ring = Vec::with_capacity(6 * radius);
+        let mut hex = self.offset(HexDirection::SouthWest, radius as i64);
+        for &direction in HexDirection::ALL.iter() {
+            for _ in 0..radius {
+                ring.push(hex);
+                hex = hex.neighbor(direction);
+            }
+        }
+
+        ring
+    }
+
+    /// Visits all hexes that are within `radius` steps of this one, including itself.
+    pub fn range(self, radius: usize) -> impl Iterator<Item = Hex> {
+        let radius = radius as i64;
+        (-radius..=radius).flat_map(move |dq| {
+            let (min_dr, max_dr) = ((-radius).max(-dq - radius), radius.min(-dq + radius));
+            (min_dr..=max_dr).map(move |dr| Hex::new(self.q + dq, self.r + dr))
+        })
+    }
+
+    /// Returns the pixel coordinates of the center of the hex, given the distance in pixels from
+    /// the center of a hex to its corners.  The center of `Hex(0, 0)` is at the origin.
+    pub fn to_pixel(self, hex_radius: f64) -> (f64, f64) {
+        let sqrt_3 = 3f64.sqrt();
+        let x = hex_radius * (sqrt_3 * self.q as f64 + sqrt_3 / 2. * self.r as f64);
+        let y = hex_radius * (1.5 * self.r as f64);
+        (x, y)
+    }
+
+    /// Returns the hex that contains the supplied pixel coordinates.  This is the inverse of
+    /// `to_pixel`.
+    pub fn from_pixel(x: f64, y: f64, hex_radius: f64) -> Self {
+        let q = (3f64.sqrt() / 3. * x - y / 3.) / hex_radius;
+        let r = (2. / 3. * y) / hex_radius;
+        Hex::round(q, r)
+    }
+
+    /// Rounds fractional axial coordinates to the hex containing them.
+    fn round(q: f64, r: f64) -> Self {
+        let s = -q - r;
+        let (mut rq, mut rr, rs) = (q.round(), r.round(), s.round());
+        let (q_diff, r_diff, s_diff) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+
+        // The coordinate that was changed the most by rounding is re-derived from the other two
+        if q_diff > r_diff && q_diff > s_diff {
+            rq = -rr - rs;
+        } else if r_diff > s_diff {
+            rr = -rq - rs;
+        }
+
+        Hex::new(rq as i64, rr as i64)
+    }
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HexUniverseConf {
+    /// The number of hexes in each row
+    pub width: u32,
+    /// The number of rows of hexes
+    pub height: u32,
+    /// The distance in pixels from the center of each hex to its corners when it's rendered
+    pub hex_radius: u32,
+    /// Seed from which the UUIDs of entities and random number streams are derived
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub seed: u64,
+}
+
+impl HexUniverseConf {
+    pub fn new(width: u32, height: u32) -> Self {
+        HexUniverseConf {
+            width,
+            height,
+            hex_radius: 4,
+            seed: 0,
+        }
+    }
+
+    /// Returns the total number of hexes in the universe.
+    pub fn len(&self) -> usize { self.width as usize * self.height as usize }
+
+    /// Returns the `(width, height)` in pixels of the image needed to fully render the universe.
+    pub fn pixel_dims(&self) -> (usize, usize) {
+        let hex_radius = self.hex_radius as f64;
+        let width = 3f64.sqrt() * hex_radius * (self.width as f64 + 0.5);
+        let height = hex_radius * (1.5 * self.height as f64 + 0.5);
+        (width.ceil() as usize, height.ceil() as usize)
+    }
+}
+
+impl Default for HexUniverseConf {
+    fn default() -> HexUniverseConf { HexUniverseConf::new(100, 100) }
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = "C: for<'d> Deserialize<'d>"))]
+pub struct HexUniverse<C: CellState, E: EntityState<C>, M: MutEntityState> {
+    pub conf: HexUniverseConf,
+    pub cells: Vec<Cell<C>>,
+    pub entities: EntityContainer<C, E, M>,
+}
+
+impl<C: CellState, E: EntityState<C>, M: MutEntityState> HexUniverse<C, E, M> {
+    pub fn new(
+        conf: HexUniverseConf,
+        gen: &mut Generator<C, E, M, HexUniverseConf>,
+    ) -> HexUniverse<C, E, M> {
+        assert!(conf.width > 0 && conf.height > 0);
+        let universe_len = conf.len();
+
+        let seed = conf.seed;
+        let mut universe = HexUniverse {
+            conf,
+            cells: Vec::new(),
+            entities: EntityContainer::with_seed(seed),
+        };
+
+        let (cells, entities) = gen.gen(&universe.conf);
+        assert_eq!(
+            cells.len(),
+            universe_len,
+            "Generator produced the wrong number of hexes for the universe's dimensions!"
+        );
+
+        universe.cells = cells;
+        for (universe_index, entity_vec) in entities.into_iter().enumerate() {
+            for entity in entity_vec {
+                universe.entities.insert(entity, universe_index);
+            }
+        }
+
+        universe
+    }
+
+    /// Creates a new shell universe without any defined logic designed for use in a hybrid client.
+    pub fn uninitialized() -> Self {
+        HexUniverse {
+            conf: HexUniverseConf::default(),
+            cells: Vec::new(),
+            entities: EntityContainer::new(),
+        }
+    }
+
+    pub fn get_conf<'a>(&'a self) -> &'a HexUniverseConf { &self.conf }
+
+    pub fn get_width(&self) -> usize { self.conf.width as usize }
+
+    pub fn get_height(&self) -> usize { self.conf.height as usize }
+
+    /// Returns the hex at the supplied universe index.
+    pub fn get_hex(&self, universe_index: usize) -> Hex {
+        let width = self.get_width();
+        Hex::from_offset((universe_index % width) as i64, (universe_index / width) as i64)
+    }
+
+    /// Returns the universe index of the supplied hex or `None` if it's outside of the universe.
+    pub fn get_hex_index(&self, hex: Hex) -> Option<usize> {
+        let (col, row) = hex.to_offset();
+        if col < 0 || row < 0 || col >= self.get_width() as i64 || row >= self.get_height() as i64 {
+            return None;
+        }
+
+        Some(row as usize * self.get_width() + col as usize)
+    }
+
+    /// Returns the number of steps needed to get between two universe indexes.
+    pub fn distance(&self, universe_index_1: usize, universe_index_2: usize) -> usize {
+        self.get_hex(universe_index_1)
+            .distance(self.get_hex(universe_index_2))
+    }
+
+    /// Visits the universe indexes of all hexes within `view_distance` steps of the supplied
+    /// universe index, including itself.
+    pub fn iter_visible<'a>(
+        &'a self,
+        universe_index: usize,
+        view_distance: usize,
+    ) -> impl Iterator<Item = usize> + 'a {
+        self.get_hex(universe_index)
+            .range(view_distance)
+            .filter_map(move |hex| self.get_hex_index(hex))
+    }
+
+    /// Visits the universe indexes of all hexes in the supplied neighborhood of a universe index,
+    /// whose offsets are interpreted as axial `(q, r)` offsets.
+    pub fn iter_neighborhood<'a, N: Neighborhood + ?Sized>(
+        &'a self,
+        universe_index: usize,
+        neighborhood: &'a N,
+    ) -> impl Iterator<Item = usize> + 'a {
+        let center = self.get_hex(universe_index);
+        iter_offsets(neighborhood).filter_map(move |(q_offset, r_offset)| {
+            self.get_hex_index(Hex::new(
+                center.q + q_offset as i64,
+                center.r + r_offset as i64,
+            ))
+        })
+    }
+
+    /// Returns the universe indexes of all hexes exactly `radius` steps away from the supplied
+    /// universe index.
+    pub fn get_ring(&self, universe_index: usize, radius: usize) -> Vec<usize> {
+        self.get_hex(universe_index)
+            .ring(radius)
+            .into_iter()
+            .filter_map(|hex| self.get_hex_index(hex))
+            .collect()
+    }
+
+    /// Moves an entity `distance` steps in the supplied direction.  Does nothing if the entity no
+    /// longer exists or if the move would take it outside of the universe.
+    pub fn translate_entity(
+        &mut self,
+        direction: HexDirection,
+        distance: usize,
+        entity_handle: EntityHandle,
+    ) {
+        let src_universe_index = match self.entities.get_verify(entity_handle) {
+            Some((_, universe_index)) => universe_index,
+            None => return,
+        };
+
+        let dst = self
+            .get_hex(src_universe_index)
+            .offset(direction, distance as i64);
+        if let Some(dst_universe_index) = self.get_hex_index(dst) {
+            self.entities.move_entity(entity_handle, dst_universe_index);
+        }
+    }
+}
+
+impl<C: CellState, E: EntityState<C>, M: MutEntityState> Default for HexUniverse<C, E, M> {
+    fn default() -> Self { HexUniverse::uninitialized() }
+}
+
+impl<C: CellState, E: EntityState<C>, M: MutEntityState> Universe<C, E, M>
+    for HexUniverse<C, E, M>
+{
+    type Coord = usize;
+
+    fn get_cell(&self, coord: usize) -> Option<Cow<Cell<C>>> {
+        self.cells.get(coord).map(|c| Cow::Borrowed(c))
+    }
+
+    unsafe fn get_cell_unchecked(&self, coord: usize) -> Cow<Cell<C>> {
+        Cow::Borrowed(self.cells.get_unchecked(coord))
+    }
+
+    fn set_cell(&mut self, coord: usize, new_state: C) {
+        if let Some(cell) = self.cells.get_mut(coord) {
+            cell.state = new_state;
+        }
+    }
+
+    fn set_cell_unchecked(&mut self, coord: usize, new_state: C) {
+        self.cells[coord].state = new_state;
+    }
+
+    fn get_entities<'a>(&'a self) -> &'a EntityContainer<C, E, M> { &self.entities }
+
+    fn get_entities_mut<'a>(&'a mut self) -> &'a mut EntityContainer<C, E, M> { &mut self.entities }
+
+    fn entities_within(&self, coord: usize, view_distance: usize) -> Vec<EntityHandle> {
+        self.iter_visible(coord, view_distance)
+            .flat_map(|universe_index| self.entities.get_entities_at(universe_index))
+            .cloned()
+            .collect()
+    }
+
+    fn empty() -> Self { Self::uninitialized() }
+}
+
+impl<C: CellState, E: EntityState<C>, M: MutEntityState> ContiguousUniverse<C, E, M>
+    for HexUniverse<C, E, M>
+{
+    fn get_cells<'a>(&'a self) -> &'a [Cell<C>] { &self.cells }
+
+    fn get_cells_mut<'a>(&'a mut self) -> &'a mut [Cell<C>] { &mut self.cells }
+}
+
+/// Renders each hex as a hexagon with the configured radius.  The top-left hex is positioned so
+/// that it touches the top and left edges of the image.
+impl<C: CellState, E: EntityState<C>, M: MutEntityState> PixelGrid<C, E, M>
+    for HexUniverse<C, E, M>
+{
+    fn pixel_coord(&self, x: i64, y: i64) -> Option<usize> {
+        let hex_radius = self.conf.hex_radius as f64;
+        let (origin_x, origin_y) = (3f64.sqrt() / 2. * hex_radius, hex_radius);
+        let hex = Hex::from_pixel(x as f64 - origin_x, y as f64 - origin_y, hex_radius);
+        self.get_hex_index(hex)
+    }
+
+    fn pixel_dims(&self) -> Option<(usize, usize)> { Some(self.conf.pixel_dims()) }
+}
+
+#[test]
+fn hex_coordinates() {
+    let center = Hex::from_offset(3, 3);
+    assert_eq!(center.to_offset(), (3, 3));
+    assert_eq!(Hex::from_offset(-3, -1).to_offset(), (-3, -1));
+
+    for &direction in HexDirection::ALL.iter() {
+        let neighbor = center.neighbor(direction);
+        assert_eq!(center.distance(neighbor), 1);
+        assert_eq!(neighbor.neighbor(direction.opposite()), center);
+    }
+
+    let ring = center.ring(2);
+    assert_eq!(ring.len(), 12);
+    assert!(ring.iter().all(|&hex| center.distance(hex) == 2));
+    assert_eq!(center.range(2).count(), 19);
+    assert!(center.range(2).all(|hex| center.distance(hex) <= 2));
+
+    let (x, y) = center.to_pixel(10.);
+    assert_eq!(Hex::from_pixel(x, y, 10.), center);
+    assert_eq!(Hex::from_pixel(x + 8., y - 4., 10.), center);
+}
+
+#[test]
+fn hex_universe_entity_movement() {
+    use entity::Entity;
+    use neighborhood::HexRange;
+    use test_util::{new_entity, CS, ES, MES};
+
+    struct Gen;
+    impl Generator<CS, ES, MES, HexUniverseConf> for Gen {
+        fn gen(
+            &mut self,
+            conf: &HexUniverseConf,
+        ) -> (Vec<Cell<CS>>, Vec<Vec<Entity<CS, ES, MES>>>) {
+            let mut entities = vec![Vec::new(); conf.len()];
+            entities[0].push(new_entity(0));
+            (vec![Cell { state: CS(0) }; conf.len()], entities)
+        }
+    }
+
+    let mut universe = HexUniverse::new(HexUniverseConf::new(4, 4), &mut Gen);
+    // The top-left hex only has neighbors to the east and south east
+    assert_eq!(universe.get_ring(0, 1).len(), 2);
+    assert_eq!(universe.iter_visible(5, 1).count(), 7);
+    let mut neighborhood: Vec<usize> = universe.iter_neighborhood(5, &HexRange(1)).collect();
+    neighborhood.push(5);
+    neighborhood.sort();
+    assert_eq!(neighborhood, {
+        let mut visible: Vec<usize> = universe.iter_visible(5, 1).collect();
+        visible.sort();
+        visible
+    });
+    assert_eq!(universe.iter_neighborhood(0, &HexRange(1)).count(), 2);
+    assert_eq!(universe.pixel_coord(0, 0), None);
+    assert_eq!(universe.pixel_coord(4, 4), Some(0));
+    assert_eq!(universe.pixel_dims(), Some((32, 26)));
+
+    let (_, entity_handle, _) = universe.entities.iter().next().unwrap();
+    universe.translate_entity(HexDirection::West, 1, entity_handle);
+    assert_eq!(universe.entities.get_verify(entity_handle).unwrap().1, 0);
+    universe.translate_entity(HexDirection::SouthEast, 2, entity_handle);
+    assert_eq!(universe.entities.get_verify(entity_handle).unwrap().1, 9);
+}
@@ -0,0 +1,413 @@
+//! Declares the universe in which all parts of the system reside.  ItThe universe is represented by
+//! a rectangular two-dimensional array of cells.  The universe has a set view distance which
+//! determines what range cells and entities have information about their neighbors; a view
+//! distance of 0 means they only have knowledge of their own state, a view distance of 1 means that
+//! they have knowledge of all neighbors touching them (including diagonals), etc.
+
+use std::{borrow::Cow, hash::Hash};
+
+#[cfg(feature = "serde")]
+use serde::Deserialize;
+
+use cell::{Cell, CellState};
+use container::{EntityContainer, EntityHandle};
+use entity::{EntityState, MutEntityState};
+use generator::Generator;
+use neighborhood::{iter_neighborhood, Neighborhood};
+use rng::StreamKey;
+use util::{get_coords, get_index, translate_entity, DistanceMetric};
+
+pub mod chunked;
+pub use self::chunked::{CellGenerator, ChunkedUniverse, P2D};
+pub mod field;
+pub use self::field::{FieldLayer, FieldLayers};
+pub mod graph;
+pub use self::graph::{Graph, GraphUniverse};
+pub mod hex;
+pub use self::hex::{Hex, HexDirection, HexUniverse, HexUniverseConf};
+pub mod universe1d;
+pub use self::universe1d::{Universe1D, Universe1DConf};
+pub mod universe3d;
+pub use self::universe3d::{Universe3D, Universe3DConf};
+
+pub trait Universe<C: CellState, E: EntityState<C>, M: MutEntityState>: Default {
+    /// The type used to address cells and to locate entities within the universe.  Universes that
+    /// store their cells in a flat buffer use `usize` indexes into it.
+    type Coord: Copy + Eq + Hash + StreamKey;
+
+    fn get_cell(&self, coord: Self::Coord) -> Option<Cow<Cell<C>>>;
+
+    unsafe fn get_cell_unchecked(&self, coord: Self::Coord) -> Cow<Cell<C>>;
+
+    fn set_cell(&mut self, coord: Self::Coord, new_state: C);
+
+    fn set_cell_unchecked(&mut self, coord: Self::Coord, new_state: C);
+
+    fn get_entities<'a>(&'a self) -> &'a EntityContainer<C, E, M, Self::Coord>;
+
+    fn get_entities_mut<'a>(&'a mut self) -> &'a mut EntityContainer<C, E, M, Self::Coord>;
+
+    /// Returns the handles of all entities within `view_distance` of the supplied coordinate,
+    /// including any at the coordinate itself.  This is used to deliver messages broadcast by
+    /// entities; universes that don't support it return no entities.
+    fn entities_within(&self, _coord: Self::Coord, _view_distance: usize) -> Vec<EntityHandle> {
+        Vec::new()
+    }
+
+    fn empty() -> Self;
+}
+
+/// Conversion between a coordinate type and the linear index of that coordinate in a row-major
+/// two-dimensional grid with the supplied width.  This is used to map universes with arbitrary
+/// coordinate types onto flat buffers such as pixel data.
+pub trait Into2DIndex: Copy {
+    /// Returns `None` if the coordinate can't be represented in the grid, such as when it's
+    /// negative or lies past its right edge.
+    fn into_2d_index(self, universe_width: usize) -> Option<usize>;
+
+    fn from_2d_index(universe_width: usize, universe_index: usize) -> Self;
+}
+
+impl Into2DIndex for usize {
+    fn into_2d_index(self, _: usize) -> Option<usize> { Some(self) }
+
+    fn from_2d_index(_: usize, universe_index: usize) -> Self { universe_index }
+}
+
+/// Provides direct, owned access to cells by coordinate regardless of how they are stored.
+pub trait CellContainer<C: CellState, I> {
+    fn get_cell_direct(&self, coord: I) -> Cell<C>;
+}
+
+impl<C: CellState> CellContainer<C, usize> for [Cell<C>] {
+    fn get_cell_direct(&self, coord: usize) -> Cell<C> { self[coord].clone() }
+}
+
+/// A universe whose cells are all stored in a single contiguous slice indexed by universe index.
+pub trait ContiguousUniverse<C: CellState, E: EntityState<C>, M: MutEntityState>:
+    Universe<C, E, M, Coord = usize>
+{
+    fn get_cells<'a>(&'a self) -> &'a [Cell<C>];
+
+    fn get_cells_mut<'a>(&'a mut self) -> &'a mut [Cell<C>];
+}
+
+/// A universe that can be displayed as a two-dimensional image, allowing it to be rendered.
+pub trait PixelGrid<C: CellState, E: EntityState<C>, M: MutEntityState>: Universe<C, E, M> {
+    /// Returns the coordinate of the cell that is displayed at the supplied pixel coordinates or
+    /// `None` if no cell is displayed there.
+    fn pixel_coord(&self, x: i64, y: i64) -> Option<Self::Coord>;
+
+    /// Returns the `(width, height)` in pixels of the image needed to fully render the universe or
+    /// `None` if it has no fixed size, allowing renderers to follow universes that change size.
+    fn pixel_dims(&self) -> Option<(usize, usize)> { None }
+
+    /// Returns the value of a scalar field layer at the supplied coordinate, allowing renderers to
+    /// visualize it.  Returns `None` if the universe has no such layer.
+    fn field_value(&self, _layer: usize, _coord: Self::Coord) -> Option<f32> { None }
+}
+
+/// Determines what happens at the edges of the universe, both for movement of entities and for
+/// the neighborhoods visible to them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum BoundaryPolicy {
+    /// The edges of the universe act as walls.  Neighborhoods are truncated at the edges and moves
+    /// that would take an entity out of bounds are dropped.
+    Clip,
+    /// The universe is a torus; coordinates that go past one edge continue on the opposite side,
+    /// both for movement and for neighborhoods.
+    Wrap,
+    /// Moves that would go past an edge are reflected back off of it, so an entity one cell from
+    /// the edge that moves by 3 ends up 2 cells from it.  Neighborhoods are truncated at the edges.
+    Reflect,
+    /// Entities that move out of bounds are removed from the universe.  Neighborhoods are truncated
+    /// at the edges.
+    Absorb,
+}
+
+impl BoundaryPolicy {
+    /// Maps a possibly out-of-bounds coordinate along one axis into the universe for the purpose of
+    /// looking at it (neighborhoods, cell lookups).  Returns `None` if the coordinate doesn't exist.
+    pub fn resolve_visible(self, coord: isize, size: usize) -> Option<usize> {
+        match self {
+            BoundaryPolicy::Wrap => Some(coord.rem_euclid(size as isize) as usize),
+            _ if coord >= 0 && coord < size as isize => Some(coord as usize),
+            _ => None,
+        }
+    }
+
+    /// Maps a possibly out-of-bounds coordinate along one axis into the universe for the purpose of
+    /// moving an entity to it.  Returns `None` if the move can't be made, in which case it's either
+    /// dropped or, for `Absorb`, the moving entity is removed.
+    pub fn resolve_move(self, coord: isize, size: usize) -> Option<usize> {
+        match self {
+            BoundaryPolicy::Reflect => {
+                let period = 2 * (size as isize - 1);
+                if period == 0 {
+                    return Some(0);
+                }
+
+                let folded = coord.rem_euclid(period);
+                Some(if folded < size as isize { folded } else { period - folded } as usize)
+            },
+            _ => self.resolve_visible(coord, size),
+        }
+    }
+}
+
+impl Default for BoundaryPolicy {
+    fn default() -> Self { BoundaryPolicy::Clip }
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Universe2DConf {
+    pub width: u32,
+    pub height: u32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub boundary: BoundaryPolicy,
+    /// Seed from which the UUIDs of entities and random number streams are derived
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub seed: u64,
+}
+
+impl Universe2DConf {
+    /// Creates a configuration for a square universe with the default boundary policy.
+    pub fn square(size: u32) -> Self {
+        Universe2DConf {
+            width: size,
+            height: size,
+            boundary: BoundaryPolicy::default(),
+            seed: 0,
+        }
+    }
+
+    /// Returns the total number of cells in the universe.
+    pub fn len(&self) -> usize { self.width as usize * self.height as usize }
+}
+
+impl Default for Universe2DConf {
+    fn default() -> Universe2DConf { Universe2DConf::square(800) }
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = "C: for<'d> Deserialize<'d>"))]
+pub struct Universe2D<C: CellState, E: EntityState<C>, M: MutEntityState> {
+    pub conf: Universe2DConf,
+    pub cells: Vec<Cell<C>>,
+    pub entities: EntityContainer<C, E, M>,
+    /// Continuous per-cell values stored alongside the cells, indexed by universe index
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fields: FieldLayers,
+}
+
+impl<C: CellState, E: EntityState<C>, M: MutEntityState> Universe2D<C, E, M> {
+    pub fn new(conf: Universe2DConf, gen: &mut Generator<C, E, M>) -> Universe2D<C, E, M> {
+        assert!(conf.width > 0 && conf.height > 0);
+
+        let seed = conf.seed;
+        let mut universe = Universe2D {
+            conf,
+            cells: Vec::new(),
+            entities: EntityContainer::with_seed(seed),
+            fields: FieldLayers::new(),
+        };
+
+        // use the generator to generate an initial layout of cells and entities with which to
+        // populate the world
+        let (cells, entities) = gen.gen(&universe.conf);
+
+        universe.cells = cells;
+        for (universe_index, entity_vec) in entities.into_iter().enumerate() {
+            for entity in entity_vec {
+                universe.entities.insert(entity, universe_index);
+            }
+        }
+
+        universe
+    }
+
+    /// Creates a new shell universe without any defined logic designed for use in a hybrid client.
+    pub fn uninitialized() -> Self {
+        Universe2D {
+            conf: Universe2DConf::default(),
+            cells: Vec::new(),
+            entities: EntityContainer::new(),
+            fields: FieldLayers::new(),
+        }
+    }
+
+    pub fn get_conf<'a>(&'a self) -> &'a Universe2DConf { &self.conf }
+
+    pub fn get_width(&self) -> usize { self.conf.width as usize }
+
+    pub fn get_height(&self) -> usize { self.conf.height as usize }
+
+    pub fn get_boundary(&self) -> BoundaryPolicy { self.conf.boundary }
+
+    /// Adds a field layer with a value of `initial` for every cell and returns its index.  If a
+    /// layer with the same name already exists, it's reset instead.
+    pub fn add_field(&mut self, name: &str, initial: f32) -> usize {
+        let len = self.conf.len();
+        self.fields.add_layer(name, len, initial)
+    }
+
+    /// Visits the indexes of all cells in the supplied neighborhood of a universe index, applying
+    /// the universe's boundary policy.
+    pub fn iter_neighborhood<'a, N: Neighborhood + ?Sized>(
+        &self,
+        universe_index: usize,
+        neighborhood: &'a N,
+    ) -> impl Iterator<Item = usize> + 'a {
+        let (width, height) = (self.get_width(), self.get_height());
+        let (cur_x, cur_y) = get_coords(universe_index, width);
+        iter_neighborhood(neighborhood, cur_x, cur_y, width, height, self.get_boundary())
+            .map(move |(x, y)| get_index(x, y, width))
+    }
+
+    /// Moves an entity by the supplied offset, applying the universe's boundary policy.  Does
+    /// nothing if the entity no longer exists.
+    pub fn translate_entity(
+        &mut self,
+        x_offset: isize,
+        y_offset: isize,
+        entity_handle: EntityHandle,
+    ) {
+        let (width, height, boundary) = (self.get_width(), self.get_height(), self.get_boundary());
+        translate_entity(
+            x_offset,
+            y_offset,
+            &mut self.entities,
+            entity_handle,
+            width,
+            height,
+            boundary,
+        )
+    }
+}
+
+impl<C: CellState, E: EntityState<C>, M: MutEntityState> Default for Universe2D<C, E, M> {
+    fn default() -> Self { Universe2D::uninitialized() }
+}
+
+impl<C: CellState, E: EntityState<C>, M: MutEntityState> Universe<C, E, M> for Universe2D<C, E, M> {
+    type Coord = usize;
+
+    fn get_cell(&self, coord: usize) -> Option<Cow<Cell<C>>> {
+        self.cells.get(coord).map(|c| Cow::Borrowed(c))
+    }
+
+    unsafe fn get_cell_unchecked(&self, coord: usize) -> Cow<Cell<C>> {
+        Cow::Borrowed(self.cells.get_unchecked(coord))
+    }
+
+    fn set_cell(&mut self, coord: usize, new_state: C) {
+        match self.cells.get_mut(coord) {
+            Some(&mut Cell { ref mut state }) => *state = new_state,
+            None => (),
+        }
+    }
+
+    fn set_cell_unchecked(&mut self, coord: usize, new_state: C) {
+        self.cells[coord].state = new_state;
+    }
+
+    fn get_entities<'a>(&'a self) -> &'a EntityContainer<C, E, M> { &self.entities }
+
+    fn get_entities_mut<'a>(&'a mut self) -> &'a mut EntityContainer<C, E, M> { &mut self.entities }
+
+    fn entities_within(&self, coord: usize, view_distance: usize) -> Vec<EntityHandle> {
+        self.entities
+            .iter_within(coord, view_distance, DistanceMetric::Chebyshev, &self.conf)
+            .map(|(_, handle, _)| handle)
+            .collect()
+    }
+
+    fn empty() -> Self { Self::uninitialized() }
+}
+
+impl<C: CellState, E: EntityState<C>, M: MutEntityState> ContiguousUniverse<C, E, M>
+    for Universe2D<C, E, M>
+{
+    fn get_cells<'a>(&'a self) -> &'a [Cell<C>] { &self.cells }
+
+    fn get_cells_mut<'a>(&'a mut self) -> &'a mut [Cell<C>] { &mut self.cells }
+}
+
+impl<C: CellState, E: EntityState<C>, M: MutEntityState> PixelGrid<C, E, M>
+    for Universe2D<C, E, M>
+{
+    fn pixel_coord(&self, x: i64, y: i64) -> Option<usize> {
+        if x < 0 || y < 0 || x >= self.get_width() as i64 || y >= self.get_height() as i64 {
+            return None;
+        }
+
+        Some(get_index(x as usize, y as usize, self.get_width()))
+    }
+
+    fn pixel_dims(&self) -> Option<(usize, usize)> { Some((self.get_width(), self.get_height())) }
+
+    fn field_value(&self, layer: usize, coord: usize) -> Option<f32> {
+        self.fields.value(layer, coord)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn universe2d_bincode_round_trip() {
+    use bincode;
+    use entity::Entity;
+    use test_util::{CS, ES, MES};
+    use uuid::Uuid;
+
+    struct Gen;
+    impl Generator<CS, ES, MES> for Gen {
+        fn gen(&mut self, conf: &Universe2DConf) -> (Vec<Cell<CS>>, Vec<Vec<Entity<CS, ES, MES>>>) {
+            let cells = (0..conf.len()).map(|i| Cell { state: CS(i) }).collect();
+            let mut entities = vec![Vec::new(); conf.len()];
+            for i in 0..4 {
+                let mut entity = Entity::new(ES(i), MES(i * 10));
+                entity.uuid = Uuid::new_v4();
+                entities[i % 2].push(entity);
+            }
+            (cells, entities)
+        }
+    }
+
+    let mut universe = Universe2D::new(Universe2DConf::square(4), &mut Gen);
+    // leave a hole in the slab and move an entity so that the layout isn't trivial
+    let handles: Vec<EntityHandle> = universe.entities.iter().map(|(_, h, _)| h).collect();
+    let removed = handles[1];
+    universe.entities.remove(removed);
+    universe.entities.move_entity(handles[2], 9);
+
+    let encoded = bincode::serialize(&universe).unwrap();
+    let mut decoded: Universe2D<CS, ES, MES> = bincode::deserialize(&encoded).unwrap();
+
+    assert_eq!(decoded.get_width(), 4);
+    for (decoded_cell, cell) in decoded.cells.iter().zip(universe.cells.iter()) {
+        assert_eq!(decoded_cell.state, cell.state);
+    }
+    assert_eq!(decoded.entities.len(), 3);
+    for (entity, handle, universe_index) in universe.entities.iter() {
+        let (decoded_entity, decoded_index) = decoded.entities.get_verify(handle).unwrap();
+        assert_eq!(decoded_entity.uuid, entity.uuid);
+        assert_eq!(decoded_entity.state, entity.state);
+        assert_eq!(decoded_entity.mut_state, entity.mut_state);
+        assert_eq!(decoded_index, universe_index);
+    }
+    for universe_index in 0..16 {
+        assert_eq!(
+            decoded.entities.get_entities_at(universe_index),
+            universe.entities.get_entities_at(universe_index)
+        );
+    }
+
+    // the removed entity's slot is reused without reviving its handle
+    assert!(decoded.entities.get(removed).is_none());
+    let inserted = decoded.entities.insert(Entity::new(ES(9), MES(0)), 0);
+    assert_eq!(inserted.index, removed.index);
+    assert!(decoded.entities.get(removed).is_none());
+}
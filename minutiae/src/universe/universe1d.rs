@@ -0,0 +1,217 @@
+//! Declares a one-dimensional universe made up of a single row of cells, suitable for things such
+//! as elementary cellular automata and traffic models.  The universe index of a cell is simply its
+//! X coordinate.
+
+use std::borrow::Cow;
+
+#[cfg(feature = "serde")]
+use serde::Deserialize;
+
+use super::{BoundaryPolicy, ContiguousUniverse, PixelGrid, Universe};
+use cell::{Cell, CellState};
+use container::{EntityContainer, EntityHandle};
+use entity::{EntityState, MutEntityState};
+use generator::Generator;
+use util::{iter_visible_1d, translate_entity_1d};
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Universe1DConf {
+    pub width: u32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub boundary: BoundaryPolicy,
+    /// Seed from which the UUIDs of entities and random number streams are derived
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub seed: u64,
+}
+
+impl Universe1DConf {
+    /// Creates a configuration for a universe of the supplied width with the default boundary
+    /// policy.
+    pub fn new(width: u32) -> Self {
+        Universe1DConf {
+            width,
+            boundary: BoundaryPolicy::default(),
+            seed: 0,
+        }
+    }
+}
+
+impl Default for Universe1DConf {
+    fn default() -> Universe1DConf { Universe1DConf::new(800) }
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = "C: for<'d> Deserialize<'d>"))]
+pub struct Universe1D<C: CellState, E: EntityState<C>, M: MutEntityState> {
+    pub conf: Universe1DConf,
+    pub cells: Vec<Cell<C>>,
+    pub entities: EntityContainer<C, E, M>,
+}
+
+impl<C: CellState, E: EntityState<C>, M: MutEntityState> Universe1D<C, E, M> {
+    pub fn new(
+        conf: Universe1DConf,
+        gen: &mut Generator<C, E, M, Universe1DConf>,
+    ) -> Universe1D<C, E, M> {
+        assert!(conf.width > 0);
+        let universe_len = conf.width as usize;
+
+        let seed = conf.seed;
+        let mut universe = Universe1D {
+            conf,
+            cells: Vec::new(),
+            entities: EntityContainer::with_seed(seed),
+        };
+
+        let (cells, entities) = gen.gen(&universe.conf);
+        assert_eq!(
+            cells.len(),
+            universe_len,
+            "Generator produced the wrong number of cells for the universe's width!"
+        );
+
+        universe.cells = cells;
+        for (universe_index, entity_vec) in entities.into_iter().enumerate() {
+            for entity in entity_vec {
+                universe.entities.insert(entity, universe_index);
+            }
+        }
+
+        universe
+    }
+
+    /// Creates a new shell universe without any defined logic designed for use in a hybrid client.
+    pub fn uninitialized() -> Self {
+        Universe1D {
+            conf: Universe1DConf::default(),
+            cells: Vec::new(),
+            entities: EntityContainer::new(),
+        }
+    }
+
+    pub fn get_conf<'a>(&'a self) -> &'a Universe1DConf { &self.conf }
+
+    pub fn get_width(&self) -> usize { self.conf.width as usize }
+
+    pub fn get_boundary(&self) -> BoundaryPolicy { self.conf.boundary }
+
+    /// Visits the indexes of all cells within `view_distance` of the supplied index, including
+    /// itself.
+    pub fn iter_visible(
+        &self,
+        universe_index: usize,
+        view_distance: usize,
+    ) -> impl Iterator<Item = usize> {
+        iter_visible_1d(
+            universe_index,
+            view_distance,
+            self.get_width(),
+            self.get_boundary(),
+        )
+    }
+
+    /// Moves an entity by the supplied offset, applying the universe's boundary policy.  Does
+    /// nothing if the entity no longer exists.
+    pub fn translate_entity(&mut self, offset: isize, entity_handle: EntityHandle) {
+        let (width, boundary) = (self.get_width(), self.get_boundary());
+        translate_entity_1d(
+            offset,
+            &mut self.entities,
+            entity_handle,
+            width,
+            boundary,
+        )
+    }
+}
+
+impl<C: CellState, E: EntityState<C>, M: MutEntityState> Default for Universe1D<C, E, M> {
+    fn default() -> Self { Universe1D::uninitialized() }
+}
+
+impl<C: CellState, E: EntityState<C>, M: MutEntityState> Universe<C, E, M> for Universe1D<C, E, M> {
+    type Coord = usize;
+
+    fn get_cell(&self, coord: usize) -> Option<Cow<Cell<C>>> {
+        self.cells.get(coord).map(|c| Cow::Borrowed(c))
+    }
+
+    unsafe fn get_cell_unchecked(&self, coord: usize) -> Cow<Cell<C>> {
+        Cow::Borrowed(self.cells.get_unchecked(coord))
+    }
+
+    fn set_cell(&mut self, coord: usize, new_state: C) {
+        if let Some(cell) = self.cells.get_mut(coord) {
+            cell.state = new_state;
+        }
+    }
+
+    fn set_cell_unchecked(&mut self, coord: usize, new_state: C) {
+        self.cells[coord].state = new_state;
+    }
+
+    fn get_entities<'a>(&'a self) -> &'a EntityContainer<C, E, M> { &self.entities }
+
+    fn get_entities_mut<'a>(&'a mut self) -> &'a mut EntityContainer<C, E, M> { &mut self.entities }
+
+    fn entities_within(&self, coord: usize, view_distance: usize) -> Vec<EntityHandle> {
+        self.iter_visible(coord, view_distance)
+            .flat_map(|universe_index| self.entities.get_entities_at(universe_index))
+            .cloned()
+            .collect()
+    }
+
+    fn empty() -> Self { Self::uninitialized() }
+}
+
+impl<C: CellState, E: EntityState<C>, M: MutEntityState> ContiguousUniverse<C, E, M>
+    for Universe1D<C, E, M>
+{
+    fn get_cells<'a>(&'a self) -> &'a [Cell<C>] { &self.cells }
+
+    fn get_cells_mut<'a>(&'a mut self) -> &'a mut [Cell<C>] { &mut self.cells }
+}
+
+/// Renders the universe as a single row of pixels.  Use a `SpaceTimeRenderer` to render successive
+/// generations as a two-dimensional image.
+impl<C: CellState, E: EntityState<C>, M: MutEntityState> PixelGrid<C, E, M>
+    for Universe1D<C, E, M>
+{
+    fn pixel_coord(&self, x: i64, y: i64) -> Option<usize> {
+        if x < 0 || y != 0 || x >= self.get_width() as i64 {
+            return None;
+        }
+
+        Some(x as usize)
+    }
+
+    fn pixel_dims(&self) -> Option<(usize, usize)> { Some((self.get_width(), 1)) }
+}
+
+#[test]
+fn universe1d_entity_movement() {
+    use entity::Entity;
+    use test_util::{new_entity, CS, ES, MES};
+
+    struct Gen;
+    impl Generator<CS, ES, MES, Universe1DConf> for Gen {
+        fn gen(
+            &mut self,
+            conf: &Universe1DConf,
+        ) -> (Vec<Cell<CS>>, Vec<Vec<Entity<CS, ES, MES>>>) {
+            let mut entities = vec![Vec::new(); conf.width as usize];
+            entities[1].push(new_entity(0));
+            (vec![Cell { state: CS(0) }; conf.width as usize], entities)
+        }
+    }
+
+    let mut conf = Universe1DConf::new(10);
+    conf.boundary = BoundaryPolicy::Wrap;
+    let mut universe = Universe1D::new(conf, &mut Gen);
+    assert_eq!(universe.iter_visible(0, 1).collect::<Vec<_>>(), vec![9, 0, 1]);
+
+    let (_, entity_handle, _) = universe.entities.iter().next().unwrap();
+    universe.translate_entity(-3, entity_handle);
+    assert_eq!(universe.entities.get_verify(entity_handle).unwrap().1, 8);
+}
@@ -0,0 +1,268 @@
+//! Declares a three-dimensional universe made up of a rectangular prism of voxels.  Cells are
+//! stored as a stack of row-major layers (one for each Z coordinate), so the universe index of a
+//! voxel is `(z * height + y) * width + x`.  Entities reside in a single voxel just like they
+//! reside in a single cell of a `Universe2D`.
+
+use std::borrow::Cow;
+
+#[cfg(feature = "serde")]
+use serde::Deserialize;
+
+use super::{BoundaryPolicy, ContiguousUniverse, Universe};
+use cell::{Cell, CellState};
+use container::{EntityContainer, EntityHandle};
+use entity::{EntityState, MutEntityState};
+use generator::Generator;
+use util::{get_coords_3d, get_index_3d, iter_visible_3d, translate_entity_3d};
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Universe3DConf {
+    pub width: u32,
+    pub height: u32,
+    pub depth: u32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub boundary: BoundaryPolicy,
+    /// Seed from which the UUIDs of entities and random number streams are derived
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub seed: u64,
+}
+
+impl Universe3DConf {
+    /// Creates a configuration for a cube-shaped universe with the default boundary policy.
+    pub fn cube(size: u32) -> Self {
+        Universe3DConf {
+            width: size,
+            height: size,
+            depth: size,
+            boundary: BoundaryPolicy::default(),
+            seed: 0,
+        }
+    }
+
+    /// Returns the dimensions of the universe as `(width, height, depth)`.
+    pub fn dims(&self) -> (usize, usize, usize) {
+        (
+            self.width as usize,
+            self.height as usize,
+            self.depth as usize,
+        )
+    }
+
+    /// Returns the total number of voxels in the universe.
+    pub fn len(&self) -> usize { self.width as usize * self.height as usize * self.depth as usize }
+}
+
+impl Default for Universe3DConf {
+    fn default() -> Universe3DConf { Universe3DConf::cube(100) }
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = "C: for<'d> Deserialize<'d>"))]
+pub struct Universe3D<C: CellState, E: EntityState<C>, M: MutEntityState> {
+    pub conf: Universe3DConf,
+    pub cells: Vec<Cell<C>>,
+    pub entities: EntityContainer<C, E, M>,
+}
+
+impl<C: CellState, E: EntityState<C>, M: MutEntityState> Universe3D<C, E, M> {
+    pub fn new(
+        conf: Universe3DConf,
+        gen: &mut Generator<C, E, M, Universe3DConf>,
+    ) -> Universe3D<C, E, M> {
+        assert!(conf.width > 0 && conf.height > 0 && conf.depth > 0);
+        let universe_len = conf.len();
+
+        let seed = conf.seed;
+        let mut universe = Universe3D {
+            conf,
+            cells: Vec::new(),
+            entities: EntityContainer::with_seed(seed),
+        };
+
+        let (cells, entities) = gen.gen(&universe.conf);
+        assert_eq!(
+            cells.len(),
+            universe_len,
+            "Generator produced the wrong number of voxels for the universe's dimensions!"
+        );
+
+        universe.cells = cells;
+        for (universe_index, entity_vec) in entities.into_iter().enumerate() {
+            for entity in entity_vec {
+                universe.entities.insert(entity, universe_index);
+            }
+        }
+
+        universe
+    }
+
+    /// Creates a new shell universe without any defined logic designed for use in a hybrid client.
+    pub fn uninitialized() -> Self {
+        Universe3D {
+            conf: Universe3DConf::default(),
+            cells: Vec::new(),
+            entities: EntityContainer::new(),
+        }
+    }
+
+    pub fn get_conf<'a>(&'a self) -> &'a Universe3DConf { &self.conf }
+
+    pub fn get_width(&self) -> usize { self.conf.width as usize }
+
+    pub fn get_height(&self) -> usize { self.conf.height as usize }
+
+    pub fn get_depth(&self) -> usize { self.conf.depth as usize }
+
+    pub fn get_boundary(&self) -> BoundaryPolicy { self.conf.boundary }
+
+    /// Visits the indexes of all voxels within `view_distance` of the supplied index along each
+    /// axis, including itself.
+    pub fn iter_visible(
+        &self,
+        universe_index: usize,
+        view_distance: usize,
+    ) -> impl Iterator<Item = usize> {
+        let (width, height, depth) = self.conf.dims();
+        let coords = get_coords_3d(universe_index, width, height);
+        iter_visible_3d(coords, view_distance, (width, height, depth), self.get_boundary())
+            .map(move |(x, y, z)| get_index_3d(x, y, z, width, height))
+    }
+
+    /// Moves an entity by the supplied offset, applying the universe's boundary policy.  Does
+    /// nothing if the entity no longer exists.
+    pub fn translate_entity(
+        &mut self,
+        x_offset: isize,
+        y_offset: isize,
+        z_offset: isize,
+        entity_handle: EntityHandle,
+    ) {
+        let (dims, boundary) = (self.conf.dims(), self.get_boundary());
+        translate_entity_3d(
+            (x_offset, y_offset, z_offset),
+            &mut self.entities,
+            entity_handle,
+            dims,
+            boundary,
+        )
+    }
+}
+
+impl<C: CellState, E: EntityState<C>, M: MutEntityState> Default for Universe3D<C, E, M> {
+    fn default() -> Self { Universe3D::uninitialized() }
+}
+
+impl<C: CellState, E: EntityState<C>, M: MutEntityState> Universe<C, E, M> for Universe3D<C, E, M> {
+    type Coord = usize;
+
+    fn get_cell(&self, coord: usize) -> Option<Cow<Cell<C>>> {
+        self.cells.get(coord).map(|c| Cow::Borrowed(c))
+    }
+
+    unsafe fn get_cell_unchecked(&self, coord: usize) -> Cow<Cell<C>> {
+        Cow::Borrowed(self.cells.get_unchecked(coord))
+    }
+
+    fn set_cell(&mut self, coord: usize, new_state: C) {
+        if let Some(cell) = self.cells.get_mut(coord) {
+            cell.state = new_state;
+        }
+    }
+
+    fn set_cell_unchecked(&mut self, coord: usize, new_state: C) {
+        self.cells[coord].state = new_state;
+    }
+
+    fn get_entities<'a>(&'a self) -> &'a EntityContainer<C, E, M> { &self.entities }
+
+    fn get_entities_mut<'a>(&'a mut self) -> &'a mut EntityContainer<C, E, M> { &mut self.entities }
+
+    fn entities_within(&self, coord: usize, view_distance: usize) -> Vec<EntityHandle> {
+        self.iter_visible(coord, view_distance)
+            .flat_map(|universe_index| self.entities.get_entities_at(universe_index))
+            .cloned()
+            .collect()
+    }
+
+    fn empty() -> Self { Self::uninitialized() }
+}
+
+impl<C: CellState, E: EntityState<C>, M: MutEntityState> ContiguousUniverse<C, E, M>
+    for Universe3D<C, E, M>
+{
+    fn get_cells<'a>(&'a self) -> &'a [Cell<C>] { &self.cells }
+
+    fn get_cells_mut<'a>(&'a mut self) -> &'a mut [Cell<C>] { &mut self.cells }
+}
+
+#[test]
+fn universe3d_entity_movement() {
+    use entity::Entity;
+    use test_util::{new_entity, CS, ES, MES};
+    use util::get_index_3d;
+
+    struct Gen;
+    impl Generator<CS, ES, MES, Universe3DConf> for Gen {
+        fn gen(&mut self, conf: &Universe3DConf) -> (Vec<Cell<CS>>, Vec<Vec<Entity<CS, ES, MES>>>) {
+            let mut entities = vec![Vec::new(); conf.len()];
+            entities[0].push(new_entity(0));
+            (vec![Cell { state: CS(0) }; conf.len()], entities)
+        }
+    }
+
+    let mut conf = Universe3DConf::cube(4);
+    conf.boundary = BoundaryPolicy::Wrap;
+    let mut universe = Universe3D::new(conf, &mut Gen);
+    let (_, entity_handle, _) = universe.entities.iter().next().unwrap();
+
+    universe.translate_entity(1, 2, -1, entity_handle);
+    let (_, universe_index) = universe.entities.get_verify(entity_handle).unwrap();
+    assert_eq!(universe_index, get_index_3d(1, 2, 3, 4, 4));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn universe3d_bincode_round_trip() {
+    use bincode;
+    use entity::Entity;
+    use test_util::{new_entity, CS, ES, MES};
+
+    struct Gen;
+    impl Generator<CS, ES, MES, Universe3DConf> for Gen {
+        fn gen(&mut self, conf: &Universe3DConf) -> (Vec<Cell<CS>>, Vec<Vec<Entity<CS, ES, MES>>>) {
+            let cells = (0..conf.len()).map(|i| Cell { state: CS(i) }).collect();
+            let mut entities = vec![Vec::new(); conf.len()];
+            entities[0].push(new_entity(1));
+            entities[conf.len() - 1].push(new_entity(2));
+            (cells, entities)
+        }
+    }
+
+    let mut conf = Universe3DConf::cube(3);
+    conf.depth = 2;
+    conf.boundary = BoundaryPolicy::Wrap;
+    conf.seed = 7;
+    let mut universe = Universe3D::new(conf, &mut Gen);
+    let (_, moved, _) = universe.entities.iter().next().unwrap();
+    universe.translate_entity(1, 1, 1, moved);
+
+    let encoded = bincode::serialize(&universe).unwrap();
+    let decoded: Universe3D<CS, ES, MES> = bincode::deserialize(&encoded).unwrap();
+
+    assert_eq!(decoded.conf.dims(), (3, 3, 2));
+    assert_eq!(decoded.conf.boundary, BoundaryPolicy::Wrap);
+    assert_eq!(decoded.conf.seed, 7);
+    for (decoded_cell, cell) in decoded.cells.iter().zip(universe.cells.iter()) {
+        assert_eq!(decoded_cell.state, cell.state);
+    }
+    assert_eq!(decoded.entities.len(), 2);
+    for (entity, handle, universe_index) in universe.entities.iter() {
+        let (decoded_entity, decoded_index) = decoded.entities.get_verify(handle).unwrap();
+        assert_eq!(decoded_entity.uuid, entity.uuid);
+        assert_eq!(decoded_entity.state, entity.state);
+        assert_eq!(decoded_index, universe_index);
+        assert_eq!(decoded.entities.get_entities_at(universe_index), &[handle]);
+    }
+}
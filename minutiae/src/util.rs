@@ -5,27 +5,32 @@ use std::{collections::HashMap, fmt::Debug};
 use uuid::Uuid;
 
 use cell::{Cell, CellState};
-use container::EntityContainer;
+use container::{EntityContainer, EntityHandle};
 use entity::{Entity, EntityState, MutEntityState};
+use lifecycle::DeathCause;
+use universe::BoundaryPolicy;
 
-pub type ColorCalculator<C, E, M> = fn(&Cell<C>, &[usize], &EntityContainer<C, E, M>) -> [u8; 4];
+pub type ColorCalculator<C, E, M, I = usize> =
+    fn(&Cell<C>, &[EntityHandle], &EntityContainer<C, E, M, I>) -> [u8; 4];
+
+/// Maps the value of a scalar field layer at a cell to the color with which the cell is rendered.
+pub type FieldColorCalculator = fn(f32) -> [u8; 4];
 
 pub fn debug<T: Debug>(x: T) -> String { format!("{:?}", x) }
 
-/// Given an index of the universe and the universe's size returns X and Y coordinates.
-pub fn get_coords(index: usize, universe_size: usize) -> (usize, usize) {
-    debug_assert!(index < universe_size * universe_size);
-    let x = index % universe_size;
-    let y = (index - x) / universe_size;
+/// Given an index of the universe and the universe's width returns X and Y coordinates.  Cells are
+/// stored in row-major order, so the height of the universe isn't needed.
+pub fn get_coords(index: usize, universe_width: usize) -> (usize, usize) {
+    let x = index % universe_width;
+    let y = (index - x) / universe_width;
     (x, y)
 }
 
-/// Given an X and Y coordinate in the universe and the universe's size, returns the index of that
+/// Given an X and Y coordinate in the universe and the universe's width, returns the index of that
 /// coordinate in the universe.
-pub fn get_index(x: usize, y: usize, universe_size: usize) -> usize {
-    debug_assert!(x < universe_size);
-    debug_assert!(y < universe_size);
-    y * universe_size + x
+pub fn get_index(x: usize, y: usize, universe_width: usize) -> usize {
+    debug_assert!(x < universe_width);
+    y * universe_width + x
 }
 
 /// Calculates the manhattan distance between the two provided grid cells
@@ -40,6 +45,105 @@ pub fn calc_offset(x1: usize, y1: usize, x2: usize, y2: usize) -> (isize, isize)
     (x2 as isize - x1 as isize, y2 as isize - y1 as isize)
 }
 
+/// A way of measuring the distance between two grid cells, used by spatial queries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DistanceMetric {
+    /// The sum of the offsets along each axis
+    Manhattan,
+    /// The largest of the offsets along each axis; a radius forms a square
+    Chebyshev,
+    /// The straight-line distance; a radius forms a circle
+    Euclidean,
+}
+
+impl DistanceMetric {
+    /// Calculates the length of an `(x, y)` offset between two cells.
+    pub fn distance(self, (x_offset, y_offset): (isize, isize)) -> f64 {
+        let (x, y) = (x_offset.abs() as f64, y_offset.abs() as f64);
+        match self {
+            DistanceMetric::Manhattan => x + y,
+            DistanceMetric::Chebyshev => x.max(y),
+            DistanceMetric::Euclidean => (x * x + y * y).sqrt(),
+        }
+    }
+
+    /// Returns `true` if the length of an `(x, y)` offset is at most `radius`.  Unlike comparing
+    /// the result of `distance`, this is exact for all metrics.
+    pub fn is_within(self, (x_offset, y_offset): (isize, isize), radius: usize) -> bool {
+        let (x, y) = (x_offset.abs() as usize, y_offset.abs() as usize);
+        match self {
+            DistanceMetric::Manhattan => x + y <= radius,
+            DistanceMetric::Chebyshev => x.max(y) <= radius,
+            DistanceMetric::Euclidean => x * x + y * y <= radius * radius,
+        }
+    }
+}
+
+/// Calculates the offset between two coordinates, taking the universe's boundary policy into
+/// account.  In a wrapping universe, this is the shortest offset around the torus.
+pub fn calc_offset_bounded(
+    x1: usize,
+    y1: usize,
+    x2: usize,
+    y2: usize,
+    universe_width: usize,
+    universe_height: usize,
+    boundary: BoundaryPolicy,
+) -> (isize, isize) {
+    let (x_offset, y_offset) = calc_offset(x1, y1, x2, y2);
+    if boundary != BoundaryPolicy::Wrap {
+        return (x_offset, y_offset);
+    }
+
+    let shortest = |offset: isize, size: usize| -> isize {
+        let size = size as isize;
+        let offset = offset.rem_euclid(size);
+        if offset > size / 2 {
+            offset - size
+        } else {
+            offset
+        }
+    };
+    (
+        shortest(x_offset, universe_width),
+        shortest(y_offset, universe_height),
+    )
+}
+
+/// Calculates the manhattan distance between the two provided grid cells, taking the universe's
+/// boundary policy into account.
+pub fn manhattan_distance_bounded(
+    x1: usize,
+    y1: usize,
+    x2: usize,
+    y2: usize,
+    universe_width: usize,
+    universe_height: usize,
+    boundary: BoundaryPolicy,
+) -> usize {
+    let (x_offset, y_offset) =
+        calc_offset_bounded(x1, y1, x2, y2, universe_width, universe_height, boundary);
+    (x_offset.abs() + y_offset.abs()) as usize
+}
+
+/// Applies an offset to a set of coordinates, returning the resulting coordinates after applying
+/// the universe's boundary policy or `None` if they're out of bounds.  This should be used for
+/// looking at cells relative to a position; see `BoundaryPolicy::resolve_move` for movement.
+pub fn offset_coords(
+    x: usize,
+    y: usize,
+    x_offset: isize,
+    y_offset: isize,
+    universe_width: usize,
+    universe_height: usize,
+    boundary: BoundaryPolicy,
+) -> Option<(usize, usize)> {
+    let new_x = boundary.resolve_visible(x as isize + x_offset, universe_width)?;
+    let new_y = boundary.resolve_visible(y as isize + y_offset, universe_height)?;
+    Some((new_x, new_y))
+}
+
 /// Searches one coordinate of the universe and attempts to find the entity ID of the entity with
 /// the supplied UUID.
 pub fn locate_entity_simple<C: CellState, E: EntityState<C>, M: MutEntityState>(
@@ -63,9 +167,9 @@ pub fn locate_entity<C: CellState, E: EntityState<C>, M: MutEntityState>(
     uuid: Uuid,
     expected_index: usize,
     entity_meta: &HashMap<Uuid, (usize, usize)>,
-    universe_size: usize,
+    universe_width: usize,
 ) -> EntityLocation {
-    debug_assert!(expected_index < (universe_size * universe_size));
+    debug_assert!(expected_index < entities.len());
     // first attempt to find the entity at its expected coordinates
     match locate_entity_simple(uuid, &entities[expected_index]) {
         Some(entity_index) => EntityLocation::Expected(entity_index),
@@ -74,7 +178,7 @@ pub fn locate_entity<C: CellState, E: EntityState<C>, M: MutEntityState>(
             // meta `HashMap`
             match entity_meta.get(&uuid) {
                 Some(&(real_x, real_y)) => {
-                    let real_index = get_index(real_x, real_y, universe_size);
+                    let real_index = get_index(real_x, real_y, universe_width);
                     let entity_index = locate_entity_simple(uuid, &entities[real_index])
                         .expect("Entity not present at coordinates listed in meta `HashMap`!");
 
@@ -89,32 +193,51 @@ pub fn locate_entity<C: CellState, E: EntityState<C>, M: MutEntityState>(
 
 /// Given current X and Y coordinates of an entity and the view distance of the universe, creates an
 /// iterator visiting the indexes of all visible grid coordinates.  Note that this will include the
-/// index of the source entity.
+/// index of the source entity.  In a wrapping universe, the visible area continues over the edges
+/// but never includes the same coordinate twice.
 pub fn iter_visible(
     cur_x: usize,
     cur_y: usize,
     view_distance: usize,
-    universe_size: usize,
+    universe_width: usize,
+    universe_height: usize,
+    boundary: BoundaryPolicy,
 ) -> impl Iterator<Item = (usize, usize)> {
-    let y_range =
-        cur_y.saturating_sub(view_distance)..=(cur_y + view_distance).min(universe_size - 1);
-    y_range.flat_map(move |y| {
-        let x_range =
-            cur_x.saturating_sub(view_distance)..=(cur_x + view_distance).min(universe_size - 1);
-        x_range.map(move |x| (x, y))
+    visible_range(cur_y, view_distance, universe_height, boundary).flat_map(move |y| {
+        visible_range(cur_x, view_distance, universe_width, boundary).map(move |x| (x, y))
     })
 }
 
+/// Visits all coordinates along a single axis that are within `view_distance` of `cur`.  In a
+/// wrapping universe, the range is capped at `size` so that no coordinate is visited twice.
+fn visible_range(
+    cur: usize,
+    view_distance: usize,
+    size: usize,
+    boundary: BoundaryPolicy,
+) -> impl Iterator<Item = usize> {
+    let start = cur as isize - view_distance as isize;
+    let mut end = cur as isize + view_distance as isize;
+    if boundary == BoundaryPolicy::Wrap {
+        end = end.min(start + size as isize - 1);
+    }
+    (start..=end).filter_map(move |coord| boundary.resolve_visible(coord, size))
+}
+
+/// Moves an entity by the supplied offset, applying the supplied boundary policy to the
+/// destination.  Moves that end up out of bounds are dropped or, if the policy is `Absorb`, cause
+/// the entity to be removed.
 pub fn translate_entity<CS: CellState, ES: EntityState<CS>, MES: MutEntityState>(
     x_offset: isize,
     y_offset: isize,
     entities: &mut EntityContainer<CS, ES, MES>,
-    entity_index: usize,
-    entity_uuid: Uuid,
-    universe_size: usize,
+    entity_handle: EntityHandle,
+    universe_width: usize,
+    universe_height: usize,
+    boundary: BoundaryPolicy,
 ) {
     // this function will return early if the entity has been deleted
-    let universe_index = match entities.get_verify(entity_index, entity_uuid) {
+    let universe_index = match entities.get_verify(entity_handle) {
         Some((_, universe_index)) => universe_index,
         None => {
             return;
@@ -122,16 +245,147 @@ pub fn translate_entity<CS: CellState, ES: EntityState<CS>, MES: MutEntityState>
     };
 
     // if this is the entity that we're looking for, check to see if the requested move is in bounds
-    let (cur_x, cur_y) = get_coords(universe_index, universe_size);
-    let new_x = cur_x as isize + x_offset;
-    let new_y = cur_y as isize + y_offset;
-    let dst_universe_index = get_index(new_x as usize, new_y as usize, universe_size);
+    let (cur_x, cur_y) = get_coords(universe_index, universe_width);
+    let new_x = boundary.resolve_move(cur_x as isize + x_offset, universe_width);
+    let new_y = boundary.resolve_move(cur_y as isize + y_offset, universe_height);
 
-    // verify that the supplied desination coordinates are in bounds
     // TODO: verify that the supplied destination coordinates are within ruled bounds of destination
-    if new_x >= 0 && new_x < universe_size as isize && new_y >= 0 && new_y < universe_size as isize
-    {
-        entities.move_entity(entity_index, dst_universe_index);
+    match (new_x, new_y) {
+        (Some(new_x), Some(new_y)) => {
+            let dst_universe_index = get_index(new_x, new_y, universe_width);
+            entities.move_entity(entity_handle, dst_universe_index);
+        },
+        _ if boundary == BoundaryPolicy::Absorb => {
+            entities.remove_with_cause(entity_handle, DeathCause::Absorbed);
+        },
+        _ => (),
+    }
+}
+
+/// Given an index of a 3D universe and the universe's width and height, returns X, Y, and Z
+/// coordinates.  Cells are stored as a stack of row-major layers, so the depth isn't needed.
+pub fn get_coords_3d(
+    index: usize,
+    universe_width: usize,
+    universe_height: usize,
+) -> (usize, usize, usize) {
+    let layer_size = universe_width * universe_height;
+    let (x, y) = get_coords(index % layer_size, universe_width);
+    (x, y, index / layer_size)
+}
+
+/// Given X, Y, and Z coordinates in a 3D universe and the universe's width and height, returns the
+/// index of that coordinate in the universe.
+pub fn get_index_3d(
+    x: usize,
+    y: usize,
+    z: usize,
+    universe_width: usize,
+    universe_height: usize,
+) -> usize {
+    debug_assert!(y < universe_height);
+    (z * universe_height * universe_width) + get_index(x, y, universe_width)
+}
+
+/// Calculates the manhattan distance between the two provided voxels
+pub fn manhattan_distance_3d(
+    (x1, y1, z1): (usize, usize, usize),
+    (x2, y2, z2): (usize, usize, usize),
+) -> usize {
+    let z_diff = if z1 < z2 { z2 - z1 } else { z1 - z2 };
+    manhattan_distance(x1, y1, x2, y2) + z_diff
+}
+
+/// Given current X, Y, and Z coordinates of an entity and the view distance of the universe,
+/// creates an iterator visiting all visible voxel coordinates, including that of the entity itself.
+pub fn iter_visible_3d(
+    (cur_x, cur_y, cur_z): (usize, usize, usize),
+    view_distance: usize,
+    (universe_width, universe_height, universe_depth): (usize, usize, usize),
+    boundary: BoundaryPolicy,
+) -> impl Iterator<Item = (usize, usize, usize)> {
+    visible_range(cur_z, view_distance, universe_depth, boundary).flat_map(move |z| {
+        iter_visible(
+            cur_x,
+            cur_y,
+            view_distance,
+            universe_width,
+            universe_height,
+            boundary,
+        )
+        .map(move |(x, y)| (x, y, z))
+    })
+}
+
+/// Moves an entity in a 3D universe by the supplied offset, applying the boundary policy in the
+/// same way as `translate_entity`.
+pub fn translate_entity_3d<CS: CellState, ES: EntityState<CS>, MES: MutEntityState>(
+    (x_offset, y_offset, z_offset): (isize, isize, isize),
+    entities: &mut EntityContainer<CS, ES, MES>,
+    entity_handle: EntityHandle,
+    (universe_width, universe_height, universe_depth): (usize, usize, usize),
+    boundary: BoundaryPolicy,
+) {
+    let universe_index = match entities.get_verify(entity_handle) {
+        Some((_, universe_index)) => universe_index,
+        None => {
+            return;
+        }, // entity has been deleted, so do nothing.
+    };
+
+    let (cur_x, cur_y, cur_z) = get_coords_3d(universe_index, universe_width, universe_height);
+    let new_x = boundary.resolve_move(cur_x as isize + x_offset, universe_width);
+    let new_y = boundary.resolve_move(cur_y as isize + y_offset, universe_height);
+    let new_z = boundary.resolve_move(cur_z as isize + z_offset, universe_depth);
+
+    match (new_x, new_y, new_z) {
+        (Some(new_x), Some(new_y), Some(new_z)) => {
+            let dst_universe_index =
+                get_index_3d(new_x, new_y, new_z, universe_width, universe_height);
+            entities.move_entity(entity_handle, dst_universe_index);
+        },
+        _ if boundary == BoundaryPolicy::Absorb => {
+            entities.remove_with_cause(entity_handle, DeathCause::Absorbed);
+        },
+        _ => (),
+    }
+}
+
+/// Visits the indexes of all cells of a 1D universe that are within `view_distance` of `cur`,
+/// applying the boundary policy in the same way as `iter_visible`.
+pub fn iter_visible_1d(
+    cur: usize,
+    view_distance: usize,
+    universe_width: usize,
+    boundary: BoundaryPolicy,
+) -> impl Iterator<Item = usize> {
+    visible_range(cur, view_distance, universe_width, boundary)
+}
+
+/// Moves an entity in a 1D universe by the supplied offset, applying the boundary policy in the
+/// same way as `translate_entity`.
+pub fn translate_entity_1d<CS: CellState, ES: EntityState<CS>, MES: MutEntityState>(
+    offset: isize,
+    entities: &mut EntityContainer<CS, ES, MES>,
+    entity_handle: EntityHandle,
+    universe_width: usize,
+    boundary: BoundaryPolicy,
+) {
+    let universe_index = match entities.get_verify(entity_handle) {
+        Some((_, universe_index)) => universe_index,
+        None => {
+            return;
+        }, // entity has been deleted, so do nothing.
+    };
+
+    match boundary.resolve_move(universe_index as isize + offset, universe_width) {
+        Some(dst_universe_index) => {
+            entities.move_entity(entity_handle, dst_universe_index);
+        },
+        None if boundary == BoundaryPolicy::Absorb => {
+            entities.remove_with_cause(entity_handle, DeathCause::Absorbed);
+        },
+        None => (),
     }
 }
 
@@ -147,14 +401,16 @@ fn iter_visible_functionality() {
     let mut cur_y = 6;
 
     let indexes: Vec<(usize, usize)> =
-        iter_visible(cur_x, cur_y, view_distance, universe_size).collect();
+        iter_visible(cur_x, cur_y, view_distance, universe_size, universe_size, BoundaryPolicy::Clip)
+            .collect();
     assert_eq!(indexes.len(), 49);
 
     view_distance = 4;
     cur_x = 3;
     cur_y = 2;
     let indexes: Vec<(usize, usize)> =
-        iter_visible(cur_x, cur_y, view_distance, universe_size).collect();
+        iter_visible(cur_x, cur_y, view_distance, universe_size, universe_size, BoundaryPolicy::Clip)
+            .collect();
 
     assert_eq!(indexes.len(), 56);
 }
@@ -168,3 +424,71 @@ fn manhattan_distance_accuracy() {
 
     assert_eq!(manhattan_distance(x1, y1, x2, y2), 8);
 }
+
+#[test]
+fn iter_visible_wrapping() {
+    let universe_size = 10;
+
+    let indexes: Vec<(usize, usize)> =
+        iter_visible(0, 9, 1, universe_size, universe_size, BoundaryPolicy::Wrap).collect();
+    assert_eq!(indexes.len(), 9);
+    assert!(indexes.contains(&(9, 0)));
+    assert!(indexes.contains(&(1, 8)));
+
+    // the visible area never contains the same coordinate twice, even if it's larger than the
+    // universe itself.
+    let indexes: Vec<(usize, usize)> =
+        iter_visible(2, 2, 7, universe_size, universe_size, BoundaryPolicy::Wrap).collect();
+    assert_eq!(indexes.len(), universe_size * universe_size);
+}
+
+#[test]
+fn boundary_policy_moves() {
+    assert_eq!(BoundaryPolicy::Clip.resolve_move(-1, 10), None);
+    assert_eq!(BoundaryPolicy::Absorb.resolve_move(10, 10), None);
+    assert_eq!(BoundaryPolicy::Wrap.resolve_move(-1, 10), Some(9));
+    assert_eq!(BoundaryPolicy::Wrap.resolve_move(23, 10), Some(3));
+    assert_eq!(BoundaryPolicy::Reflect.resolve_move(-2, 10), Some(2));
+    assert_eq!(BoundaryPolicy::Reflect.resolve_move(11, 10), Some(7));
+    assert_eq!(BoundaryPolicy::Reflect.resolve_move(5, 1), Some(0));
+
+    assert_eq!(calc_offset_bounded(1, 1, 9, 8, 10, 10, BoundaryPolicy::Wrap), (-2, -3));
+    assert_eq!(manhattan_distance_bounded(1, 1, 9, 8, 10, 10, BoundaryPolicy::Wrap), 5);
+}
+
+#[test]
+fn rectangular_coordinates() {
+    let (width, height) = (7, 3);
+
+    for index in 0..(width * height) {
+        let (x, y) = get_coords(index, width);
+        assert!(x < width && y < height);
+        assert_eq!(get_index(x, y, width), index);
+    }
+
+    let indexes: Vec<(usize, usize)> =
+        iter_visible(6, 1, 2, width, height, BoundaryPolicy::Clip).collect();
+    assert_eq!(indexes.len(), 9);
+    let indexes: Vec<(usize, usize)> =
+        iter_visible(6, 1, 2, width, height, BoundaryPolicy::Wrap).collect();
+    assert_eq!(indexes.len(), 15);
+}
+
+#[test]
+fn coordinates_3d() {
+    let dims = (4, 3, 5);
+
+    for index in 0..(dims.0 * dims.1 * dims.2) {
+        let (x, y, z) = get_coords_3d(index, dims.0, dims.1);
+        assert!(x < dims.0 && y < dims.1 && z < dims.2);
+        assert_eq!(get_index_3d(x, y, z, dims.0, dims.1), index);
+    }
+
+    let visible: Vec<(usize, usize, usize)> =
+        iter_visible_3d((1, 1, 1), 1, dims, BoundaryPolicy::Clip).collect();
+    assert_eq!(visible.len(), 27);
+    let visible: Vec<(usize, usize, usize)> =
+        iter_visible_3d((0, 0, 4), 1, dims, BoundaryPolicy::Clip).collect();
+    assert_eq!(visible.len(), 8);
+    assert_eq!(manhattan_distance_3d((0, 0, 4), (1, 2, 0)), 7);
+}
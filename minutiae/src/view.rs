@@ -0,0 +1,255 @@
+//! Declares the view of the universe that's given to an entity's driver.  Rather than having access
+//! to the entire universe, an entity can only see the cells and entities within its view distance,
+//! which it looks up by their offset from its own position.
+//!
+//! Views also hold the messages that were sent to the entity during the last tick.
+//!
+//! In a two-dimensional universe, an entity can see everything within a square of `view_distance`
+//! cells in each direction, so a view distance of 1 includes all cells touching the entity's own.
+
+use rand_pcg::Pcg32;
+
+use cell::{Cell, CellState};
+use container::EntityHandle;
+use entity::{Entity, EntityState, MutEntityState};
+use mailbox::Envelope;
+use neighborhood::{iter_offsets, Neighborhood};
+use universe::{Universe, Universe2D};
+use util::{calc_offset_bounded, get_coords, get_index, DistanceMetric};
+
+/// A read-only view of the universe from the perspective of a single entity.  `T` is the type of
+/// the messages in the entity's inbox.
+pub struct EntityView<'a, C, E, M, U, T = ()>
+where
+    C: CellState + 'a,
+    E: EntityState<C> + 'a,
+    M: MutEntityState + 'a,
+    U: Universe<C, E, M> + 'a,
+    T: 'a,
+{
+    universe: &'a U,
+    entity: &'a Entity<C, E, M>,
+    handle: EntityHandle,
+    position: U::Coord,
+    view_distance: usize,
+    inbox: &'a [Envelope<T>],
+}
+
+impl<'a, C: CellState + 'a, E: EntityState<C> + 'a, M: MutEntityState + 'a, U, T>
+    EntityView<'a, C, E, M, U, T>
+where
+    U: Universe<C, E, M> + 'a,
+    T: 'a,
+{
+    pub fn new(
+        universe: &'a U,
+        entity: &'a Entity<C, E, M>,
+        handle: EntityHandle,
+        position: U::Coord,
+        view_distance: usize,
+    ) -> Self {
+        EntityView {
+            universe,
+            entity,
+            handle,
+            position,
+            view_distance,
+            inbox: &[],
+        }
+    }
+
+    /// Sets the messages that were delivered to the entity.
+    pub fn with_inbox(mut self, inbox: &'a [Envelope<T>]) -> Self {
+        self.inbox = inbox;
+        self
+    }
+
+    /// Returns the entity that's doing the viewing.
+    pub fn entity(&self) -> &'a Entity<C, E, M> { self.entity }
+
+    pub fn handle(&self) -> EntityHandle { self.handle }
+
+    /// Returns the coordinate of the cell that the entity occupies.
+    pub fn position(&self) -> U::Coord { self.position }
+
+    pub fn view_distance(&self) -> usize { self.view_distance }
+
+    /// Returns the messages that were sent to the entity during the last tick in the order that
+    /// they were sent.
+    pub fn inbox(&self) -> &'a [Envelope<T>] { self.inbox }
+
+    /// Returns the entity's random number generator for the current tick.
+    pub fn rng(&self) -> Pcg32 { self.universe.get_entities().entity_rng(self.entity) }
+
+    /// Returns the handles of the entities other than the viewing entity within its view distance.
+    /// This works with any universe that implements `Universe::entities_within`.
+    pub fn visible_entities(&self) -> Vec<EntityHandle> {
+        let own_handle = self.handle;
+        let mut handles = self
+            .universe
+            .entities_within(self.position, self.view_distance);
+        handles.retain(|&handle| handle != own_handle);
+        handles
+    }
+}
+
+impl<'a, C: CellState + 'a, E: EntityState<C> + 'a, M: MutEntityState + 'a, T: 'a>
+    EntityView<'a, C, E, M, Universe2D<C, E, M>, T>
+{
+    /// Returns the `(x, y)` coordinates of the cell that the entity occupies.
+    pub fn coords(&self) -> (usize, usize) { get_coords(self.position, self.universe.get_width()) }
+
+    /// Returns `true` if the supplied offset is within the entity's view distance.
+    pub fn is_visible(&self, x_offset: isize, y_offset: isize) -> bool {
+        DistanceMetric::Chebyshev.is_within((x_offset, y_offset), self.view_distance)
+    }
+
+    /// Returns the universe index of the cell at the supplied offset or `None` if it isn't
+    /// visible or is outside of the universe.  This is used to target cells with cell actions.
+    pub fn index_of(&self, x_offset: isize, y_offset: isize) -> Option<usize> {
+        if !self.is_visible(x_offset, y_offset) {
+            return None;
+        }
+
+        let universe = self.universe;
+        let (width, height, boundary) = (
+            universe.get_width(),
+            universe.get_height(),
+            universe.get_boundary(),
+        );
+        let (cur_x, cur_y) = self.coords();
+        let x = boundary.resolve_visible(cur_x as isize + x_offset, width)?;
+        let y = boundary.resolve_visible(cur_y as isize + y_offset, height)?;
+        Some(get_index(x, y, width))
+    }
+
+    /// Returns the offset of the cell with the supplied universe index from the entity, taking
+    /// wrapping into account.
+    fn offset_of(&self, universe_index: usize) -> (isize, isize) {
+        offset_between(self.universe, self.position, universe_index)
+    }
+
+    /// Returns the cell at the supplied offset or `None` if it isn't visible.
+    pub fn get_cell(&self, x_offset: isize, y_offset: isize) -> Option<&'a Cell<C>> {
+        let universe = self.universe;
+        self.index_of(x_offset, y_offset)
+            .map(|universe_index| &universe.cells[universe_index])
+    }
+
+    /// Returns the value of a field layer at the supplied offset or `None` if the cell isn't
+    /// visible or the layer doesn't exist.
+    pub fn field(&self, layer: usize, x_offset: isize, y_offset: isize) -> Option<f32> {
+        self.index_of(x_offset, y_offset)
+            .and_then(|universe_index| self.universe.fields.value(layer, universe_index))
+    }
+
+    /// Returns the handles of the entities in the cell at the supplied offset, which is empty if
+    /// the cell isn't visible.
+    pub fn entities_at(&self, x_offset: isize, y_offset: isize) -> &'a [EntityHandle] {
+        let universe = self.universe;
+        match self.index_of(x_offset, y_offset) {
+            Some(universe_index) => universe.entities.get_entities_at(universe_index),
+            None => &[],
+        }
+    }
+
+    /// Returns a visible entity along with its offset from the viewing entity.
+    pub fn get_entity(
+        &self,
+        handle: EntityHandle,
+    ) -> Option<(&'a Entity<C, E, M>, (isize, isize))> {
+        let (entity, universe_index) = self.universe.entities.get_verify(handle)?;
+        let offset = self.offset_of(universe_index);
+        if !self.is_visible(offset.0, offset.1) {
+            return None;
+        }
+
+        Some((entity, offset))
+    }
+
+    /// Visits all visible entities other than the viewing entity itself with the format
+    /// `(Entity, entity_handle, offset)`.
+    pub fn iter_entities(
+        &self,
+    ) -> impl Iterator<Item = (&'a Entity<C, E, M>, EntityHandle, (isize, isize))> + 'a {
+        let universe = self.universe;
+        let (own_handle, position) = (self.handle, self.position);
+        universe
+            .entities
+            .iter_within(
+                position,
+                self.view_distance,
+                DistanceMetric::Chebyshev,
+                universe.get_conf(),
+            )
+            .filter(move |&(_, handle, _)| handle != own_handle)
+            .map(move |(entity, handle, universe_index)| {
+                (entity, handle, offset_between(universe, position, universe_index))
+            })
+    }
+
+    /// Visits the visible cells in the supplied neighborhood of the entity along with their
+    /// offsets.
+    pub fn iter_neighborhood<'b, N: Neighborhood + ?Sized>(
+        &'b self,
+        neighborhood: &'b N,
+    ) -> impl Iterator<Item = ((isize, isize), &'a Cell<C>)> + 'b
+    where
+        'a: 'b,
+    {
+        iter_offsets(neighborhood).filter_map(move |(x_offset, y_offset)| {
+            self.get_cell(x_offset, y_offset)
+                .map(|cell| ((x_offset, y_offset), cell))
+        })
+    }
+}
+
+/// Returns the offset of `universe_index` from `origin`, taking wrapping into account.
+fn offset_between<C: CellState, E: EntityState<C>, M: MutEntityState>(
+    universe: &Universe2D<C, E, M>,
+    origin: usize,
+    universe_index: usize,
+) -> (isize, isize) {
+    let width = universe.get_width();
+    let (cur_x, cur_y) = get_coords(origin, width);
+    let (x, y) = get_coords(universe_index, width);
+    let (height, boundary) = (universe.get_height(), universe.get_boundary());
+    calc_offset_bounded(cur_x, cur_y, x, y, width, height, boundary)
+}
+
+#[test]
+fn entity_view_is_bounded() {
+    use neighborhood::VonNeumann;
+    use test_util::{new_entity, CS, ES, MES};
+    use universe::{BoundaryPolicy, Universe2DConf};
+
+    let mut universe: Universe2D<CS, ES, MES> = Universe2D::uninitialized();
+    universe.conf = Universe2DConf::square(6);
+    universe.conf.boundary = BoundaryPolicy::Wrap;
+    universe.cells = (0..36).map(|i| Cell { state: CS(i) }).collect();
+    let viewer = universe.entities.insert(new_entity(0), get_index(0, 0, 6));
+    let near = universe.entities.insert(new_entity(0), get_index(5, 1, 6));
+    let far = universe.entities.insert(new_entity(0), get_index(3, 3, 6));
+
+    let (entity, position) = universe.entities.get_verify(viewer).unwrap();
+    let view: EntityView<_, _, _, _> = EntityView::new(&universe, entity, viewer, position, 1);
+    assert_eq!(view.coords(), (0, 0));
+    assert_eq!(view.get_cell(-1, 0), Some(&Cell { state: CS(5) }));
+    assert_eq!(view.get_cell(2, 0), None);
+    assert_eq!(view.index_of(0, -1), Some(get_index(0, 5, 6)));
+    assert_eq!(view.entities_at(-1, 1), &[near]);
+
+    assert_eq!(view.get_entity(near).map(|(_, offset)| offset), Some((-1, 1)));
+    assert!(view.get_entity(far).is_none());
+    let visible: Vec<_> = view
+        .iter_entities()
+        .map(|(_, handle, offset)| (handle, offset))
+        .collect();
+    assert_eq!(visible, vec![(near, (-1, 1))]);
+
+    let cells: Vec<usize> = view
+        .iter_neighborhood(&VonNeumann(1))
+        .map(|(_, cell)| cell.state.0)
+        .collect();
+    assert_eq!(cells, vec![30, 5, 1, 6]);
+}
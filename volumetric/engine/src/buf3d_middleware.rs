@@ -4,13 +4,14 @@
 use std::f32;
 
 use minutiae::prelude::*;
+use minutiae::universe::Universe3D;
 use noise::Point3;
 
 use super::debug;
 
-pub trait BufColumn {
-    fn get_col(&self) -> &[f32];
-    fn get_col_mut(&mut self) -> &mut [f32];
+pub trait BufVoxel {
+    fn get_val(&self) -> f32;
+    fn set_val(&mut self, val: f32);
 }
 
 type RenderCb = unsafe extern fn(*const f32, usize, usize, f32, f32, f32, f32, f32, f32, f32);
@@ -27,18 +28,16 @@ pub struct Buf3dWriter {
 }
 
 impl<
-    C: CellState, E: EntityState<C>, M: MutEntityState, CA: CellAction<C>, EA: EntityAction<C, E>, U: Universe<C, E, M>, G: Engine<C, E, M, CA, EA, U>,
-// require that supplied `CellState` can be converted into a row of the buffer
-> Middleware<C, E, M, CA, EA, U, G> for Buf3dWriter where C: BufColumn {
-    fn after_render(&mut self, universe: &mut U) {
-        // populate the buffer with the values from each vector of Z values
-        for (ix, stack) in universe.get_cells()
-            .iter()
-            .map(|cell| cell.state.get_col()) // fetch the slice of Z `f32`s
-            .enumerate() {
-            for (z, val) in stack.iter().enumerate() {
-                self.buf[(ix * stack.len()) + z] = *val;
-            }
+    C: CellState, E: EntityState<C>, M: MutEntityState, CA: CellAction<C>, EA: EntityAction<C, E>, G: Engine<C, E, M, CA, EA, Universe3D<C, E, M>>,
+// require that supplied `CellState` can be converted into a value of the buffer
+> Middleware<C, E, M, CA, EA, Universe3D<C, E, M>, G> for Buf3dWriter where C: BufVoxel {
+    fn after_render(&mut self, universe: &mut Universe3D<C, E, M>) {
+        // populate the buffer with the values of each voxel.  The renderer expects columns of Z values
+        // for each (X, Y) coordinate.
+        let (width, height) = (universe.get_width(), universe.get_height());
+        for (universe_index, cell) in universe.cells.iter().enumerate() {
+            let (x, y, z) = get_coords_3d(universe_index, width, height);
+            self.buf[(((y * width) + x) * self.universe_size) + z] = cell.state.get_val();
         }
 
         // const STEPS_PER_ORBIT: usize = 128;
@@ -1,46 +1,55 @@
-//! Defines functions that define the behavior of the engine.
+//! Defines functions that define the behavior of the engine.  The built-in self actions, including `Translate3D`, are
+//! applied by minutiae's default executor; only the actions specific to this simulation are applied here.
 
-use minutiae::prelude::{Universe, OwnedAction};
+use minutiae::engine::executor::{self, ActionHandler};
+use minutiae::prelude::OwnedAction;
 
 use super::*;
 
-pub fn exec_actions(
-    universe: &mut Universe2D<CS, ES, MES>,
-    self_actions: &[OwnedAction<CS, ES, CA, EA>],
-    cell_actions: &[OwnedAction<CS, ES, CA, EA>],
-    entity_actions: &[OwnedAction<CS, ES, CA, EA>]
-) {
-    // process actions in order of cell actions, then self actions, and finally entity actions
-    for cell_action in cell_actions {
-        exec_cell_action(cell_action, universe);
-    }
+struct OurActionHandler;
 
-    for self_action in self_actions {
-        exec_self_action(self_action, universe);
+/// Resets the mutable state of an entity, if it's still alive.
+fn clear_messenger_state(universe: &mut Universe3D<CS, ES, MES>, entity_handle: EntityHandle) {
+    if let Some(entity) = universe.entities.get_mut(entity_handle) {
+        entity.mut_state = MES::default();
     }
+}
 
-    for entity_action in entity_actions {
-        exec_entity_action(entity_action, universe);
+impl ActionHandler<CS, ES, MES, CA, EA, Universe3D<CS, ES, MES>> for OurActionHandler {
+    fn exec_cell_action(
+        &mut self,
+        _: &mut Universe3D<CS, ES, MES>,
+        _: EntityHandle,
+        action: &CA,
+        _: usize
+    ) {
+        match *action {}
     }
-}
 
-pub fn exec_cell_action(
-    action: &OwnedAction<CS, ES, CA, EA>,
-    universe: &mut Universe2D<CS, ES, MES>
-) {
-    unimplemented!(); // TODO
-}
+    fn exec_custom_self_action(&mut self, universe: &mut Universe3D<CS, ES, MES>, source: EntityHandle, action: &EA) {
+        match *action {
+            EA::ClearMessengerState => clear_messenger_state(universe, source),
+        }
+    }
 
-pub fn exec_self_action(
-    action: &OwnedAction<CS, ES, CA, EA>,
-    universe: &mut Universe2D<CS, ES, MES>
-) {
-    unimplemented!(); // TODO
+    fn exec_entity_action(
+        &mut self,
+        universe: &mut Universe3D<CS, ES, MES>,
+        _: EntityHandle,
+        action: &EA,
+        target: EntityHandle
+    ) {
+        match *action {
+            EA::ClearMessengerState => clear_messenger_state(universe, target),
+        }
+    }
 }
 
-pub fn exec_entity_action(
-    action: &OwnedAction<CS, ES, CA, EA>,
-    universe: &mut Universe2D<CS, ES, MES>
+pub fn exec_actions(
+    universe: &mut Universe3D<CS, ES, MES>,
+    cell_actions: &[OwnedAction<CS, ES, CA, EA>],
+    self_actions: &[OwnedAction<CS, ES, CA, EA>],
+    entity_actions: &[OwnedAction<CS, ES, CA, EA>]
 ) {
-    unimplemented!(); // TODO
+    executor::exec_actions(&mut OurActionHandler, universe, None, cell_actions, self_actions, entity_actions);
 }
@@ -1,7 +1,6 @@
 //! Defines the behavior of the universe's entities
 
 use minutiae::prelude::*;
-use uuid::Uuid;
 
 use super::*;
 
@@ -12,7 +11,7 @@ pub fn entity_driver(
     cells: &[Cell<CS>],
     cell_action_executor: &mut FnMut(CA, usize),
     self_action_executor: &mut FnMut(SelfAction<CS, ES, EA>),
-    entity_action_executor: &mut FnMut(EA, usize, Uuid)
+    entity_action_executor: &mut FnMut(EA, EntityHandle)
 ) {
     // unimplemented!(); // TODO
 }
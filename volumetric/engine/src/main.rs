@@ -17,10 +17,11 @@ use minutiae::prelude::*;
 use minutiae::emscripten::EmscriptenDriver;
 use minutiae::engine::serial::SerialEngine;
 use minutiae::engine::iterator::SerialEntityIterator;
-use minutiae::universe::Universe2D;
+use minutiae::mailbox::Recipient;
+use minutiae::universe::{Universe3D, Universe3DConf};
+use minutiae::view::EntityView;
 #[allow(unused_imports)]
 use noise::{BasicMulti, Billow, Fbm, MultiFractal, NoiseModule, RidgedMulti, Point3, RangeFunction};
-use uuid::Uuid;
 
 extern {
     /// Invokes the external JS function to pass this buffer to WebGL and render it
@@ -67,14 +68,14 @@ const CAMERA_COORD: Point3<f32> = [1.5f32, 1.5f32, 1.5f32];
 const FOCAL_CORD: Point3<f32> = [0.0f32, 0.0f32, 0.0f32];
 const SCREEN_RATIO: f32 = 1.0f32;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct CS(Vec<f32>); // Psuedo-3d
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct CS(f32);
 
 impl CellState for CS {}
 
-impl BufColumn for CS {
-    fn get_col(&self) -> &[f32] { &self.0 } // nothing to compute since we're just storing `f32`s in the backend as well.
-    fn get_col_mut(&mut self) -> &mut [f32] { &mut self.0 }
+impl BufVoxel for CS {
+    fn get_val(&self) -> f32 { self.0 } // nothing to compute since we're just storing `f32`s in the backend as well.
+    fn set_val(&mut self, val: f32) { self.0 = val; }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -111,37 +112,34 @@ impl Drop for OurEngine {
     }
 }
 
-impl SerialEngine<CS, ES, MES, CA, EA, SerialEntityIterator<CS, ES>, Universe2D<CS, ES, MES>> for OurEngine {
-    fn iter_entities(&self, _: &Universe2D<CS, ES, MES>) -> SerialEntityIterator<CS, ES> {
+impl SerialEngine<CS, ES, MES, CA, EA, SerialEntityIterator<CS, ES>, Universe3D<CS, ES, MES>> for OurEngine {
+    fn iter_entities(&self, _: &Universe3D<CS, ES, MES>) -> SerialEntityIterator<CS, ES> {
         SerialEntityIterator::new(UNIVERSE_SIZE)
     }
 
     fn exec_actions(
-        &self, mut universe: &mut Universe2D<CS, ES, MES>, cell_actions: &[OwnedAction<CS, ES, CA, EA>],
+        &self, universe: &mut Universe3D<CS, ES, MES>, cell_actions: &[OwnedAction<CS, ES, CA, EA>],
         self_actions: &[OwnedAction<CS, ES, CA, EA>], entity_actions: &[OwnedAction<CS, ES, CA, EA>]
     ) {
-        for cell_action in cell_actions { exec_cell_action(cell_action, &mut universe); }
-        for self_action in self_actions { exec_self_action(self_action, universe); }
-        for entity_action in entity_actions { exec_entity_action(entity_action, universe); }
+        exec_actions(universe, cell_actions, self_actions, entity_actions);
     }
 
     fn drive_entity(
         &mut self,
-        universe_index: usize,
-        entity: &Entity<CS, ES, MES>,
-        universe: &Universe2D<CS, ES, MES>,
+        view: &EntityView<CS, ES, MES, Universe3D<CS, ES, MES>>,
         cell_action_executor: &mut FnMut(CA, usize),
         self_action_executor: &mut FnMut(SelfAction<CS, ES, EA>),
-        entity_action_executor: &mut FnMut(EA, usize, Uuid)
+        entity_action_executor: &mut FnMut(EA, EntityHandle),
+        message_executor: &mut FnMut(Recipient, ())
     ) {}
 }
 
 pub struct WG;
-impl Generator<CS, ES, MES> for WG {
-    fn gen(&mut self, _: &UniverseConf) -> (Vec<Cell<CS>>, Vec<Vec<Entity<CS, ES, MES>>>) {
+impl Generator<CS, ES, MES, Universe3DConf> for WG {
+    fn gen(&mut self, conf: &Universe3DConf) -> (Vec<Cell<CS>>, Vec<Vec<Entity<CS, ES, MES>>>) {
         // create a blank universe to start off with
         (
-            vec![Cell{ state: CS(vec![0.0f32; UNIVERSE_SIZE]) }; UNIVERSE_SIZE * UNIVERSE_SIZE],
+            vec![Cell{ state: CS(0.0f32) }; conf.len()],
             vec![vec![Entity::new(ES::Unit, MES::default())]]
         )
     }
@@ -183,12 +181,10 @@ impl NoiseModule<Point3<f32>> for DummyNoise {
 
 pub fn main()  {
     // set up the minutiae environment
-    let conf = UniverseConf {
-        size: UNIVERSE_SIZE as u32,
-    };
-    let universe = Universe2D::new(conf, &mut WG);
+    let conf = Universe3DConf::cube(UNIVERSE_SIZE as u32);
+    let universe = Universe3D::new(conf, &mut WG);
     let driver = EmscriptenDriver;
-    let engine: Box<SerialEngine<CS, ES, MES, CA, EA, SerialEntityIterator<CS, ES>, Universe2D<CS, ES, MES>>> = Box::new(OurEngine);
+    let engine: Box<SerialEngine<CS, ES, MES, CA, EA, SerialEntityIterator<CS, ES>, Universe3D<CS, ES, MES>>> = Box::new(OurEngine);
 
     // create a noise generator to be used to populate the buffer
     let noise_gen = BasicMulti::new()
@@ -203,7 +199,7 @@ pub fn main()  {
             needs_resize: false,
             speed: 0.00758 * 10.,
             zoom: 0.0132312 * 10.,
-        }), UNIVERSE_SIZE)),
+        }))),
         Box::new(Buf3dWriter::new(UNIVERSE_SIZE, CANVAS_SIZE, buf_render, SCREEN_RATIO, CAMERA_COORD, FOCAL_CORD)),
     ]);
 }
@@ -1,10 +1,11 @@
 //! Defines a middleware that sets the state of every cell in the universe equal the output of a noise function.
 
 use minutiae::prelude::*;
+use minutiae::universe::Universe3D;
 use noise::{NoiseModule, Point3};
 
 use super::*;
-use buf3d_middleware::BufColumn;
+use buf3d_middleware::BufVoxel;
 
 /// Configuration status and state for the entire backend.
 pub struct MasterConf {
@@ -25,27 +26,24 @@ impl Default for MasterConf {
     }
 }
 
-/// given a buffer containing all of the cells in the universe, calculates values for each of them using
-/// perlin noise and sets their states according to the result.
-fn drive_noise<C: CellState + BufColumn>(
-    cells_buf: &mut [Cell<C>], seq: usize, noise: &NoiseModule<Point3<f32>, Output=f32>,
-    universe_size: usize, zoom: f32, speed: f32
+/// given a universe, calculates values for each of its voxels using perlin noise and sets their
+/// states according to the result.
+fn drive_noise<C: CellState + BufVoxel, E: EntityState<C>, M: MutEntityState>(
+    universe: &mut Universe3D<C, E, M>, seq: usize, noise: &NoiseModule<Point3<f32>, Output=f32>,
+    zoom: f32, speed: f32
 ) {
-    for y in 0..universe_size {
-        for x in 0..universe_size {
-            let column = cells_buf[(y * universe_size) + x].state.get_col_mut();
-            for z in 0..universe_size {
-                // calculate noise value for current coordinate and sequence number
-                let val = noise.get([
-                    (x as f32 + (seq as f32 * 5.8)) * zoom,
-                    (y as f32) * zoom,
-                    (z as f32) * speed,
-                ]);
+    let (width, height) = (universe.get_width(), universe.get_height());
+    for (universe_index, cell) in universe.cells.iter_mut().enumerate() {
+        let (x, y, z) = get_coords_3d(universe_index, width, height);
+        // calculate noise value for current coordinate and sequence number
+        let val = noise.get([
+            (x as f32 + (seq as f32 * 5.8)) * zoom,
+            (y as f32) * zoom,
+            (z as f32) * speed,
+        ]);
 
-                // set the cell's state equal to that value
-                column[z] = val as f32;
-            }
-        }
+        // set the cell's state equal to that value
+        cell.state.set_val(val as f32);
     }
 }
 
@@ -66,29 +64,26 @@ fn drive_noise<C: CellState + BufColumn>(
 pub struct NoiseStepper<N: NoiseModule<Point3<f32>>> {
     conf: MasterConf,
     noise: N,
-    universe_size: usize,
     seq: usize
 }
 
 impl<
-    C: CellState + BufColumn,
+    C: CellState + BufVoxel,
     E: EntityState<C>,
     M: MutEntityState,
     CA: CellAction<C>,
     EA: EntityAction<C, E>,
-    U: Universe<C, E, M>,
-    G: Engine<C, E, M, CA, EA, U>,
+    G: Engine<C, E, M, CA, EA, Universe3D<C, E, M>>,
     N: NoiseModule<Point3<f32>, Output=f32>
-> Middleware<C, E, M, CA, EA, U, G> for NoiseStepper<N> {
-    fn after_render(&mut self, universe: &mut U) {
+> Middleware<C, E, M, CA, EA, Universe3D<C, E, M>, G> for NoiseStepper<N> {
+    fn after_render(&mut self, universe: &mut Universe3D<C, E, M>) {
         // handle any new setting changes before rendering
 
         if self.seq % ((60 * 4) + 1) == 0 || self.seq == 1 {
             drive_noise(
-                universe.get_cells_mut(),
+                universe,
                 self.seq,
                 &self.noise,
-                self.universe_size,
                 self.conf.zoom,
                 self.conf.speed
             );
@@ -99,14 +94,13 @@ impl<
 }
 
 impl<N: NoiseModule<Point3<f32>>> NoiseStepper<N> {
-    pub fn new(noise: N, conf: Option<MasterConf>, universe_size: usize) -> Self {
+    pub fn new(noise: N, conf: Option<MasterConf>) -> Self {
         NoiseStepper {
             conf: match conf {
                 Some(c) => c,
                 None => MasterConf::default(),
             },
             noise,
-            universe_size,
             seq: 0
         }
     }